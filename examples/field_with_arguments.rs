@@ -50,7 +50,6 @@ mod db_schema {
 }
 
 mod models {
-    use super::CountryUsersArgs;
     use chrono::prelude::*;
     use diesel::prelude::*;
 
@@ -65,29 +64,21 @@ mod models {
     pub struct Country {
         pub id: i32,
     }
+}
 
-    impl<'a> juniper_eager_loading::LoadFrom<Country, CountryUsersArgs<'a>> for User {
-        type Error = diesel::result::Error;
-        type Context = super::Context;
-
-        fn load(
-            countries: &[Country],
-            field_args: &CountryUsersArgs<'a>,
-            ctx: &Self::Context,
-        ) -> Result<Vec<Self>, Self::Error> {
-            use crate::db_schema::users::dsl::*;
-            use diesel::pg::expression::dsl::any;
-
-            let country_ids = countries
-                .iter()
-                .map(|country| country.id)
-                .collect::<Vec<_>>();
-
-            users
-                .filter(country_id.eq(any(country_ids)))
-                .filter(active_since.gt(&field_args.active_since()))
-                .load::<User>(&ctx.db)
-        }
+use db_schema::users;
+
+juniper_eager_loading::impl_load_from_for_diesel_pg! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        models::Country.id -> (
+            users.country_id,
+            models::User,
+            args = CountryUsersArgs<'_>,
+            filter = |args| users::active_since.gt(args.active_since()),
+        ),
     }
 }
 
@@ -111,6 +102,12 @@ pub struct Context {
     db: PgConnection,
 }
 
+impl Context {
+    fn db(&self) -> &PgConnection {
+        &self.db
+    }
+}
+
 impl juniper::Context for Context {}
 
 #[derive(Clone, EagerLoading)]
@@ -235,7 +235,7 @@ impl<'a>
             }
         }
 
-        Ok(LoadChildrenOutput::ChildAndJoinModels(
+        Ok(LoadChildrenOutput::child_and_join_models(
             child_and_join_model_pairs,
         ))
     }
@@ -160,7 +160,7 @@ impl<'a> EagerLoadChildrenOfType<'a, User, EagerLoadingContextCountryForUsers, (
         ctx: &Self::Context,
     ) -> Result<LoadChildrenOutput<models::User, ()>, Self::Error> {
         let child_models: Vec<models::User> = LoadFrom::load(&models, field_args, ctx)?;
-        Ok(LoadChildrenOutput::ChildModels(child_models))
+        Ok(LoadChildrenOutput::child_models(child_models))
     }
 
     fn is_child_of(
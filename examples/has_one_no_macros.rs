@@ -179,7 +179,7 @@ impl<'a> EagerLoadChildrenOfType<'a, Country, EagerLoadingContextUserForCountry,
 
         let child_models: Vec<models::Country> = LoadFrom::load(&ids, field_args, ctx)?;
 
-        Ok(LoadChildrenOutput::ChildModels(child_models))
+        Ok(LoadChildrenOutput::child_models(child_models))
     }
 
     fn is_child_of(
@@ -1,17 +1,29 @@
+mod enums;
 mod field_args;
 
 use field_args::{
-    EagerLoading, FieldArgs, HasMany, HasManyThrough, HasOne, OptionHasOne, RootModelField, Spanned,
+    EagerLoading, FieldArgs, HasAggregate, HasConnection, HasMany, HasManyFromIds, HasManyThrough,
+    HasOne, KeyFields, OptionHasOne, PolymorphicHasOne, PolymorphicVariant, RootModelField,
+    Spanned,
 };
 use heck::{CamelCase, SnakeCase};
 use proc_macro2::{Span, TokenStream};
 use proc_macro_error::*;
 use quote::{format_ident, quote};
 use syn::spanned::Spanned as _;
-use syn::{parse_macro_input, Fields, GenericArgument, Ident, ItemStruct, PathArguments, Type};
+use syn::{
+    parse_macro_input, Fields, GenericArgument, Ident, Item, ItemStruct, PathArguments, Type,
+};
 
 pub fn gen_tokens(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let item_struct = parse_macro_input!(tokens as ItemStruct);
+    let item_struct = match parse_macro_input!(tokens as Item) {
+        Item::Struct(item_struct) => item_struct,
+        Item::Enum(item_enum) => return enums::gen_tokens(item_enum).into(),
+        other => abort!(
+            other.span(),
+            "Only structs and enums can derive `EagerLoading`"
+        ),
+    };
 
     let ItemStruct {
         ident: struct_name,
@@ -57,29 +69,45 @@ impl DeriveData {
     fn gen_eager_load_children_of_type(&mut self) {
         let impls = self
             .struct_fields()
-            .filter_map(|field| self.gen_eager_load_children_of_type_for_field(field));
+            .flat_map(|field| self.parse_field_args(field))
+            .map(|data| self.gen_eager_load_children_of_type_for_field(&data));
 
         let code = quote! { #(#impls)* };
         self.out.extend(code);
     }
 
-    fn gen_eager_load_children_of_type_for_field(&self, field: &syn::Field) -> Option<TokenStream> {
-        let data = self.parse_field_args(field)?;
-
+    fn gen_eager_load_children_of_type_for_field(&self, data: &FieldDeriveData) -> TokenStream {
         let inner_type = &data.inner_type;
         let struct_name = self.struct_name();
-        let join_model_impl = self.join_model_impl(&data);
-        let load_children_impl = self.load_children_impl(&data);
-        let association_impl = self.association_impl(&data);
-        let is_child_of_impl = self.is_child_of_impl(&data);
-        let context = self.field_impl_context_name(&field);
+        let join_model_impl = self.join_model_impl(data);
+        let load_children_impl = self.load_children_impl(data);
+        let association_impl = self.association_impl(data);
+        let is_child_of_impl = self.is_child_of_impl(data);
+        let join_key_impl = self.join_key_impl(data);
+        let pagination_impl = self.pagination_impl(data);
+        let compare_children_impl = self.compare_children_impl(data);
+        let assign_children_impl = self.assign_children_impl(data);
+        let context = self.field_impl_context_name(data);
         let field_arguments = data.args.field_arguments();
 
+        let (async_trait_attr, eager_load_children_of_type_trait) = if self.args.asynchronous() {
+            (
+                quote! { #[juniper_eager_loading::async_support::async_trait] },
+                quote! { juniper_eager_loading::AsyncEagerLoadChildrenOfType },
+            )
+        } else {
+            (
+                quote! {},
+                quote! { juniper_eager_loading::EagerLoadChildrenOfType },
+            )
+        };
+
         let full_output = quote! {
             #[allow(missing_docs, dead_code)]
             struct #context;
 
-            impl<'a> juniper_eager_loading::EagerLoadChildrenOfType<
+            #async_trait_attr
+            impl<'a> #eager_load_children_of_type_trait<
                 'a,
                 #inner_type,
                 #context,
@@ -89,6 +117,10 @@ impl DeriveData {
 
                 #load_children_impl
                 #is_child_of_impl
+                #join_key_impl
+                #pagination_impl
+                #compare_children_impl
+                #assign_children_impl
                 #association_impl
             }
         };
@@ -98,15 +130,24 @@ impl DeriveData {
         }
 
         if data.args.skip() {
-            return Some(quote! {});
+            return quote! {};
         }
 
-        Some(full_output)
+        full_output
     }
 
-    fn parse_field_args(&self, field: &syn::Field) -> Option<FieldDeriveData> {
-        let inner_type = get_type_from_association(&field.ty)?.clone();
-        let association_type = association_type(&field.ty)?;
+    /// Parse the attributes of an association field.
+    ///
+    /// A `PolymorphicHasOne` is loaded like a `HasOne` for each of its variants, so it results in
+    /// one `FieldDeriveData` per variant.
+    fn parse_field_args(&self, field: &syn::Field) -> Vec<FieldDeriveData> {
+        let (inner_type, association_type) = match (
+            get_type_from_association(&field.ty),
+            association_type(&field.ty),
+        ) {
+            (Some(inner_type), Some(association_type)) => (inner_type.clone(), association_type),
+            _ => return Vec::new(),
+        };
         let span = field.span();
 
         let args = match association_type {
@@ -123,13 +164,26 @@ impl DeriveData {
             AssociationType::HasMany => {
                 let args = HasMany::from_attributes(&field.attrs)
                     .unwrap_or_else(|e| abort!(e.span(), "{}", e));
-                FieldArgs::HasMany(Spanned::new(span, args))
+                FieldArgs::HasMany(Spanned::new(span, Box::new(args)))
             }
             AssociationType::HasManyThrough => {
                 let args = HasManyThrough::from_attributes(&field.attrs)
                     .unwrap_or_else(|e| abort!(e.span(), "{}", e));
                 FieldArgs::HasManyThrough(Spanned::new(span, Box::new(args)))
             }
+            AssociationType::HasConnection => HasConnection::from_attributes(&field.attrs)
+                .unwrap_or_else(|e| abort!(e.span(), "{}", e))
+                .into_field_args(span),
+            AssociationType::PolymorphicHasOne => {
+                let args = PolymorphicHasOne::from_attributes(&field.attrs)
+                    .unwrap_or_else(|e| abort!(e.span(), "{}", e));
+                FieldArgs::PolymorphicHasOne(Spanned::new(span, args))
+            }
+            AssociationType::HasManyFromIds => {
+                let args = HasManyFromIds::from_attributes(&field.attrs)
+                    .unwrap_or_else(|e| abort!(e.span(), "{}", e));
+                FieldArgs::HasManyFromIds(Spanned::new(span, args))
+            }
         };
 
         let field_name = field
@@ -139,24 +193,50 @@ impl DeriveData {
             .unwrap_or_else(|| abort!(span, "Found association field without a name"));
 
         let foreign_key_field_default = match args {
-            FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) => &field_name,
+            FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) if args.inverse() => {
+                self.struct_name()
+            }
+            FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) | FieldArgs::PolymorphicHasOne(_) => {
+                &field_name
+            }
             FieldArgs::HasMany(_) | FieldArgs::HasManyThrough(_) => self.struct_name(),
+            FieldArgs::HasManyFromIds(_) => last_ident_in_type_segment(&inner_type)
+                .unwrap_or_else(|| abort!(span, "Expected the association to contain a type")),
         }
         .clone();
 
-        let data = FieldDeriveData {
+        if let FieldArgs::PolymorphicHasOne(polymorphic_has_one) = &args {
+            return polymorphic_has_one
+                .variants()
+                .into_iter()
+                .map(|variant| FieldDeriveData {
+                    field_name: field_name.clone(),
+                    inner_type: variant.ty.clone(),
+                    association_type,
+                    foreign_key_field_default: foreign_key_field_default.clone(),
+                    args: args.clone(),
+                    variant: Some(variant),
+                })
+                .collect();
+        }
+
+        vec![FieldDeriveData {
             field_name,
             inner_type,
+            association_type,
             foreign_key_field_default,
             args,
-        };
-
-        Some(data)
+            variant: None,
+        }]
     }
 
     fn join_model_impl(&self, data: &FieldDeriveData) -> TokenStream {
         match &data.args {
-            FieldArgs::HasMany(_) | FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) => {
+            FieldArgs::HasMany(_)
+            | FieldArgs::HasOne(_)
+            | FieldArgs::OptionHasOne(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => {
                 quote! { () }
             }
             FieldArgs::HasManyThrough(has_many_through) => {
@@ -170,26 +250,44 @@ impl DeriveData {
         let join_model: syn::Type;
         let foreign_key_field = &data.args.foreign_key_field(&data.foreign_key_field_default);
         let inner_type = &data.inner_type;
+        let eager_loading_trait = self.eager_loading_trait();
 
         let load_children_impl = match &data.args {
-            FieldArgs::HasOne(_) => {
+            FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) if data.args.inverse() => {
                 join_model = syn::parse_str::<syn::Type>("()").unwrap();
+                let load = self.load_from(data, quote! { &models });
+
+                quote! {
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load;
+
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_models(child_models))
+                }
+            }
+            FieldArgs::HasOne(has_one) => {
+                join_model = syn::parse_str::<syn::Type>("()").unwrap();
+                let child_primary_key_field = has_one.child_primary_key_field();
+                foreign_key_field.assert_same_len(&child_primary_key_field);
+                let load = self.load_by_ids(data, &child_primary_key_field);
+                let id = foreign_key_field.owned(quote! { model });
 
                 quote! {
                     let ids = models
                         .iter()
-                        .map(|model| model.#foreign_key_field.clone())
+                        .map(|model| #id)
                         .collect::<Vec<_>>();
                     let ids = juniper_eager_loading::unique(ids);
 
-                    let child_models: Vec<<#inner_type as juniper_eager_loading::EagerLoading>::Model> =
-                        juniper_eager_loading::LoadFrom::load(&ids, field_args, ctx)?;
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load;
 
-                    Ok(juniper_eager_loading::LoadChildrenOutput::ChildModels(child_models))
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_models(child_models))
                 }
             }
-            FieldArgs::OptionHasOne(_) => {
+            FieldArgs::OptionHasOne(option_has_one) => {
                 join_model = syn::parse_str::<syn::Type>("()").unwrap();
+                let foreign_key_field = foreign_key_field.single("`#[option_has_one]`");
+                let load = self.load_by_ids(data, &option_has_one.child_primary_key_field());
 
                 quote! {
                     let ids = models
@@ -199,10 +297,10 @@ impl DeriveData {
                         .collect::<Vec<_>>();
                     let ids = juniper_eager_loading::unique(ids);
 
-                    let child_models: Vec<<#inner_type as juniper_eager_loading::EagerLoading>::Model> =
-                        juniper_eager_loading::LoadFrom::load(&ids, field_args, ctx)?;
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load;
 
-                    Ok(juniper_eager_loading::LoadChildrenOutput::ChildModels(child_models))
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_models(child_models))
                 }
             }
             FieldArgs::HasMany(has_many) => {
@@ -219,13 +317,15 @@ impl DeriveData {
                     quote! {}
                 };
 
+                let load = self.load_from(data, quote! { &models });
+
                 quote! {
-                    let child_models: Vec<<#inner_type as juniper_eager_loading::EagerLoading>::Model> =
-                        juniper_eager_loading::LoadFrom::load(&models, field_args, ctx)?;
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load;
 
                     #filter
 
-                    Ok(juniper_eager_loading::LoadChildrenOutput::ChildModels(child_models))
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_models(child_models))
                 }
             }
             FieldArgs::HasManyThrough(has_many_through) => {
@@ -234,6 +334,10 @@ impl DeriveData {
 
                 let child_primary_key_field_on_join_model =
                     has_many_through.child_primary_key_field_on_join_model(&data.inner_type);
+                child_primary_key_field_on_join_model.assert_same_len(&child_primary_key_field);
+                let child_key = child_primary_key_field.owned(quote! { child_model });
+                let join_model_key =
+                    child_primary_key_field_on_join_model.owned(quote! { join_model });
 
                 let filter = if let Some(predicate_method) = has_many_through.predicate_method() {
                     quote! {
@@ -246,44 +350,94 @@ impl DeriveData {
                     quote! {}
                 };
 
+                let load_join_models = self.load_from(data, quote! { &models });
+                let load_child_models = self.load_from(data, quote! { &join_models });
+
                 quote! {
                     let join_models: Vec<#join_model> =
-                        juniper_eager_loading::LoadFrom::load(&models, field_args, ctx)?;
+                        #load_join_models;
 
                     #filter
 
-                    let child_models: Vec<<#inner_type as juniper_eager_loading::EagerLoading>::Model> =
-                        juniper_eager_loading::LoadFrom::load(&join_models, field_args, ctx)?;
-
-                    let mut child_and_join_model_pairs = Vec::new();
-                    for join_model in join_models {
-                        for child_model in &child_models {
-                            if join_model.#child_primary_key_field_on_join_model == child_model.#child_primary_key_field {
-                                let pair = (
-                                    std::clone::Clone::clone(child_model),
-                                    std::clone::Clone::clone(&join_model),
-                                );
-                                child_and_join_model_pairs.push(pair);
-                            }
-                        }
-                    }
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load_child_models;
+
+                    let child_and_join_model_pairs = juniper_eager_loading::pair_with_join_models(
+                        child_models,
+                        join_models,
+                        |child_model| #child_key,
+                        |join_model| #join_model_key,
+                    );
 
-                    Ok(juniper_eager_loading::LoadChildrenOutput::ChildAndJoinModels(
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_and_join_models(
                         child_and_join_model_pairs
                     ))
                 }
             }
+            FieldArgs::PolymorphicHasOne(polymorphic_has_one) => {
+                join_model = syn::parse_str::<syn::Type>("()").unwrap();
+                let type_field = polymorphic_has_one.type_field(&data.field_name);
+                let discriminator = &polymorphic_variant(data).discriminator;
+                let child_primary_key_field = polymorphic_has_one.child_primary_key_field();
+                foreign_key_field.assert_same_len(&child_primary_key_field);
+                let load = self.load_by_ids(data, &child_primary_key_field);
+                let id = foreign_key_field.owned(quote! { model });
+
+                quote! {
+                    let ids = models
+                        .iter()
+                        .filter(|model| model.#type_field == #discriminator)
+                        .map(|model| #id)
+                        .collect::<Vec<_>>();
+                    let ids = juniper_eager_loading::unique(ids);
+
+                    if ids.is_empty() {
+                        return Ok(juniper_eager_loading::LoadChildrenOutput::child_models(
+                            Vec::new(),
+                        ));
+                    }
+
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load;
+
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_models(child_models))
+                }
+            }
+            FieldArgs::HasManyFromIds(has_many_from_ids) => {
+                join_model = syn::parse_str::<syn::Type>("()").unwrap();
+                let foreign_key_field = foreign_key_field.single("`#[has_many_from_ids]`");
+                let load = self.load_by_ids(data, &has_many_from_ids.child_primary_key_field());
+
+                quote! {
+                    let ids = models
+                        .iter()
+                        .flat_map(|model| model.#foreign_key_field.iter().cloned())
+                        .collect::<Vec<_>>();
+                    let ids = juniper_eager_loading::unique(ids);
+
+                    let child_models: Vec<<#inner_type as #eager_loading_trait>::Model> =
+                        #load;
+
+                    Ok(juniper_eager_loading::LoadChildrenOutput::child_models(child_models))
+                }
+            }
+        };
+
+        let async_token = if self.args.asynchronous() {
+            quote! { async }
+        } else {
+            quote! {}
         };
 
         quote! {
             #[allow(unused_variables)]
-            fn load_children(
+            #async_token fn load_children(
                 models: &[Self::Model],
                 field_args: &Self::FieldArguments,
                 ctx: &Self::Context,
             ) -> Result<
                 juniper_eager_loading::LoadChildrenOutput<
-                    <#inner_type as juniper_eager_loading::EagerLoading>::Model,
+                    <#inner_type as #eager_loading_trait>::Model,
                     #join_model
                 >,
                 Self::Error,
@@ -293,6 +447,212 @@ impl DeriveData {
         }
     }
 
+    fn pagination_impl(&self, data: &FieldDeriveData) -> TokenStream {
+        if !data.args.paginate() {
+            return quote! {};
+        }
+
+        if !data.args.has_field_arguments() {
+            abort!(
+                data.field_name.span(),
+                "`paginate` requires `field_arguments` to be set"
+            );
+        }
+
+        quote! {
+            fn pagination(
+                field_args: &Self::FieldArguments,
+            ) -> Option<juniper_eager_loading::Pagination> {
+                Some(juniper_eager_loading::Pagination::new(
+                    field_args.first(),
+                    field_args.after(),
+                ))
+            }
+        }
+    }
+
+    fn compare_children_impl(&self, data: &FieldDeriveData) -> TokenStream {
+        let order_by = if let Some(order_by) = data.args.order_by() {
+            order_by
+        } else {
+            return quote! {};
+        };
+
+        let inner_type = &data.inner_type;
+        let join_model = self.join_model_impl(data);
+        let eager_loading_trait = self.eager_loading_trait();
+
+        let child_model_field = match &data.args {
+            FieldArgs::HasMany(has_many) => has_many.root_model_field(&data.field_name),
+            FieldArgs::HasManyThrough(has_many_through) => {
+                has_many_through.model_field(&data.inner_type)
+            }
+            FieldArgs::HasOne(_)
+            | FieldArgs::OptionHasOne(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => unreachable!(),
+        };
+
+        let compare = match order_by {
+            // `order_by = key_method`
+            syn::Expr::Path(path) if path.path.get_ident().is_some() => {
+                let key_method = path.path.get_ident().unwrap();
+                quote! {
+                    std::cmp::Ord::cmp(
+                        &a.0.#child_model_field.#key_method(),
+                        &b.0.#child_model_field.#key_method(),
+                    )
+                }
+            }
+            // `order_by = join_model.field`
+            syn::Expr::Field(field) => {
+                let has_many_through = match &data.args {
+                    FieldArgs::HasManyThrough(has_many_through) => has_many_through,
+                    _ => abort!(
+                        field.span(),
+                        "Ordering by a join model field requires `join_model`"
+                    ),
+                };
+
+                let expected = has_many_through.join_model_name();
+                match &*field.base {
+                    syn::Expr::Path(path) if path.path.is_ident(&expected) => {}
+                    base => abort!(
+                        base.span(),
+                        "Expected the name of the join model, `{}`",
+                        expected
+                    ),
+                }
+
+                let member = &field.member;
+                quote! { std::cmp::Ord::cmp(&a.1.#member, &b.1.#member) }
+            }
+            // `order_by = |a, b| ...`
+            syn::Expr::Closure(closure) => {
+                quote! {
+                    let compare: fn(
+                        &<#inner_type as #eager_loading_trait>::Model,
+                        &<#inner_type as #eager_loading_trait>::Model,
+                    ) -> std::cmp::Ordering = #closure;
+                    compare(&a.0.#child_model_field, &b.0.#child_model_field)
+                }
+            }
+            other => abort!(
+                other.span(),
+                "`order_by` must be a key method, a join model field, or a closure comparing two models"
+            ),
+        };
+
+        quote! {
+            fn compare_children(
+                a: (&#inner_type, &#join_model),
+                b: (&#inner_type, &#join_model),
+                _field_args: &Self::FieldArguments,
+                _context: &Self::Context,
+            ) -> std::cmp::Ordering {
+                #compare
+            }
+        }
+    }
+
+    fn load_from(&self, data: &FieldDeriveData, froms: TokenStream) -> TokenStream {
+        let chunk_size = data.args.chunk_size();
+
+        if self.args.asynchronous() {
+            let load_from_in_chunks = if self.args.record_loads() {
+                quote! { juniper_eager_loading::testing::async_record_load_from_in_chunks }
+            } else {
+                quote! { juniper_eager_loading::async_load_from_in_chunks }
+            };
+
+            let field_args_log = self.field_args_log(quote! { field_args });
+
+            quote! {
+                #load_from_in_chunks(
+                    #froms,
+                    field_args,
+                    ctx,
+                    #chunk_size
+                    #field_args_log
+                ).await?
+            }
+        } else {
+            let load_from_in_chunks = if self.args.record_loads() {
+                quote! { juniper_eager_loading::testing::record_load_from_in_chunks }
+            } else {
+                quote! { juniper_eager_loading::load_from_in_chunks }
+            };
+
+            let field_args_log = self.field_args_log(quote! { field_args });
+
+            quote! {
+                #load_from_in_chunks(#froms, field_args, ctx, #chunk_size #field_args_log)?
+            }
+        }
+    }
+
+    /// The field arguments as shown in the `LoadRecorder` log, with a leading comma, if
+    /// `record_loads` is set. `field_args` must be a reference to the arguments.
+    fn field_args_log(&self, field_args: TokenStream) -> TokenStream {
+        if !self.args.record_loads() {
+            return quote! {};
+        }
+
+        quote! {
+            , {
+                #[allow(unused_imports)]
+                use juniper_eager_loading::testing::{RenderDebug, RenderTypeName};
+                (&juniper_eager_loading::testing::FieldArgs(#field_args)).render()
+            }
+        }
+    }
+
+    fn load_by_ids(
+        &self,
+        data: &FieldDeriveData,
+        child_primary_key_field: &KeyFields,
+    ) -> TokenStream {
+        if !self.args.cache() {
+            return self.load_from(data, quote! { &ids });
+        }
+
+        if self.args.asynchronous() {
+            abort!(
+                data.field_name.span(),
+                "`#[eager_loading(cache)]` cannot be combined with `asynchronous`"
+            );
+        }
+
+        if data.args.has_field_arguments() {
+            abort!(
+                data.field_name.span(),
+                "Associations with `field_arguments` cannot be used with `#[eager_loading(cache)]`"
+            );
+        }
+
+        let inner_type = &data.inner_type;
+        let id = child_primary_key_field.owned(quote! { model });
+        let key = child_primary_key_field.name();
+        let chunk_size = data.args.chunk_size();
+
+        let load_cached = if self.args.record_loads() {
+            quote! { juniper_eager_loading::testing::record_load_cached }
+        } else {
+            quote! { juniper_eager_loading::load_cached }
+        };
+
+        quote! {
+            #load_cached(
+                &ids,
+                field_args,
+                ctx,
+                #chunk_size,
+                #key,
+                |model: &<#inner_type as juniper_eager_loading::EagerLoading>::Model| #id,
+            )?
+        }
+    }
+
     fn is_child_of_impl(&self, data: &FieldDeriveData) -> TokenStream {
         let root_model_field = self.root_model_field();
         let foreign_key_field = &data.args.foreign_key_field(&data.foreign_key_field_default);
@@ -301,49 +661,92 @@ impl DeriveData {
         let field_name = &data.field_name;
 
         let is_child_of_impl = match &data.args {
+            FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) if data.args.inverse() => {
+                let (node_primary_key, child_foreign_key) = self.inverse_keys(data);
+
+                quote! {
+                    #node_primary_key == #child_foreign_key
+                }
+            }
             FieldArgs::HasOne(has_one) => {
-                let child_primary_key_field = has_one.child_primary_key_field();
                 let field_root_model_field = has_one.root_model_field(field_name);
+                let foreign_key = foreign_key_field.borrowed(quote! { node.#root_model_field });
+                let child_primary_key = has_one
+                    .child_primary_key_field()
+                    .borrowed(quote! { child.#field_root_model_field });
 
                 quote! {
-                    node.#root_model_field.#foreign_key_field == child.#field_root_model_field.#child_primary_key_field
+                    #foreign_key == #child_primary_key
                 }
             }
             FieldArgs::OptionHasOne(option_has_one) => {
                 let field_root_model_field = option_has_one.root_model_field(field_name);
+                let foreign_key_field = foreign_key_field.single("`#[option_has_one]`");
                 let child_primary_key_field = option_has_one.child_primary_key_field();
+                let child_primary_key_field = child_primary_key_field.single("`#[option_has_one]`");
 
                 quote! {
                     node.#root_model_field.#foreign_key_field == Some(child.#field_root_model_field.#child_primary_key_field)
                 }
             }
             FieldArgs::HasMany(has_many) => {
-                let field_root_model_field = has_many.root_model_field(field_name);
-                let node_primary_key_field = self.primary_key_field();
+                let child_foreign_key = has_many_child_foreign_key(has_many, field_name, data);
+                let node_primary_key = self.node_primary_key(has_many, data);
 
                 if has_many.foreign_key_optional.is_some() {
                     quote! {
-                        Some(node.#root_model_field.#node_primary_key_field) ==
-                            child.#field_root_model_field.#foreign_key_field
+                        Some(#node_primary_key) == #child_foreign_key
                     }
                 } else {
                     quote! {
-                        node.#root_model_field.#node_primary_key_field ==
-                            child.#field_root_model_field.#foreign_key_field
+                        #node_primary_key == #child_foreign_key
                     }
                 }
             }
             FieldArgs::HasManyThrough(has_many_through) => {
                 join_model = has_many_through.join_model(has_many_through.span());
                 let model_field = has_many_through.model_field(&data.inner_type);
-                let child_primary_key_field_on_join_model =
-                    has_many_through.child_primary_key_field_on_join_model(&data.inner_type);
-                let child_primary_key_field = has_many_through.child_primary_key_field();
                 let node_primary_key_field = self.primary_key_field();
+                foreign_key_field.assert_same_len(&node_primary_key_field);
+                let node_primary_key =
+                    node_primary_key_field.borrowed(quote! { node.#root_model_field });
+                let foreign_key = foreign_key_field.borrowed(quote! { join_model });
+                let child_primary_key_on_join_model = has_many_through
+                    .child_primary_key_field_on_join_model(&data.inner_type)
+                    .borrowed(quote! { join_model });
+                let child_primary_key = has_many_through
+                    .child_primary_key_field()
+                    .borrowed(quote! { child.#model_field });
 
                 quote! {
-                    node.#root_model_field.#node_primary_key_field == join_model.#foreign_key_field &&
-                        join_model.#child_primary_key_field_on_join_model == child.#model_field.#child_primary_key_field
+                    #node_primary_key == #foreign_key &&
+                        #child_primary_key_on_join_model == #child_primary_key
+                }
+            }
+            FieldArgs::PolymorphicHasOne(polymorphic_has_one) => {
+                let variant = polymorphic_variant(data);
+                let type_field = polymorphic_has_one.type_field(field_name);
+                let discriminator = &variant.discriminator;
+                let field_root_model_field = variant.root_model_field();
+                let foreign_key = foreign_key_field.borrowed(quote! { node.#root_model_field });
+                let child_primary_key = polymorphic_has_one
+                    .child_primary_key_field()
+                    .borrowed(quote! { child.#field_root_model_field });
+
+                quote! {
+                    node.#root_model_field.#type_field == #discriminator &&
+                        #foreign_key == #child_primary_key
+                }
+            }
+            FieldArgs::HasManyFromIds(has_many_from_ids) => {
+                let field_root_model_field = has_many_from_ids.root_model_field(field_name);
+                let foreign_key_field = foreign_key_field.single("`#[has_many_from_ids]`");
+                let child_primary_key = has_many_from_ids
+                    .child_primary_key_field()
+                    .owned(quote! { child.#field_root_model_field });
+
+                quote! {
+                    node.#root_model_field.#foreign_key_field.contains(&#child_primary_key)
                 }
             }
         };
@@ -361,6 +764,136 @@ impl DeriveData {
         }
     }
 
+    fn join_key_impl(&self, data: &FieldDeriveData) -> TokenStream {
+        let root_model_field = self.root_model_field();
+        let foreign_key_field = &data.args.foreign_key_field(&data.foreign_key_field_default);
+        let inner_type = &data.inner_type;
+        let mut join_model = syn::parse_str::<syn::Type>("()").unwrap();
+        let field_name = &data.field_name;
+
+        let (parent_join_key, child_join_key) = match &data.args {
+            FieldArgs::HasOne(_) | FieldArgs::OptionHasOne(_) if data.args.inverse() => {
+                let (node_primary_key, child_foreign_key) = self.inverse_keys(data);
+
+                (
+                    quote! { &#node_primary_key },
+                    quote! { &#child_foreign_key },
+                )
+            }
+            FieldArgs::HasOne(has_one) => {
+                let field_root_model_field = has_one.root_model_field(field_name);
+                let foreign_key = foreign_key_field.borrowed(quote! { node.#root_model_field });
+                let child_primary_key = has_one
+                    .child_primary_key_field()
+                    .borrowed(quote! { child.#field_root_model_field });
+
+                (quote! { &#foreign_key }, quote! { &#child_primary_key })
+            }
+            FieldArgs::OptionHasOne(option_has_one) => {
+                let field_root_model_field = option_has_one.root_model_field(field_name);
+                let foreign_key_field = foreign_key_field.single("`#[option_has_one]`");
+                let child_primary_key_field = option_has_one.child_primary_key_field();
+                let child_primary_key_field = child_primary_key_field.single("`#[option_has_one]`");
+
+                (
+                    quote! { &node.#root_model_field.#foreign_key_field },
+                    quote! { &Some(&child.#field_root_model_field.#child_primary_key_field) },
+                )
+            }
+            FieldArgs::HasMany(has_many) => {
+                let child_foreign_key = has_many_child_foreign_key(has_many, field_name, data);
+                let node_primary_key = self.node_primary_key(has_many, data);
+
+                if has_many.foreign_key_optional.is_some() {
+                    (
+                        quote! { &Some(&#node_primary_key) },
+                        quote! { &#child_foreign_key },
+                    )
+                } else {
+                    (
+                        quote! { &#node_primary_key },
+                        quote! { &#child_foreign_key },
+                    )
+                }
+            }
+            FieldArgs::HasManyThrough(has_many_through) => {
+                join_model = has_many_through.join_model(has_many_through.span());
+                let node_primary_key = self
+                    .primary_key_field()
+                    .borrowed(quote! { node.#root_model_field });
+                let foreign_key = foreign_key_field.borrowed(quote! { join_model });
+
+                (quote! { &#node_primary_key }, quote! { &#foreign_key })
+            }
+            FieldArgs::PolymorphicHasOne(polymorphic_has_one) => {
+                let field_root_model_field = polymorphic_variant(data).root_model_field();
+                let foreign_key = foreign_key_field.borrowed(quote! { node.#root_model_field });
+                let child_primary_key = polymorphic_has_one
+                    .child_primary_key_field()
+                    .borrowed(quote! { child.#field_root_model_field });
+
+                (quote! { &#foreign_key }, quote! { &#child_primary_key })
+            }
+            // The children are given to the parents by `assign_children` instead.
+            FieldArgs::HasManyFromIds(_) => return quote! {},
+        };
+
+        quote! {
+            fn parent_join_key(
+                node: &Self,
+                _field_args: &Self::FieldArguments,
+                _context: &Self::Context,
+            ) -> Option<juniper_eager_loading::JoinKey> {
+                Some(juniper_eager_loading::JoinKey::new(#parent_join_key))
+            }
+
+            #[allow(unused_variables)]
+            fn child_join_key(
+                child: &#inner_type,
+                join_model: &#join_model,
+                _field_args: &Self::FieldArguments,
+                _context: &Self::Context,
+            ) -> Option<juniper_eager_loading::JoinKey> {
+                Some(juniper_eager_loading::JoinKey::new(#child_join_key))
+            }
+        }
+    }
+
+    /// `HasManyFromIds` gives each parent its children in the order of its ids, including
+    /// repeated ids, which can't be done by matching children with parents.
+    fn assign_children_impl(&self, data: &FieldDeriveData) -> TokenStream {
+        let has_many_from_ids = match &data.args {
+            FieldArgs::HasManyFromIds(has_many_from_ids) => has_many_from_ids,
+            _ => return quote! {},
+        };
+
+        let root_model_field = self.root_model_field();
+        let inner_type = &data.inner_type;
+        let field_root_model_field = has_many_from_ids.root_model_field(&data.field_name);
+        let foreign_key_field = data.args.foreign_key_field(&data.foreign_key_field_default);
+        let foreign_key_field = foreign_key_field.single("`#[has_many_from_ids]`");
+        let child_primary_key = has_many_from_ids
+            .child_primary_key_field()
+            .owned(quote! { child.#field_root_model_field });
+
+        quote! {
+            fn assign_children(
+                nodes: &mut [Self],
+                children: Vec<(#inner_type, ())>,
+                _field_args: &Self::FieldArguments,
+                _context: &Self::Context,
+            ) {
+                juniper_eager_loading::assign_children_by_ids(
+                    nodes,
+                    children,
+                    |node| &node.#root_model_field.#foreign_key_field[..],
+                    |child| #child_primary_key,
+                    Self::association,
+                );
+            }
+        }
+    }
+
     fn association_impl(&self, data: &FieldDeriveData) -> TokenStream {
         let field_name = &data.field_name;
         let inner_type = &data.inner_type;
@@ -375,25 +908,67 @@ impl DeriveData {
     }
 
     fn gen_eager_loading(&mut self) {
+        if let Some(remote) = self.args.remote() {
+            abort!(remote.span(), "`remote` is only supported on enums");
+        }
+
+        let tree_fields = self
+            .struct_fields()
+            .flat_map(|field| self.parse_field_args(field))
+            .filter(|data| data.args.tree())
+            .collect::<Vec<_>>();
+
+        for data in &tree_fields {
+            self.validate_tree_field(data);
+        }
+
+        if self.args.asynchronous() {
+            if self.args.concurrent() {
+                abort!(
+                    self.struct_name().span(),
+                    "`concurrent` cannot be combined with `asynchronous`. Async associations are always loaded concurrently"
+                );
+            }
+
+            if let Some(field) = self
+                .struct_fields()
+                .find(|field| is_aggregate_field(&field.ty))
+            {
+                abort!(
+                    field.span(),
+                    "`HasAggregate` cannot be used with `#[eager_loading(asynchronous)]`"
+                );
+            }
+
+            self.gen_async_eager_loading();
+            return;
+        }
+
         let struct_name = self.struct_name();
         let model = self.model();
         let id = self.id();
         let context = self.context();
         let error = self.error();
 
-        let field_setters = self.struct_fields().map(|field| {
-            let ident = &field.ident;
+        let field_setters = self.field_setters();
 
-            if is_association_field(&field.ty) {
-                quote! { #ident: std::default::Default::default() }
-            } else {
-                quote! { #ident: std::clone::Clone::clone(model) }
-            }
-        });
+        let eager_load_children_calls = if self.args.concurrent() {
+            self.gen_concurrent_eager_load_children_calls()
+        } else {
+            self.struct_fields()
+                .flat_map(|field| self.parse_field_args(field))
+                .map(|data| self.gen_eager_load_for_field(&data))
+                .collect()
+        };
 
-        let eager_load_children_calls = self
+        let load_aggregate_calls = self
             .struct_fields()
-            .filter_map(|field| self.gen_eager_load_for_field(field));
+            .filter_map(|field| self.gen_load_aggregate_for_field(field));
+
+        if !tree_fields.is_empty() {
+            self.gen_eager_load_tree(eager_load_children_calls, load_aggregate_calls.collect());
+            return;
+        }
 
         let code = quote! {
             impl juniper_eager_loading::EagerLoading for #struct_name {
@@ -415,7 +990,8 @@ impl DeriveData {
                 ) -> Result<Vec<Self>, Self::Error> {
                     let mut nodes = Self::from_db_models(models);
 
-                    #(#eager_load_children_calls)*
+                    #eager_load_children_calls
+                    #(#load_aggregate_calls)*
 
                     Ok(nodes)
                 }
@@ -424,30 +1000,343 @@ impl DeriveData {
         self.out.extend(code);
     }
 
-    fn gen_eager_load_for_field(&self, field: &syn::Field) -> Option<TokenStream> {
-        let inner_type = get_type_from_association(&field.ty)?;
+    /// Types with tree associations implement `EagerLoadTree` so the levels of the tree can be
+    /// eager loaded without loading the tree again.
+    fn gen_eager_load_tree(
+        &mut self,
+        eager_load_children_calls: TokenStream,
+        load_aggregate_calls: Vec<TokenStream>,
+    ) {
+        let struct_name = self.struct_name();
+        let model = self.model();
+        let id = self.id();
+        let context = self.context();
+        let error = self.error();
 
-        let data = self.parse_field_args(field)?;
-        let args = data.args;
+        let field_setters = self.field_setters();
 
-        let field_name = args
-            .graphql_field()
-            .clone()
-            .map(|ident| {
-                let ident = ident.to_string().to_snake_case();
-                Ident::new(&ident, Span::call_site())
-            })
-            .unwrap_or_else(|| {
-                field.ident.clone().unwrap_or_else(|| {
-                    abort!(field.span(), "Found association field without a name")
-                })
+        let code = quote! {
+            impl juniper_eager_loading::EagerLoading for #struct_name {
+                type Model = #model;
+                type Id = #id;
+                type Context = #context;
+                type Error = #error;
+
+                fn new_from_model(model: &Self::Model) -> Self {
+                    Self {
+                        #(#field_setters),*
+                    }
+                }
+
+                fn eager_load_each(
+                    models: &[Self::Model],
+                    ctx: &Self::Context,
+                    trail: &juniper_from_schema::QueryTrail<'_, Self, juniper_from_schema::Walked>,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    let mut nodes = Self::from_db_models(models);
+
+                    <Self as juniper_eager_loading::EagerLoadTree>::eager_load_associations(
+                        &mut nodes,
+                        models,
+                        ctx,
+                        trail,
+                        None,
+                    )?;
+
+                    Ok(nodes)
+                }
+            }
+
+            impl juniper_eager_loading::EagerLoadTree for #struct_name {
+                fn eager_load_associations(
+                    mut nodes: &mut [Self],
+                    models: &[Self::Model],
+                    ctx: &Self::Context,
+                    trail: &juniper_from_schema::QueryTrail<'_, Self, juniper_from_schema::Walked>,
+                    skip_tree: Option<&str>,
+                ) -> Result<(), Self::Error> {
+                    #eager_load_children_calls
+                    #(#load_aggregate_calls)*
+
+                    Ok(())
+                }
+            }
+        };
+        self.out.extend(code);
+    }
+
+    fn validate_tree_field(&self, data: &FieldDeriveData) {
+        let span = data.field_name.span();
+
+        if self.args.asynchronous() || self.args.concurrent() {
+            abort!(
+                span,
+                "`tree` cannot be used with `#[eager_loading(asynchronous)]` or `#[eager_loading(concurrent)]`"
+            );
+        }
+
+        if data.args.has_field_arguments() {
+            abort!(span, "`tree` cannot be combined with `field_arguments`");
+        }
+
+        if last_ident_in_type_segment(&data.inner_type) != Some(self.struct_name()) {
+            abort!(
+                span,
+                "`tree` requires the association to hold the same type as the struct, `{}`",
+                self.struct_name()
+            );
+        }
+    }
+
+    fn gen_eager_load_tree_for_field(&self, data: &FieldDeriveData) -> TokenStream {
+        let field_name = self.graphql_field_name(data);
+        let tree_name = field_name.to_string();
+        let child_trail = self.child_trail(data, &field_name);
+        let impl_context = self.field_impl_context_name(data);
+
+        let load_levels = if let Some(tree_loader) = data.args.tree_loader() {
+            let load_tree = if self.args.record_loads() {
+                quote! {
+                    juniper_eager_loading::testing::record_load_tree::<#tree_loader, Self::Model>
+                }
+            } else {
+                quote! { <#tree_loader as juniper_eager_loading::LoadTree<Self::Model>>::load }
+            };
+
+            quote! {
+                |models, depth| {
+                    let levels = #load_tree(models, depth, ctx)?;
+                    Ok(levels)
+                }
+            }
+        } else {
+            quote! {
+                |models, depth| {
+                    juniper_eager_loading::load_tree_breadth_first::<Self, #impl_context>(
+                        models,
+                        depth,
+                        ctx,
+                    )
+                }
+            }
+        };
+
+        quote! {
+            if skip_tree != Some(#tree_name) {
+                if let Some(child_trail) = #child_trail {
+                    juniper_eager_loading::eager_load_tree::<Self, #impl_context>(
+                        &mut nodes,
+                        models,
+                        ctx,
+                        child_trail,
+                        #tree_name,
+                        |trail| trail.#field_name().walk(),
+                        #load_levels,
+                    )?;
+                }
+            }
+        }
+    }
+
+    fn gen_concurrent_eager_load_children_calls(&self) -> TokenStream {
+        let mut field_args_lets = Vec::new();
+        let mut spawns = Vec::new();
+        let mut children_vars = Vec::new();
+        let mut assign_children_calls = Vec::new();
+
+        for data in self
+            .struct_fields()
+            .flat_map(|field| self.parse_field_args(field))
+        {
+            let inner_type = &data.inner_type;
+            let join_model = self.join_model_impl(&data);
+            let impl_context = self.field_impl_context_name(&data);
+            let field_name = self.graphql_field_name(&data);
+            let field_args_name = format_ident!("{}_args", field_name);
+            let field_args_var = format_ident!("{}_args", var_prefix(&data));
+            let children_var = format_ident!("{}_children", var_prefix(&data));
+            let child_trail = self.child_trail(&data, &field_name);
+
+            let eager_load_children_of_type = quote! {
+                <Self as juniper_eager_loading::EagerLoadChildrenOfType<
+                    '_,
+                    #inner_type,
+                    #impl_context,
+                    #join_model,
+                >>
+            };
+
+            field_args_lets.push(quote! {
+                let #field_args_var = trail.#field_args_name();
+            });
+
+            spawns.push(quote! {
+                let #children_var = &mut #children_var;
+                let #field_args_var = &#field_args_var;
+                scope.spawn(move |_| {
+                    if let Some(child_trail) = #child_trail {
+                        *#children_var = #eager_load_children_of_type::load_and_eager_load_children(
+                            models,
+                            ctx,
+                            &child_trail,
+                            #field_args_var,
+                        )
+                        .map(Some);
+                    }
+                });
+            });
+
+            assign_children_calls.push(quote! {
+                if let Some(children) = #children_var? {
+                    #eager_load_children_of_type::assign_children(
+                        &mut nodes,
+                        children,
+                        &#field_args_var,
+                        ctx,
+                    );
+                }
             });
+
+            children_vars.push(children_var);
+        }
+
+        if children_vars.is_empty() {
+            return quote! {};
+        }
+
+        quote! {
+            #(#field_args_lets)*
+            #(let mut #children_vars = Ok(None);)*
+
+            juniper_eager_loading::concurrent_support::scope(|scope| {
+                #(#spawns)*
+            });
+
+            #(#assign_children_calls)*
+        }
+    }
+
+    fn gen_async_eager_loading(&mut self) {
+        let struct_name = self.struct_name();
+        let model = self.model();
+        let id = self.id();
+        let context = self.context();
+        let error = self.error();
+        let field_setters = self.field_setters();
+
+        let mut load_children_futures = Vec::new();
+        let mut children_vars = Vec::new();
+        let mut assign_children_calls = Vec::new();
+
+        for data in self
+            .struct_fields()
+            .flat_map(|field| self.parse_field_args(field))
+        {
+            let inner_type = &data.inner_type;
+            let join_model = self.join_model_impl(&data);
+            let impl_context = self.field_impl_context_name(&data);
+            let field_name = self.graphql_field_name(&data);
+            let field_args_name = format_ident!("{}_args", field_name);
+            let field_args_var = format_ident!("{}_args", var_prefix(&data));
+            let children_var = format_ident!("{}_children", var_prefix(&data));
+            let child_trail = self.child_trail(&data, &field_name);
+
+            let eager_load_children_of_type = quote! {
+                <Self as juniper_eager_loading::AsyncEagerLoadChildrenOfType<
+                    '_,
+                    #inner_type,
+                    #impl_context,
+                    #join_model,
+                >>
+            };
+
+            load_children_futures.push(quote! {
+                let #field_args_var = trail.#field_args_name();
+                let #children_var = async {
+                    if let Some(child_trail) = #child_trail {
+                        let children = #eager_load_children_of_type::load_and_eager_load_children(
+                            models,
+                            ctx,
+                            &child_trail,
+                            &#field_args_var,
+                        ).await?;
+                        Ok::<_, Self::Error>(Some(children))
+                    } else {
+                        Ok(None)
+                    }
+                };
+            });
+
+            assign_children_calls.push(quote! {
+                if let Some(children) = #children_var {
+                    #eager_load_children_of_type::assign_children(
+                        &mut nodes,
+                        children,
+                        &#field_args_var,
+                        ctx,
+                    );
+                }
+            });
+
+            children_vars.push(children_var);
+        }
+
+        let join_children_futures = if children_vars.is_empty() {
+            quote! {}
+        } else {
+            quote! {
+                let (#(#children_vars,)*) =
+                    juniper_eager_loading::async_support::try_join!(#(#children_vars),*)?;
+            }
+        };
+
+        let code = quote! {
+            #[juniper_eager_loading::async_support::async_trait]
+            impl juniper_eager_loading::AsyncEagerLoading for #struct_name {
+                type Model = #model;
+                type Id = #id;
+                type Context = #context;
+                type Error = #error;
+
+                fn new_from_model(model: &Self::Model) -> Self {
+                    Self {
+                        #(#field_setters),*
+                    }
+                }
+
+                async fn eager_load_each(
+                    models: &[Self::Model],
+                    ctx: &Self::Context,
+                    trail: &juniper_from_schema::QueryTrail<'_, Self, juniper_from_schema::Walked>,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    let mut nodes = Self::from_db_models(models);
+
+                    #(#load_children_futures)*
+                    #join_children_futures
+                    #(#assign_children_calls)*
+
+                    Ok(nodes)
+                }
+            }
+        };
+        self.out.extend(code);
+    }
+
+    fn gen_eager_load_for_field(&self, data: &FieldDeriveData) -> TokenStream {
+        if data.args.tree() {
+            return self.gen_eager_load_tree_for_field(data);
+        }
+
+        let inner_type = &data.inner_type;
+
+        let field_name = self.graphql_field_name(data);
         let field_args_name = format_ident!("{}_args", field_name);
+        let child_trail = self.child_trail(data, &field_name);
 
-        let impl_context = self.field_impl_context_name(&field);
+        let impl_context = self.field_impl_context_name(data);
 
-        Some(quote! {
-            if let Some(child_trail) = trail.#field_name().walk() {
+        quote! {
+            if let Some(child_trail) = #child_trail {
                 let field_args = trail.#field_args_name();
 
                 EagerLoadChildrenOfType::<#inner_type, #impl_context, _>::eager_load_children(
@@ -458,7 +1347,133 @@ impl DeriveData {
                     &field_args,
                 )?;
             }
-        })
+        }
+    }
+
+    fn gen_load_aggregate_for_field(&self, field: &syn::Field) -> Option<TokenStream> {
+        if !is_aggregate_field(&field.ty) {
+            return None;
+        }
+
+        let args = HasAggregate::from_attributes(&field.attrs)
+            .unwrap_or_else(|e| abort!(e.span(), "{}", e));
+
+        let ident = field
+            .ident
+            .as_ref()
+            .unwrap_or_else(|| abort!(field.span(), "Found aggregate field without a name"));
+        let field_name = args
+            .graphql_field()
+            .as_ref()
+            .map(|ident| format_ident!("{}", ident.to_string().to_snake_case()))
+            .unwrap_or_else(|| ident.clone());
+        let field_args_name = format_ident!("{}_args", field_name);
+        let aggregate = args.aggregate();
+        let chunk_size = args.chunk_size();
+        let key = args
+            .key_field(self.primary_key_field())
+            .owned(quote! { model });
+
+        let load_aggregate = if self.args.record_loads() {
+            quote! { juniper_eager_loading::testing::record_load_aggregate }
+        } else {
+            quote! { juniper_eager_loading::load_aggregate }
+        };
+        let field_args_log = self.field_args_log(quote! { &field_args });
+
+        let code = quote! {
+            if trail.#field_name() {
+                let field_args = trail.#field_args_name();
+
+                #load_aggregate::<#aggregate, _, _, _>(
+                    &mut nodes,
+                    models,
+                    &field_args,
+                    ctx,
+                    #chunk_size,
+                    |model| #key,
+                    |node| &mut node.#ident
+                    #field_args_log
+                )?;
+            }
+        };
+
+        if args.print() {
+            eprintln!("{}", code);
+        }
+
+        if args.skip() {
+            return Some(quote! {});
+        }
+
+        Some(code)
+    }
+
+    /// The trail of the children, if the field is part of the query.
+    ///
+    /// The children of a connection are found at `edges { node }`. They're still loaded if only
+    /// `pageInfo` is queried, since the page info depends on them.
+    ///
+    /// The trail of a `PolymorphicHasOne` is downcasted to the type of the variant.
+    fn child_trail(&self, data: &FieldDeriveData, field_name: &Ident) -> TokenStream {
+        if let Some(variant) = &data.variant {
+            let ty = &variant.ty;
+            quote! {
+                trail.#field_name().walk().map(|union_trail| {
+                    let variant_trail: juniper_from_schema::QueryTrail<
+                        '_,
+                        #ty,
+                        juniper_from_schema::Walked,
+                    > = (&union_trail).downcast();
+                    variant_trail
+                })
+            }
+        } else if data.association_type == AssociationType::HasConnection {
+            quote! {
+                trail.#field_name().walk().map(|connection_trail| {
+                    connection_trail
+                        .edges()
+                        .node()
+                        .walk()
+                        .unwrap_or_else(juniper_eager_loading::unselected_trail)
+                })
+            }
+        } else {
+            quote! { trail.#field_name().walk() }
+        }
+    }
+
+    fn field_setters(&self) -> Vec<TokenStream> {
+        self.struct_fields()
+            .map(|field| {
+                let ident = &field.ident;
+
+                if is_association_field(&field.ty) || is_aggregate_field(&field.ty) {
+                    quote! { #ident: std::default::Default::default() }
+                } else {
+                    quote! { #ident: std::clone::Clone::clone(model) }
+                }
+            })
+            .collect()
+    }
+
+    fn graphql_field_name(&self, data: &FieldDeriveData) -> Ident {
+        data.args
+            .graphql_field()
+            .clone()
+            .map(|ident| {
+                let ident = ident.to_string().to_snake_case();
+                Ident::new(&ident, Span::call_site())
+            })
+            .unwrap_or_else(|| data.field_name.clone())
+    }
+
+    fn eager_loading_trait(&self) -> TokenStream {
+        if self.args.asynchronous() {
+            quote! { juniper_eager_loading::AsyncEagerLoading }
+        } else {
+            quote! { juniper_eager_loading::EagerLoading }
+        }
     }
 
     fn struct_name(&self) -> &syn::Ident {
@@ -466,7 +1481,7 @@ impl DeriveData {
     }
 
     fn model(&self) -> TokenStream {
-        self.args.model(&self.struct_name())
+        self.args.model(self.struct_name())
     }
 
     fn id(&self) -> TokenStream {
@@ -482,25 +1497,70 @@ impl DeriveData {
     }
 
     fn root_model_field(&self) -> TokenStream {
-        self.args.root_model_field(&self.struct_name())
+        self.args.root_model_field(self.struct_name())
     }
 
-    fn primary_key_field(&self) -> Ident {
+    fn primary_key_field(&self) -> KeyFields {
         self.args.primary_key_field()
     }
 
-    fn struct_fields(&self) -> syn::punctuated::Iter<syn::Field> {
+    /// The primary key of a node compared with the foreign keys of its `HasMany` children.
+    fn node_primary_key(&self, has_many: &HasMany, data: &FieldDeriveData) -> TokenStream {
+        let root_model_field = self.root_model_field();
+        let primary_key_field = self.primary_key_field();
+
+        if has_many.foreign_key_method().is_none() {
+            primary_key_field
+                .assert_same_len(&data.args.foreign_key_field(&data.foreign_key_field_default));
+        }
+
+        if has_many.foreign_key_optional.is_some() {
+            let primary_key_field = primary_key_field.single("`foreign_key_optional`");
+            quote! { node.#root_model_field.#primary_key_field }
+        } else {
+            primary_key_field.borrowed(quote! { node.#root_model_field })
+        }
+    }
+
+    /// The primary key of the parent and the foreign key on the child of a has one association
+    /// marked with `inverse`.
+    fn inverse_keys(&self, data: &FieldDeriveData) -> (TokenStream, TokenStream) {
+        let field_root_model_field = match &data.args {
+            FieldArgs::HasOne(has_one) => has_one.root_model_field(&data.field_name),
+            FieldArgs::OptionHasOne(option_has_one) => {
+                option_has_one.root_model_field(&data.field_name)
+            }
+            _ => unreachable!(),
+        };
+
+        let root_model_field = self.root_model_field();
+        let primary_key_field = self.primary_key_field();
+        let foreign_key_field = data.args.foreign_key_field(&data.foreign_key_field_default);
+        primary_key_field.assert_same_len(&foreign_key_field);
+
+        (
+            primary_key_field.borrowed(quote! { node.#root_model_field }),
+            foreign_key_field.borrowed(quote! { child.#field_root_model_field }),
+        )
+    }
+
+    fn struct_fields(&self) -> syn::punctuated::Iter<'_, syn::Field> {
         self.fields.iter()
     }
 
-    fn field_impl_context_name(&self, field: &syn::Field) -> Ident {
-        let camel_name = field
-            .ident
+    fn field_impl_context_name(&self, data: &FieldDeriveData) -> Ident {
+        let camel_name = data.field_name.to_string().to_camel_case();
+        let variant_name = data
+            .variant
             .as_ref()
-            .expect("field without name")
-            .to_string()
-            .to_camel_case();
-        let full_name = format!("EagerLoadingContext{}For{}", self.struct_name(), camel_name);
+            .map(|variant| variant.name.to_string())
+            .unwrap_or_default();
+        let full_name = format!(
+            "EagerLoadingContext{}For{}{}",
+            self.struct_name(),
+            camel_name,
+            variant_name
+        );
         Ident::new(&full_name, Span::call_site())
     }
 }
@@ -536,6 +1596,9 @@ enum AssociationType {
     OptionHasOne,
     HasMany,
     HasManyThrough,
+    HasConnection,
+    PolymorphicHasOne,
+    HasManyFromIds,
 }
 
 fn association_type(ty: &syn::Type) -> Option<AssociationType> {
@@ -547,6 +1610,18 @@ fn association_type(ty: &syn::Type) -> Option<AssociationType> {
         return Some(AssociationType::HasManyThrough);
     }
 
+    if *last_ident_in_type_segment(ty)? == "HasConnection" {
+        return Some(AssociationType::HasConnection);
+    }
+
+    if *last_ident_in_type_segment(ty)? == "PolymorphicHasOne" {
+        return Some(AssociationType::PolymorphicHasOne);
+    }
+
+    if *last_ident_in_type_segment(ty)? == "HasManyFromIds" {
+        return Some(AssociationType::HasManyFromIds);
+    }
+
     if *last_ident_in_type_segment(ty)? == "HasMany" {
         return Some(AssociationType::HasMany);
     }
@@ -555,6 +1630,14 @@ fn association_type(ty: &syn::Type) -> Option<AssociationType> {
         return Some(AssociationType::HasOne);
     }
 
+    if *last_ident_in_type_segment(ty)? == "SharedHasMany" {
+        return Some(AssociationType::HasMany);
+    }
+
+    if *last_ident_in_type_segment(ty)? == "SharedHasOne" {
+        return Some(AssociationType::HasOne);
+    }
+
     None
 }
 
@@ -562,6 +1645,48 @@ fn is_association_field(ty: &syn::Type) -> bool {
     association_type(ty).is_some()
 }
 
+/// The prefix of variables generated for an association, unique for each variant of a
+/// `PolymorphicHasOne`.
+fn var_prefix(data: &FieldDeriveData) -> String {
+    match &data.variant {
+        Some(variant) => format!(
+            "{}_{}",
+            data.field_name,
+            variant.name.to_string().to_snake_case()
+        ),
+        None => data.field_name.to_string(),
+    }
+}
+
+fn polymorphic_variant(data: &FieldDeriveData) -> &PolymorphicVariant {
+    data.variant
+        .as_ref()
+        .expect("polymorphic association without a variant")
+}
+
+/// The foreign key of a `HasMany` child. Children that aren't structs, such as the enums backing
+/// unions, can provide it through a method.
+fn has_many_child_foreign_key(
+    has_many: &HasMany,
+    field_name: &Ident,
+    data: &FieldDeriveData,
+) -> TokenStream {
+    if let Some(foreign_key_method) = has_many.foreign_key_method() {
+        quote! { child.#foreign_key_method() }
+    } else {
+        let field_root_model_field = has_many.root_model_field(field_name);
+        data.args
+            .foreign_key_field(&data.foreign_key_field_default)
+            .borrowed(quote! { child.#field_root_model_field })
+    }
+}
+
+fn is_aggregate_field(ty: &syn::Type) -> bool {
+    last_ident_in_type_segment(ty)
+        .map(|ident| ident == "HasAggregate" || ident == "HasCount")
+        .unwrap_or(false)
+}
+
 fn last_ident_in_type_segment(ty: &syn::Type) -> Option<&syn::Ident> {
     let type_path = if_let_or_none!(Type::Path, ty);
     let path = &type_path.path;
@@ -574,8 +1699,10 @@ fn last_ident_in_type_segment(ty: &syn::Type) -> Option<&syn::Ident> {
 struct FieldDeriveData {
     field_name: Ident,
     inner_type: syn::Type,
+    association_type: AssociationType,
     args: FieldArgs,
     foreign_key_field_default: Ident,
+    variant: Option<PolymorphicVariant>,
 }
 
 fn remove_possible_box_wrapper(ty: &Type) -> &syn::Type {
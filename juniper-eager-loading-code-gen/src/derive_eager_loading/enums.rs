@@ -0,0 +1,331 @@
+use super::field_args::EagerLoading;
+use heck::SnakeCase;
+use proc_macro2::TokenStream;
+use proc_macro_error::*;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned as _;
+use syn::{Fields, Ident, ItemEnum};
+
+/// Derive `EagerLoading` for an enum backing a GraphQL union or interface.
+///
+/// Each variant must wrap a type that implements `EagerLoading`. The models are split up by
+/// variant, each group is eager loaded with the downcasted trail, and the results are put back
+/// in the order of the models.
+pub fn gen_tokens(item_enum: ItemEnum) -> TokenStream {
+    let args = match EagerLoading::from_attributes(&item_enum.attrs) {
+        Ok(args) => args,
+        Err(err) => return err.to_compile_error(),
+    };
+
+    if args.concurrent() {
+        abort!(
+            item_enum.ident.span(),
+            "`concurrent` is not supported on enums. Each variant is loaded separately"
+        );
+    }
+
+    let enum_name = if let Some(remote) = args.remote() {
+        quote! { #remote }
+    } else {
+        let ident = &item_enum.ident;
+        quote! { #ident }
+    };
+
+    let model_name = args
+        .remote()
+        .and_then(last_ident_in_type)
+        .unwrap_or(&item_enum.ident);
+    let model = args.model(model_name);
+
+    let variants = item_enum
+        .variants
+        .iter()
+        .map(|variant| {
+            let ty = match &variant.fields {
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+                _ => abort!(
+                    variant.span(),
+                    "Each variant must wrap a single type that implements `EagerLoading`"
+                ),
+            };
+
+            Variant {
+                name: variant.ident.clone(),
+                ty: ty.clone(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let data = EnumDeriveData {
+        enum_name,
+        model,
+        args,
+        variants,
+    };
+
+    if data.args.asynchronous() {
+        data.gen_async_eager_loading()
+    } else {
+        data.gen_eager_loading()
+    }
+}
+
+struct EnumDeriveData {
+    enum_name: TokenStream,
+    model: TokenStream,
+    args: EagerLoading,
+    variants: Vec<Variant>,
+}
+
+struct Variant {
+    name: Ident,
+    ty: syn::Type,
+}
+
+impl Variant {
+    fn models_var(&self) -> Ident {
+        format_ident!("{}_models", self.name.to_string().to_snake_case())
+    }
+
+    fn nodes_var(&self) -> Ident {
+        format_ident!("{}_nodes", self.name.to_string().to_snake_case())
+    }
+
+    fn trail_var(&self) -> Ident {
+        format_ident!("{}_trail", self.name.to_string().to_snake_case())
+    }
+}
+
+impl EnumDeriveData {
+    fn gen_eager_loading(&self) -> TokenStream {
+        let enum_name = &self.enum_name;
+        let model = &self.model;
+        let id = self.args.id();
+        let context = self.args.context();
+        let error = self.args.error();
+        let new_from_model = self.new_from_model(quote! { juniper_eager_loading::EagerLoading });
+        let partition_models = self.partition_models();
+        let reassemble_nodes = self.reassemble_nodes();
+
+        let load_variants = self.variants.iter().map(|variant| {
+            let ty = &variant.ty;
+            let models_var = variant.models_var();
+            let nodes_var = variant.nodes_var();
+            let trail_var = variant.trail_var();
+
+            quote! {
+                let mut #nodes_var = if #models_var.is_empty() {
+                    Vec::new()
+                } else {
+                    let #trail_var: juniper_from_schema::QueryTrail<
+                        '_,
+                        #ty,
+                        juniper_from_schema::Walked,
+                    > = trail.downcast();
+                    <#ty as juniper_eager_loading::EagerLoading>::eager_load_each(
+                        &#models_var,
+                        ctx,
+                        &#trail_var,
+                    )?
+                }
+                .into_iter();
+            }
+        });
+
+        let code = quote! {
+            impl juniper_eager_loading::EagerLoading for #enum_name {
+                type Model = #model;
+                type Id = #id;
+                type Context = #context;
+                type Error = #error;
+
+                #new_from_model
+
+                fn eager_load_each(
+                    models: &[Self::Model],
+                    ctx: &Self::Context,
+                    trail: &juniper_from_schema::QueryTrail<'_, Self, juniper_from_schema::Walked>,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    #partition_models
+                    #(#load_variants)*
+                    #reassemble_nodes
+                }
+            }
+        };
+
+        if self.args.print() {
+            eprintln!("{}", code);
+        }
+
+        code
+    }
+
+    fn gen_async_eager_loading(&self) -> TokenStream {
+        let enum_name = &self.enum_name;
+        let model = &self.model;
+        let id = self.args.id();
+        let context = self.args.context();
+        let error = self.args.error();
+        let new_from_model =
+            self.new_from_model(quote! { juniper_eager_loading::AsyncEagerLoading });
+        let partition_models = self.partition_models();
+        let reassemble_nodes = self.reassemble_nodes();
+
+        let load_variants = self.variants.iter().map(|variant| {
+            let ty = &variant.ty;
+            let models_var = variant.models_var();
+            let nodes_var = variant.nodes_var();
+            let trail_var = variant.trail_var();
+
+            quote! {
+                let #trail_var: juniper_from_schema::QueryTrail<
+                    '_,
+                    #ty,
+                    juniper_from_schema::Walked,
+                > = trail.downcast();
+                let #nodes_var = async {
+                    if #models_var.is_empty() {
+                        Ok::<_, Self::Error>(Vec::new())
+                    } else {
+                        <#ty as juniper_eager_loading::AsyncEagerLoading>::eager_load_each(
+                            &#models_var,
+                            ctx,
+                            &#trail_var,
+                        )
+                        .await
+                    }
+                };
+            }
+        });
+
+        let nodes_vars = self
+            .variants
+            .iter()
+            .map(|variant| variant.nodes_var())
+            .collect::<Vec<_>>();
+
+        let code = quote! {
+            #[juniper_eager_loading::async_support::async_trait]
+            impl juniper_eager_loading::AsyncEagerLoading for #enum_name {
+                type Model = #model;
+                type Id = #id;
+                type Context = #context;
+                type Error = #error;
+
+                #new_from_model
+
+                async fn eager_load_each(
+                    models: &[Self::Model],
+                    ctx: &Self::Context,
+                    trail: &juniper_from_schema::QueryTrail<'_, Self, juniper_from_schema::Walked>,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    #partition_models
+                    #(#load_variants)*
+
+                    let (#(#nodes_vars,)*) =
+                        juniper_eager_loading::async_support::try_join!(#(#nodes_vars),*)?;
+                    #(let mut #nodes_vars = #nodes_vars.into_iter();)*
+
+                    #reassemble_nodes
+                }
+            }
+        };
+
+        if self.args.print() {
+            eprintln!("{}", code);
+        }
+
+        code
+    }
+
+    fn new_from_model(&self, eager_loading_trait: TokenStream) -> TokenStream {
+        let enum_name = &self.enum_name;
+        let model = &self.model;
+
+        let arms = self.variants.iter().map(|variant| {
+            let name = &variant.name;
+            let ty = &variant.ty;
+
+            quote! {
+                #model::#name(model) => {
+                    #enum_name::#name(<#ty as #eager_loading_trait>::new_from_model(model))
+                }
+            }
+        });
+
+        quote! {
+            fn new_from_model(model: &Self::Model) -> Self {
+                match model {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+
+    /// Split the models up by variant, remembering the variant of each model so the nodes can be
+    /// put back in the same order.
+    fn partition_models(&self) -> TokenStream {
+        let model = &self.model;
+
+        let models_vars = self
+            .variants
+            .iter()
+            .map(|variant| variant.models_var())
+            .collect::<Vec<_>>();
+
+        let arms = self.variants.iter().enumerate().map(|(idx, variant)| {
+            let name = &variant.name;
+            let models_var = variant.models_var();
+
+            quote! {
+                #model::#name(model) => {
+                    variant_idxs.push(#idx);
+                    #models_var.push(std::clone::Clone::clone(model));
+                }
+            }
+        });
+
+        quote! {
+            let mut variant_idxs = Vec::with_capacity(models.len());
+            #(let mut #models_vars = Vec::new();)*
+
+            for model in models {
+                match model {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+
+    fn reassemble_nodes(&self) -> TokenStream {
+        let enum_name = &self.enum_name;
+
+        let arms = self.variants.iter().enumerate().map(|(idx, variant)| {
+            let name = &variant.name;
+            let nodes_var = variant.nodes_var();
+
+            quote! {
+                #idx => #enum_name::#name(
+                    #nodes_var.next().expect("one node is loaded for each model"),
+                ),
+            }
+        });
+
+        quote! {
+            Ok(variant_idxs
+                .into_iter()
+                .map(|variant_idx| match variant_idx {
+                    #(#arms)*
+                    _ => unreachable!(),
+                })
+                .collect())
+        }
+    }
+}
+
+fn last_ident_in_type(ty: &syn::Type) -> Option<&Ident> {
+    match ty {
+        syn::Type::Path(type_path) => type_path.path.segments.last().map(|segment| &segment.ident),
+        _ => None,
+    }
+}
@@ -4,7 +4,13 @@ use proc_macro2::{Span, TokenStream};
 use proc_macro_error::*;
 use quote::{format_ident, quote};
 use std::ops::{Deref, DerefMut};
-use syn::{self, Ident};
+use syn::{
+    self,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned as _,
+    Ident, Token,
+};
 
 macro_rules! token_stream_getter {
     ( $name:ident ) => {
@@ -15,6 +21,104 @@ macro_rules! token_stream_getter {
     };
 }
 
+/// The field holding a key, or a tuple of fields for a composite key such as `(tenant_id, id)`.
+#[derive(Debug, Clone)]
+pub struct KeyFields {
+    fields: Vec<Ident>,
+    span: Span,
+}
+
+impl KeyFields {
+    pub fn new(field: Ident) -> Self {
+        let span = field.span();
+        Self {
+            fields: vec![field],
+            span,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// An owned copy of the key, such as `model.id.clone()` or
+    /// `(model.tenant_id.clone(), model.id.clone())`.
+    pub fn owned(&self, base: TokenStream) -> TokenStream {
+        match &self.fields[..] {
+            [field] => quote! { #base.#field.clone() },
+            fields => quote! { (#(#base.#fields.clone(),)*) },
+        }
+    }
+
+    /// The key for comparing and hashing, such as `model.id` or `(&model.tenant_id, &model.id)`.
+    ///
+    /// A tuple of references hashes the same as a tuple of the values so both forms can be used
+    /// for the same `JoinKey`.
+    pub fn borrowed(&self, base: TokenStream) -> TokenStream {
+        match &self.fields[..] {
+            [field] => quote! { #base.#field },
+            fields => quote! { (#(&#base.#fields,)*) },
+        }
+    }
+
+    /// The name of the key, such as `"id"` or `"(tenant_id, id)"`.
+    pub fn name(&self) -> String {
+        match &self.fields[..] {
+            [field] => field.to_string(),
+            fields => format!(
+                "({})",
+                fields
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// The field of a key that can't be composite, aborting if it is.
+    pub fn single(&self, what: &str) -> &Ident {
+        match &self.fields[..] {
+            [field] => field,
+            _ => abort!(self.span, "Composite keys are not supported with {}", what),
+        }
+    }
+
+    /// Abort if the key doesn't have as many fields as the key it is compared with.
+    pub fn assert_same_len(&self, other: &KeyFields) {
+        if self.len() != other.len() {
+            abort!(
+                self.span,
+                "Expected a key with {} field(s) to match the other side of the association, found {}",
+                other.len(),
+                self.len()
+            );
+        }
+    }
+}
+
+impl Parse for KeyFields {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
+
+        if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            let fields = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+            if fields.is_empty() {
+                return Err(syn::Error::new(span, "Expected at least one field"));
+            }
+
+            Ok(Self {
+                fields: fields.into_iter().collect(),
+                span,
+            })
+        } else {
+            input.parse().map(Self::new)
+        }
+    }
+}
+
 #[derive(Debug, FromAttributes)]
 pub struct EagerLoading {
     model: Option<syn::Type>,
@@ -23,7 +127,12 @@ pub struct EagerLoading {
     error: syn::Type,
     root_model_field: Option<syn::Ident>,
     print: Option<()>,
-    primary_key_field: Option<syn::Ident>,
+    primary_key_field: Option<KeyFields>,
+    cache: Option<()>,
+    record_loads: Option<()>,
+    asynchronous: Option<()>,
+    concurrent: Option<()>,
+    remote: Option<syn::Type>,
 }
 
 impl EagerLoading {
@@ -60,11 +169,31 @@ impl EagerLoading {
         self.print.is_some()
     }
 
-    pub fn primary_key_field(&self) -> syn::Ident {
+    pub fn cache(&self) -> bool {
+        self.cache.is_some()
+    }
+
+    pub fn record_loads(&self) -> bool {
+        self.record_loads.is_some()
+    }
+
+    pub fn asynchronous(&self) -> bool {
+        self.asynchronous.is_some()
+    }
+
+    pub fn concurrent(&self) -> bool {
+        self.concurrent.is_some()
+    }
+
+    pub fn remote(&self) -> Option<&syn::Type> {
+        self.remote.as_ref()
+    }
+
+    pub fn primary_key_field(&self) -> KeyFields {
         if let Some(id) = &self.primary_key_field {
             id.clone()
         } else {
-            format_ident!("id")
+            KeyFields::new(format_ident!("id"))
         }
     }
 }
@@ -74,21 +203,26 @@ pub struct HasOne {
     print: Option<()>,
     skip: Option<()>,
     field_arguments: Option<syn::TypePath>,
-    foreign_key_field: Option<syn::Ident>,
+    chunk_size: Option<syn::LitInt>,
+    foreign_key_field: Option<KeyFields>,
     root_model_field: Option<syn::Ident>,
     graphql_field: Option<syn::Ident>,
+    #[allow(dead_code)]
     default: Option<()>,
-    child_primary_key_field: Option<syn::Ident>,
+    child_primary_key_field: Option<KeyFields>,
+    tree: Option<()>,
+    tree_loader: Option<syn::Type>,
+    inverse: Option<()>,
 }
 
 impl HasOne {
-    pub fn child_primary_key_field(&self) -> syn::Ident {
+    pub fn child_primary_key_field(&self) -> KeyFields {
         let child_primary_key_field = &self.child_primary_key_field;
 
         if let Some(id) = child_primary_key_field {
             id.clone()
         } else {
-            format_ident!("id")
+            KeyFields::new(format_ident!("id"))
         }
     }
 }
@@ -97,22 +231,27 @@ impl HasOne {
 pub struct OptionHasOne {
     print: Option<()>,
     skip: Option<()>,
-    foreign_key_field: Option<syn::Ident>,
+    foreign_key_field: Option<KeyFields>,
     root_model_field: Option<syn::Ident>,
     graphql_field: Option<syn::Ident>,
+    #[allow(dead_code)]
     default: Option<()>,
     field_arguments: Option<syn::TypePath>,
-    child_primary_key_field: Option<syn::Ident>,
+    chunk_size: Option<syn::LitInt>,
+    child_primary_key_field: Option<KeyFields>,
+    tree: Option<()>,
+    tree_loader: Option<syn::Type>,
+    inverse: Option<()>,
 }
 
 impl OptionHasOne {
-    pub fn child_primary_key_field(&self) -> syn::Ident {
+    pub fn child_primary_key_field(&self) -> KeyFields {
         let child_primary_key_field = &self.child_primary_key_field;
 
         if let Some(id) = child_primary_key_field {
             id.clone()
         } else {
-            format_ident!("id")
+            KeyFields::new(format_ident!("id"))
         }
     }
 }
@@ -122,17 +261,27 @@ pub struct HasMany {
     print: Option<()>,
     skip: Option<()>,
     field_arguments: Option<syn::TypePath>,
-    foreign_key_field: Option<syn::Ident>,
+    chunk_size: Option<syn::LitInt>,
+    foreign_key_field: Option<KeyFields>,
     pub foreign_key_optional: Option<()>,
+    foreign_key_method: Option<syn::Ident>,
     root_model_field: Option<syn::Ident>,
     predicate_method: Option<syn::Ident>,
     graphql_field: Option<syn::Ident>,
+    paginate: Option<()>,
+    order_by: Option<syn::Expr>,
+    tree: Option<()>,
+    tree_loader: Option<syn::Type>,
 }
 
 impl HasMany {
     pub fn predicate_method(&self) -> &Option<syn::Ident> {
         &self.predicate_method
     }
+
+    pub fn foreign_key_method(&self) -> &Option<syn::Ident> {
+        &self.foreign_key_method
+    }
 }
 
 #[derive(Debug, Clone, FromAttributes)]
@@ -140,13 +289,16 @@ pub struct HasManyThrough {
     print: Option<()>,
     skip: Option<()>,
     field_arguments: Option<syn::TypePath>,
+    chunk_size: Option<syn::LitInt>,
     model_field: Option<syn::Type>,
     join_model: Option<syn::TypePath>,
-    foreign_key_field: Option<syn::Ident>,
+    foreign_key_field: Option<KeyFields>,
     predicate_method: Option<syn::Ident>,
     graphql_field: Option<syn::Ident>,
-    child_primary_key_field_on_join_model: Option<syn::Ident>,
-    child_primary_key_field: Option<syn::Ident>,
+    child_primary_key_field_on_join_model: Option<KeyFields>,
+    child_primary_key_field: Option<KeyFields>,
+    paginate: Option<()>,
+    order_by: Option<syn::Expr>,
 }
 
 impl HasManyThrough {
@@ -158,6 +310,17 @@ impl HasManyThrough {
             .unwrap_or_else(|| abort!(span, "`#[has_many_through]` missing `join_model`"))
     }
 
+    /// The join model type in snake case, e.g. `employment` for `models::Employment`.
+    pub fn join_model_name(&self) -> Ident {
+        let name = self
+            .join_model
+            .as_ref()
+            .and_then(|join_model| join_model.path.segments.last())
+            .map(|segment| segment.ident.to_string().to_snake_case())
+            .unwrap_or_default();
+        Ident::new(&name, Span::call_site())
+    }
+
     pub fn model_field(&self, inner_type: &syn::Type) -> TokenStream {
         if let Some(inner) = &self.model_field {
             quote! { #inner }
@@ -168,14 +331,14 @@ impl HasManyThrough {
         }
     }
 
-    pub fn child_primary_key_field_on_join_model(&self, inner_type: &syn::Type) -> Ident {
+    pub fn child_primary_key_field_on_join_model(&self, inner_type: &syn::Type) -> KeyFields {
         if let Some(id) = &self.child_primary_key_field_on_join_model {
             id.clone()
         } else {
-            Ident::new(
+            KeyFields::new(Ident::new(
                 &format!("{}_id", self.model_field(inner_type)),
                 Span::call_site(),
-            )
+            ))
         }
     }
 
@@ -183,21 +346,253 @@ impl HasManyThrough {
         &self.predicate_method
     }
 
-    pub fn child_primary_key_field(&self) -> syn::Ident {
+    pub fn child_primary_key_field(&self) -> KeyFields {
         if let Some(id) = &self.child_primary_key_field {
             id.clone()
         } else {
-            format_ident!("id")
+            KeyFields::new(format_ident!("id"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromAttributes)]
+pub struct HasManyFromIds {
+    print: Option<()>,
+    skip: Option<()>,
+    field_arguments: Option<syn::TypePath>,
+    chunk_size: Option<syn::LitInt>,
+    foreign_key_field: Option<KeyFields>,
+    root_model_field: Option<syn::Ident>,
+    graphql_field: Option<syn::Ident>,
+    child_primary_key_field: Option<KeyFields>,
+}
+
+impl HasManyFromIds {
+    pub fn child_primary_key_field(&self) -> KeyFields {
+        if let Some(id) = &self.child_primary_key_field {
+            id.clone()
+        } else {
+            KeyFields::new(format_ident!("id"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, FromAttributes)]
+pub struct HasConnection {
+    print: Option<()>,
+    skip: Option<()>,
+    field_arguments: Option<syn::TypePath>,
+    chunk_size: Option<syn::LitInt>,
+    foreign_key_field: Option<KeyFields>,
+    foreign_key_optional: Option<()>,
+    foreign_key_method: Option<syn::Ident>,
+    root_model_field: Option<syn::Ident>,
+    predicate_method: Option<syn::Ident>,
+    graphql_field: Option<syn::Ident>,
+    model_field: Option<syn::Type>,
+    join_model: Option<syn::TypePath>,
+    child_primary_key_field_on_join_model: Option<KeyFields>,
+    child_primary_key_field: Option<KeyFields>,
+    order_by: Option<syn::Expr>,
+}
+
+impl HasConnection {
+    /// A connection is a paginated `HasMany`, or `HasManyThrough` if `join_model` is set.
+    pub fn into_field_args(self, span: Span) -> FieldArgs {
+        if self.join_model.is_some() {
+            if self.root_model_field.is_some()
+                || self.foreign_key_optional.is_some()
+                || self.foreign_key_method.is_some()
+            {
+                abort!(
+                    span,
+                    "`root_model_field`, `foreign_key_optional`, and `foreign_key_method` cannot be combined with `join_model`"
+                );
+            }
+
+            let args = HasManyThrough {
+                print: self.print,
+                skip: self.skip,
+                field_arguments: self.field_arguments,
+                chunk_size: self.chunk_size,
+                model_field: self.model_field,
+                join_model: self.join_model,
+                foreign_key_field: self.foreign_key_field,
+                predicate_method: self.predicate_method,
+                graphql_field: self.graphql_field,
+                child_primary_key_field_on_join_model: self.child_primary_key_field_on_join_model,
+                child_primary_key_field: self.child_primary_key_field,
+                paginate: Some(()),
+                order_by: self.order_by,
+            };
+            FieldArgs::HasManyThrough(Spanned::new(span, Box::new(args)))
+        } else {
+            if self.model_field.is_some()
+                || self.child_primary_key_field_on_join_model.is_some()
+                || self.child_primary_key_field.is_some()
+            {
+                abort!(
+                    span,
+                    "`model_field`, `child_primary_key_field_on_join_model`, and `child_primary_key_field` require `join_model`"
+                );
+            }
+
+            let args = HasMany {
+                print: self.print,
+                skip: self.skip,
+                field_arguments: self.field_arguments,
+                chunk_size: self.chunk_size,
+                foreign_key_field: self.foreign_key_field,
+                foreign_key_optional: self.foreign_key_optional,
+                foreign_key_method: self.foreign_key_method,
+                root_model_field: self.root_model_field,
+                predicate_method: self.predicate_method,
+                graphql_field: self.graphql_field,
+                paginate: Some(()),
+                order_by: self.order_by,
+                tree: None,
+                tree_loader: None,
+            };
+            FieldArgs::HasMany(Spanned::new(span, Box::new(args)))
         }
     }
 }
 
+#[derive(Debug, Clone, FromAttributes)]
+pub struct PolymorphicHasOne {
+    print: Option<()>,
+    skip: Option<()>,
+    field_arguments: Option<syn::TypePath>,
+    chunk_size: Option<syn::LitInt>,
+    variants: syn::ExprArray,
+    type_field: Option<syn::Ident>,
+    foreign_key_field: Option<KeyFields>,
+    graphql_field: Option<syn::Ident>,
+    child_primary_key_field: Option<KeyFields>,
+}
+
+impl PolymorphicHasOne {
+    /// Parse `variants = [Post, Issue = "issue"]`.
+    pub fn variants(&self) -> Vec<PolymorphicVariant> {
+        self.variants
+            .elems
+            .iter()
+            .map(|elem| {
+                let (path, discriminator) = match elem {
+                    syn::Expr::Path(path) => {
+                        let name = path_ident(&path.path, elem).to_string();
+                        (&path.path, quote! { #name })
+                    }
+                    syn::Expr::Assign(assign) => match &*assign.left {
+                        syn::Expr::Path(path) => {
+                            let right = &assign.right;
+                            (&path.path, quote! { #right })
+                        }
+                        other => abort!(other.span(), "Expected the name of a GraphQL type"),
+                    },
+                    other => abort!(
+                        other.span(),
+                        "Expected `Type` or `Type = value` where `value` is the value of the type field"
+                    ),
+                };
+
+                PolymorphicVariant {
+                    name: path_ident(path, elem).clone(),
+                    ty: syn::Type::Path(syn::TypePath {
+                        qself: None,
+                        path: path.clone(),
+                    }),
+                    discriminator,
+                }
+            })
+            .collect()
+    }
+
+    pub fn type_field(&self, field_name: &Ident) -> Ident {
+        if let Some(type_field) = &self.type_field {
+            type_field.clone()
+        } else {
+            format_ident!("{}_type", field_name.to_string().to_snake_case())
+        }
+    }
+
+    pub fn child_primary_key_field(&self) -> KeyFields {
+        if let Some(id) = &self.child_primary_key_field {
+            id.clone()
+        } else {
+            KeyFields::new(format_ident!("id"))
+        }
+    }
+}
+
+fn path_ident<'a>(path: &'a syn::Path, elem: &syn::Expr) -> &'a Ident {
+    &path
+        .segments
+        .last()
+        .unwrap_or_else(|| abort!(elem.span(), "Expected the name of a GraphQL type"))
+        .ident
+}
+
+/// One of the types a `PolymorphicHasOne` can hold.
+#[derive(Debug, Clone)]
+pub struct PolymorphicVariant {
+    pub name: Ident,
+    pub ty: syn::Type,
+    pub discriminator: TokenStream,
+}
+
+impl PolymorphicVariant {
+    /// The field on the GraphQL type that holds the model, following the default of
+    /// `#[eager_loading]`.
+    pub fn root_model_field(&self) -> Ident {
+        format_ident!("{}", self.name.to_string().to_snake_case())
+    }
+}
+
+#[derive(Debug, Clone, FromAttributes)]
+pub struct HasAggregate {
+    print: Option<()>,
+    skip: Option<()>,
+    aggregate: syn::Type,
+    key_field: Option<KeyFields>,
+    graphql_field: Option<syn::Ident>,
+    chunk_size: Option<syn::LitInt>,
+}
+
+impl HasAggregate {
+    token_stream_getter!(aggregate);
+
+    pub fn skip(&self) -> bool {
+        self.skip.is_some()
+    }
+
+    pub fn print(&self) -> bool {
+        self.print.is_some()
+    }
+
+    pub fn graphql_field(&self) -> &Option<syn::Ident> {
+        &self.graphql_field
+    }
+
+    pub fn key_field(&self, primary_key_field: KeyFields) -> KeyFields {
+        self.key_field.clone().unwrap_or(primary_key_field)
+    }
+
+    /// The `chunk_size` to split the models passed to `LoadAggregate` into, as an
+    /// `Option<usize>`.
+    pub fn chunk_size(&self) -> TokenStream {
+        chunk_size_tokens(&self.chunk_size)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum FieldArgs {
     HasOne(Spanned<HasOne>),
     OptionHasOne(Spanned<OptionHasOne>),
-    HasMany(Spanned<HasMany>),
+    HasMany(Spanned<Box<HasMany>>),
     HasManyThrough(Spanned<Box<HasManyThrough>>),
+    PolymorphicHasOne(Spanned<PolymorphicHasOne>),
+    HasManyFromIds(Spanned<HasManyFromIds>),
 }
 
 impl FieldArgs {
@@ -207,6 +602,8 @@ impl FieldArgs {
             FieldArgs::OptionHasOne(inner) => inner.skip.is_some(),
             FieldArgs::HasMany(inner) => inner.skip.is_some(),
             FieldArgs::HasManyThrough(inner) => inner.skip.is_some(),
+            FieldArgs::PolymorphicHasOne(inner) => inner.skip.is_some(),
+            FieldArgs::HasManyFromIds(inner) => inner.skip.is_some(),
         }
     }
 
@@ -216,6 +613,8 @@ impl FieldArgs {
             FieldArgs::OptionHasOne(inner) => inner.print.is_some(),
             FieldArgs::HasMany(inner) => inner.print.is_some(),
             FieldArgs::HasManyThrough(inner) => inner.print.is_some(),
+            FieldArgs::PolymorphicHasOne(inner) => inner.print.is_some(),
+            FieldArgs::HasManyFromIds(inner) => inner.print.is_some(),
         }
     }
 
@@ -225,38 +624,124 @@ impl FieldArgs {
             FieldArgs::OptionHasOne(inner) => &inner.graphql_field,
             FieldArgs::HasMany(inner) => &inner.graphql_field,
             FieldArgs::HasManyThrough(inner) => &inner.graphql_field,
+            FieldArgs::PolymorphicHasOne(inner) => &inner.graphql_field,
+            FieldArgs::HasManyFromIds(inner) => &inner.graphql_field,
         }
     }
 
-    pub fn field_arguments(&self) -> syn::Type {
-        let field_arguments = match self {
+    pub fn paginate(&self) -> bool {
+        match self {
+            FieldArgs::HasOne(_)
+            | FieldArgs::OptionHasOne(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => false,
+            FieldArgs::HasMany(inner) => inner.paginate.is_some(),
+            FieldArgs::HasManyThrough(inner) => inner.paginate.is_some(),
+        }
+    }
+
+    pub fn order_by(&self) -> Option<&syn::Expr> {
+        match self {
+            FieldArgs::HasOne(_)
+            | FieldArgs::OptionHasOne(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => None,
+            FieldArgs::HasMany(inner) => inner.order_by.as_ref(),
+            FieldArgs::HasManyThrough(inner) => inner.order_by.as_ref(),
+        }
+    }
+
+    /// Is this a has one association where the foreign key lives on the child?
+    pub fn inverse(&self) -> bool {
+        match self {
+            FieldArgs::HasOne(inner) => inner.inverse.is_some(),
+            FieldArgs::OptionHasOne(inner) => inner.inverse.is_some(),
+            FieldArgs::HasMany(_)
+            | FieldArgs::HasManyThrough(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => false,
+        }
+    }
+
+    /// Is this a self-referential association that should be loaded one level of the tree at a
+    /// time?
+    pub fn tree(&self) -> bool {
+        match self {
+            FieldArgs::HasOne(inner) => inner.tree.is_some() || inner.tree_loader.is_some(),
+            FieldArgs::OptionHasOne(inner) => inner.tree.is_some() || inner.tree_loader.is_some(),
+            FieldArgs::HasMany(inner) => inner.tree.is_some() || inner.tree_loader.is_some(),
+            FieldArgs::HasManyThrough(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => false,
+        }
+    }
+
+    pub fn tree_loader(&self) -> Option<&syn::Type> {
+        match self {
+            FieldArgs::HasOne(inner) => inner.tree_loader.as_ref(),
+            FieldArgs::OptionHasOne(inner) => inner.tree_loader.as_ref(),
+            FieldArgs::HasMany(inner) => inner.tree_loader.as_ref(),
+            FieldArgs::HasManyThrough(_)
+            | FieldArgs::PolymorphicHasOne(_)
+            | FieldArgs::HasManyFromIds(_) => None,
+        }
+    }
+
+    /// The `chunk_size` to split the values passed to `LoadFrom` into, as an `Option<usize>`.
+    pub fn chunk_size(&self) -> TokenStream {
+        let chunk_size = match self {
+            FieldArgs::HasOne(inner) => &inner.chunk_size,
+            FieldArgs::OptionHasOne(inner) => &inner.chunk_size,
+            FieldArgs::HasMany(inner) => &inner.chunk_size,
+            FieldArgs::HasManyThrough(inner) => &inner.chunk_size,
+            FieldArgs::PolymorphicHasOne(inner) => &inner.chunk_size,
+            FieldArgs::HasManyFromIds(inner) => &inner.chunk_size,
+        };
+
+        chunk_size_tokens(chunk_size)
+    }
+
+    pub fn has_field_arguments(&self) -> bool {
+        self.get_field_arguments().is_some()
+    }
+
+    fn get_field_arguments(&self) -> &Option<syn::TypePath> {
+        match self {
             FieldArgs::HasOne(inner) => &inner.field_arguments,
             FieldArgs::OptionHasOne(inner) => &inner.field_arguments,
             FieldArgs::HasMany(inner) => &inner.field_arguments,
             FieldArgs::HasManyThrough(inner) => &inner.field_arguments,
-        };
+            FieldArgs::PolymorphicHasOne(inner) => &inner.field_arguments,
+            FieldArgs::HasManyFromIds(inner) => &inner.field_arguments,
+        }
+    }
 
-        if let Some(field_arguments) = field_arguments {
+    pub fn field_arguments(&self) -> syn::Type {
+        if let Some(field_arguments) = self.get_field_arguments() {
             syn::parse2(quote! { #field_arguments<'a> }).unwrap()
         } else {
             syn::parse_str("()").unwrap()
         }
     }
 
-    pub fn foreign_key_field(&self, field_name: &Ident) -> TokenStream {
+    pub fn foreign_key_field(&self, field_name: &Ident) -> KeyFields {
         let foreign_key_field = match self {
             FieldArgs::HasOne(inner) => &inner.foreign_key_field,
             FieldArgs::OptionHasOne(inner) => &inner.foreign_key_field,
             FieldArgs::HasMany(inner) => &inner.foreign_key_field,
             FieldArgs::HasManyThrough(inner) => &inner.foreign_key_field,
+            FieldArgs::PolymorphicHasOne(inner) => &inner.foreign_key_field,
+            FieldArgs::HasManyFromIds(inner) => &inner.foreign_key_field,
         };
 
         if let Some(inner) = foreign_key_field {
-            quote! { #inner }
+            inner.clone()
+        } else if let FieldArgs::HasManyFromIds(_) = self {
+            let field_name = field_name.to_string().to_snake_case();
+            KeyFields::new(format_ident!("{}_ids", field_name))
         } else {
             let field_name = field_name.to_string().to_snake_case();
-            let field_name = format_ident!("{}_id", field_name);
-            quote! { #field_name }
+            KeyFields::new(format_ident!("{}_id", field_name))
         }
     }
 }
@@ -293,6 +778,12 @@ impl RootModelField for HasMany {
     }
 }
 
+impl RootModelField for HasManyFromIds {
+    fn get_root_model_field(&self) -> &Option<Ident> {
+        &self.root_model_field
+    }
+}
+
 fn type_to_string(ty: &syn::Type) -> String {
     use quote::ToTokens;
     let mut tokenized = quote! {};
@@ -326,3 +817,18 @@ impl<T> DerefMut for Spanned<T> {
         &mut self.1
     }
 }
+
+fn chunk_size_tokens(chunk_size: &Option<syn::LitInt>) -> TokenStream {
+    match chunk_size {
+        Some(chunk_size) => {
+            let value = chunk_size
+                .base10_parse::<usize>()
+                .unwrap_or_else(|e| abort!(chunk_size.span(), "{}", e));
+            if value == 0 {
+                abort!(chunk_size.span(), "`chunk_size` must be greater than zero");
+            }
+            quote! { Some(#value) }
+        }
+        None => quote! { None },
+    }
+}
@@ -1,10 +1,10 @@
 use proc_macro2::TokenStream;
-use quote::quote;
+use quote::{format_ident, quote};
 use syn::{
     braced, parenthesized,
     parse::{Parse, ParseStream},
     punctuated::Punctuated,
-    Ident, Token, Type,
+    Expr, ExprClosure, Ident, Index, Token, Type,
 };
 
 pub fn go(input: proc_macro::TokenStream, backend: Backend) -> proc_macro::TokenStream {
@@ -22,6 +22,18 @@ pub fn go(input: proc_macro::TokenStream, backend: Backend) -> proc_macro::Token
     tokens.into()
 }
 
+/// The version of Diesel to generate code for, set with `diesel = 2` in the prelude.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DieselVersion {
+    One,
+    Two,
+}
+
+/// The number of bound parameters the chunk size leaves room for when an impl has a `filter`
+/// without `filter_params`. The filter is an arbitrary Diesel expression so the number of
+/// parameters it binds isn't known when the impl is generated.
+const FILTER_BIND_PARAMS: usize = 100;
+
 #[derive(Debug)]
 pub enum Backend {
     Pg,
@@ -29,15 +41,72 @@ pub enum Backend {
     Sqlite,
 }
 
+impl Backend {
+    fn diesel_type(&self) -> TokenStream {
+        match self {
+            Backend::Pg => quote! { diesel::pg::Pg },
+            Backend::Mysql => quote! { diesel::mysql::Mysql },
+            Backend::Sqlite => quote! { diesel::sqlite::Sqlite },
+        }
+    }
+
+    /// Whether `column = ANY(ids)` has to be written with `diesel::pg::expression::dsl::any`.
+    /// Diesel 2 generates it from `eq_any` by itself.
+    fn uses_any(&self, diesel: DieselVersion) -> bool {
+        match self {
+            Backend::Pg => diesel == DieselVersion::One,
+            Backend::Mysql | Backend::Sqlite => false,
+        }
+    }
+
+    /// The largest number of bound parameters allowed in a query.
+    fn max_bind_params(&self) -> usize {
+        match self {
+            Backend::Pg | Backend::Mysql => 65_535,
+            Backend::Sqlite => 999,
+        }
+    }
+
+    /// How many keys of `arity` columns to load per query, leaving room for `other_params`.
+    fn chunk_size(&self, arity: usize, other_params: usize) -> TokenStream {
+        let chunk_size = match self.max_bind_params().checked_sub(other_params) {
+            Some(params) if params >= arity => params / arity,
+            _ => {
+                let message = format!(
+                    "`filter_params` leaves no room for the ids. The backend allows {} bound parameters",
+                    self.max_bind_params()
+                );
+                return quote! { compile_error!(#message); };
+            }
+        };
+
+        quote! {
+            fn chunk_size() -> Option<usize> {
+                Some(#chunk_size)
+            }
+        }
+    }
+}
+
 mod kw {
     syn::custom_keyword!(error);
     syn::custom_keyword!(context);
+    syn::custom_keyword!(paginate);
+    syn::custom_keyword!(order_by);
+    syn::custom_keyword!(count);
+    syn::custom_keyword!(args);
+    syn::custom_keyword!(filter);
+    syn::custom_keyword!(filter_params);
+    syn::custom_keyword!(connection);
+    syn::custom_keyword!(diesel);
 }
 
 #[derive(Debug)]
 struct Input {
     error_ty: Type,
     context_ty: Type,
+    connection: Option<Box<ExprClosure>>,
+    diesel: DieselVersion,
     impls: Punctuated<InputImpl, Token![,]>,
 }
 
@@ -56,8 +125,20 @@ impl Parse for Input {
         prelude.parse::<Token![=]>()?;
         let context_ty = prelude.parse::<Type>()?;
 
-        if prelude.peek(Token![,]) {
+        let mut connection = None;
+        let mut diesel = DieselVersion::One;
+        while !prelude.is_empty() {
             prelude.parse::<Token![,]>()?;
+
+            if prelude.is_empty() {
+                break;
+            } else if prelude.peek(kw::connection) {
+                connection = Some(parse_connection(&prelude)?);
+            } else if prelude.peek(kw::diesel) {
+                diesel = parse_diesel_version(&prelude)?;
+            } else {
+                return Err(prelude.error("expected `connection` or `diesel`"));
+            }
         }
 
         input.parse::<Token![=>]>()?;
@@ -69,31 +150,348 @@ impl Parse for Input {
         Ok(Self {
             error_ty,
             context_ty,
+            connection,
+            diesel,
             impls,
         })
     }
 }
 
+impl Input {
+    /// Get the connection from the `connection` option of the impl, the one in the prelude, or
+    /// `ctx.db()`. The connection is passed to `.load(_)` with [`load_connection`].
+    fn let_connection(&self, options: &Options) -> TokenStream {
+        let connection =
+            inline_connection(options.connection.as_deref().or(self.connection.as_deref()));
+
+        if self.diesel == DieselVersion::Two {
+            quote! {
+                #[allow(unused_mut)]
+                let mut connection = #connection;
+            }
+        } else {
+            quote! {
+                let connection = #connection;
+            }
+        }
+    }
+}
+
+/// The connection given to `.load(_)`. Diesel 2 requires `&mut` connections, so they are usually
+/// behind a `RefCell`, `Mutex`, or pool and dereferenced here.
+fn load_connection(diesel: DieselVersion) -> TokenStream {
+    if diesel == DieselVersion::Two {
+        quote! { &mut *connection }
+    } else {
+        quote! { &*connection }
+    }
+}
+
+/// The signature of `QueryFragment::walk_ast`, which gained lifetimes in Diesel 2.
+fn walk_ast_signature(backend: &Backend, diesel: DieselVersion) -> TokenStream {
+    let backend_ty = backend.diesel_type();
+    if diesel == DieselVersion::Two {
+        quote! {
+            fn walk_ast<'b>(
+                &'b self,
+                mut out: diesel::query_builder::AstPass<'_, 'b, #backend_ty>,
+            ) -> diesel::QueryResult<()>
+        }
+    } else {
+        quote! {
+            fn walk_ast(
+                &self,
+                mut out: diesel::query_builder::AstPass<#backend_ty>,
+            ) -> diesel::QueryResult<()>
+        }
+    }
+}
+
+/// Mark an expression as not being an aggregate, so it can be used in `.filter(_)`. Diesel 2
+/// replaced `NonAggregate` with `ValidGrouping`.
+fn impl_non_aggregate(
+    diesel: DieselVersion,
+    generics: TokenStream,
+    ty: TokenStream,
+) -> TokenStream {
+    if diesel == DieselVersion::Two {
+        quote! {
+            impl<#generics, GroupBy> diesel::expression::ValidGrouping<GroupBy> for #ty {
+                type IsAggregate = diesel::expression::is_aggregate::Never;
+            }
+        }
+    } else {
+        quote! {
+            impl<#generics> diesel::expression::NonAggregate for #ty {}
+        }
+    }
+}
+
+/// Parse `diesel = 1` or `diesel = 2`. Diesel 2 requires the `diesel2` feature.
+fn parse_diesel_version(input: ParseStream) -> syn::parse::Result<DieselVersion> {
+    input.parse::<kw::diesel>()?;
+    input.parse::<Token![=]>()?;
+    let version = input.parse::<syn::LitInt>()?;
+
+    match version.base10_digits() {
+        "1" => Ok(DieselVersion::One),
+        "2" if cfg!(feature = "diesel2") => Ok(DieselVersion::Two),
+        "2" => Err(syn::Error::new_spanned(
+            version,
+            "`diesel = 2` requires the `diesel2` cargo feature",
+        )),
+        _ => Err(syn::Error::new_spanned(
+            version,
+            "`diesel` must be either `1` or `2`",
+        )),
+    }
+}
+
+/// The connection returned by a `connection` closure, or `ctx.db()` without one. Also used by
+/// `impl_load_from_for_sqlx!`.
+pub(crate) fn inline_connection(closure: Option<&ExprClosure>) -> TokenStream {
+    match closure {
+        Some(closure) => {
+            // The closure is inlined so `?` returns from `load` and converts the error.
+            let ctx = &closure.inputs[0];
+            let body = &closure.body;
+            quote! {
+                {
+                    let #ctx = ctx;
+                    #body
+                }
+            }
+        }
+        None => quote! { ctx.db() },
+    }
+}
+
+/// Parse `connection = |ctx| ...`.
+pub(crate) fn parse_connection(input: ParseStream) -> syn::parse::Result<Box<ExprClosure>> {
+    input.parse::<kw::connection>()?;
+    input.parse::<Token![=]>()?;
+    let closure = input.parse::<ExprClosure>()?;
+
+    if closure.inputs.len() != 1 {
+        return Err(syn::Error::new_spanned(
+            &closure.inputs,
+            "`connection` must be a closure taking the context, such as `|ctx| ctx.pool.get()?`",
+        ));
+    }
+
+    Ok(Box::new(closure))
+}
+
 #[derive(Debug)]
 enum InputImpl {
     HasOne(HasOne),
     HasMany(HasMany),
+    Count(Box<Count>),
 }
 
 #[derive(Debug)]
 struct HasOne {
     id_ty: Type,
     table: Ident,
+    columns: Option<Vec<Ident>>,
     self_ty: Type,
+    options: Options,
 }
 
 #[derive(Debug)]
 struct HasMany {
     join_ty: Type,
-    join_from: Ident,
+    join_from: Vec<Ident>,
     table: Ident,
-    join_to: Ident,
+    join_to: Vec<Ident>,
     self_ty: Type,
+    options: Options,
+}
+
+/// The options after the model type, such as `paginate = UserCarsArgs<'_>, order_by = name` or
+/// `args = CountryUsersArgs<'_>, filter = |args| ...`.
+#[derive(Debug, Default)]
+struct Options {
+    paginate: Option<Box<Type>>,
+    order_by: Option<Ident>,
+    args: Option<Box<Type>>,
+    filter: Option<Box<Expr>>,
+    filter_params: Option<usize>,
+    connection: Option<Box<ExprClosure>>,
+}
+
+/// The kind of impl the options are given to.
+#[derive(Debug, Clone, Copy)]
+enum OptionsFor {
+    HasOne,
+    /// A has many association with a key of this many columns.
+    HasMany(usize),
+    Count,
+}
+
+impl Options {
+    fn parse(input: ParseStream, options_for: OptionsFor) -> syn::parse::Result<Self> {
+        let mut options = Options::default();
+
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let lookahead = input.lookahead1();
+            if lookahead.peek(kw::connection) {
+                options.connection = Some(parse_connection(input)?);
+            } else if let OptionsFor::Count = options_for {
+                return Err(input.error("Only `connection` is supported for counts"));
+            } else if lookahead.peek(kw::paginate) {
+                let paginate = input.parse::<kw::paginate>()?;
+                match options_for {
+                    OptionsFor::HasOne | OptionsFor::Count => {
+                        return Err(syn::Error::new(
+                            paginate.span,
+                            "`paginate` is only supported for has many associations",
+                        ))
+                    }
+                    OptionsFor::HasMany(arity) if arity > 1 => {
+                        return Err(syn::Error::new(
+                            paginate.span,
+                            "`paginate` is not supported with composite keys",
+                        ))
+                    }
+                    OptionsFor::HasMany(_) => {}
+                }
+                input.parse::<Token![=]>()?;
+                options.paginate = Some(Box::new(input.parse::<Type>()?));
+            } else if lookahead.peek(kw::order_by) {
+                input.parse::<kw::order_by>()?;
+                input.parse::<Token![=]>()?;
+                options.order_by = Some(input.parse::<Ident>()?);
+            } else if lookahead.peek(kw::args) {
+                input.parse::<kw::args>()?;
+                input.parse::<Token![=]>()?;
+                options.args = Some(Box::new(input.parse::<Type>()?));
+            } else if lookahead.peek(kw::filter) {
+                input.parse::<kw::filter>()?;
+                input.parse::<Token![=]>()?;
+                options.filter = Some(Box::new(input.parse::<Expr>()?));
+            } else if lookahead.peek(kw::filter_params) {
+                input.parse::<kw::filter_params>()?;
+                input.parse::<Token![=]>()?;
+                options.filter_params = Some(input.parse::<syn::LitInt>()?.base10_parse()?);
+            } else {
+                return Err(lookahead.error());
+            }
+        }
+
+        if options.filter.is_some() && options.args.is_none() {
+            return Err(input.error("`filter` requires `args` to be set"));
+        }
+
+        if options.filter_params.is_some() && options.filter.is_none() {
+            return Err(input.error("`filter_params` requires `filter` to be set"));
+        }
+
+        if options.order_by.is_some() && options.paginate.is_none() {
+            return Err(input.error("`order_by` requires `paginate` to be set"));
+        }
+
+        if options.paginate.is_some() && options.args.is_some() {
+            return Err(input.error(
+                "`paginate` cannot be combined with `args` and `filter`. The arguments type is set by `paginate`",
+            ));
+        }
+
+        Ok(options)
+    }
+
+    /// The type of the field arguments `LoadFrom` is implemented for.
+    fn args_ty(&self) -> TokenStream {
+        match self.paginate.as_ref().or(self.args.as_ref()) {
+            Some(args_ty) => quote! { #args_ty },
+            None => quote! { () },
+        }
+    }
+
+    /// The name of the field arguments parameter of `LoadFrom::load`.
+    fn field_args(&self) -> Ident {
+        if self.paginate.is_some() || self.filter.is_some() {
+            format_ident!("field_args")
+        } else {
+            format_ident!("_field_args")
+        }
+    }
+
+    /// The number of bound parameters reserved for the `filter`, if there is one.
+    fn filter_bind_params(&self) -> usize {
+        if self.filter.is_some() {
+            self.filter_params.unwrap_or(FILTER_BIND_PARAMS)
+        } else {
+            0
+        }
+    }
+
+    /// The `filter` applied to the field arguments, as an extra `.filter(_)` on the query.
+    fn field_args_filter(&self) -> TokenStream {
+        match &self.filter {
+            Some(filter) => quote! {
+                .filter(juniper_eager_loading::proc_macros::field_args_filter(
+                    field_args,
+                    #filter,
+                ))
+            },
+            None => quote! {},
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Count {
+    join_ty: Type,
+    join_from: Vec<Ident>,
+    key_ty: Type,
+    table: Ident,
+    join_to: Vec<Ident>,
+    aggregate_ty: Type,
+    options: Options,
+}
+
+/// Parse a field or column, or a tuple of them for a composite key such as `(tenant_id, id)`.
+fn parse_key_fields(input: ParseStream) -> syn::parse::Result<Vec<Ident>> {
+    if input.peek(syn::token::Paren) {
+        let inside;
+        parenthesized!(inside in input);
+        let fields = Punctuated::<Ident, Token![,]>::parse_terminated(&inside)?;
+        if fields.is_empty() {
+            return Err(inside.error("Expected at least one field"));
+        }
+        Ok(fields.into_iter().collect())
+    } else {
+        Ok(vec![input.parse::<Ident>()?])
+    }
+}
+
+fn check_key_lengths(
+    input: ParseStream,
+    join_from: &[Ident],
+    join_to: &[Ident],
+) -> syn::parse::Result<()> {
+    if join_from.len() != join_to.len() {
+        return Err(input.error(format!(
+            "Expected {} column(s) to match the key of the parent, found {}",
+            join_from.len(),
+            join_to.len()
+        )));
+    }
+    Ok(())
+}
+
+/// The key of a parent model, such as `other.id` or `(other.tenant_id, other.id)`.
+fn key_of_other(join_from: &[Ident]) -> TokenStream {
+    match join_from {
+        [field] => quote! { other.#field },
+        fields => quote! { (#(other.#fields,)*) },
+    }
 }
 
 impl Parse for InputImpl {
@@ -103,7 +501,35 @@ impl Parse for InputImpl {
         if input.peek(Token![.]) {
             let join_ty = id_ty;
             input.parse::<Token![.]>()?;
-            let join_from = input.parse::<Ident>()?;
+            let join_from = parse_key_fields(input)?;
+
+            if input.peek(Token![:]) {
+                input.parse::<Token![:]>()?;
+                let key_ty = input.parse::<Type>()?;
+
+                input.parse::<Token![->]>()?;
+                input.parse::<kw::count>()?;
+
+                let inside;
+                parenthesized!(inside in input);
+                let table = inside.parse::<Ident>()?;
+                inside.parse::<Token![.]>()?;
+                let join_to = parse_key_fields(&inside)?;
+                check_key_lengths(&inside, &join_from, &join_to)?;
+                inside.parse::<Token![,]>()?;
+                let aggregate_ty = inside.parse::<Type>()?;
+                let options = Options::parse(&inside, OptionsFor::Count)?;
+
+                return Ok(InputImpl::Count(Box::new(Count {
+                    join_ty,
+                    join_from,
+                    key_ty,
+                    table,
+                    join_to,
+                    aggregate_ty,
+                    options,
+                })));
+            }
 
             input.parse::<Token![->]>()?;
 
@@ -111,9 +537,11 @@ impl Parse for InputImpl {
             parenthesized!(inside in input);
             let table = inside.parse::<Ident>()?;
             inside.parse::<Token![.]>()?;
-            let join_to = inside.parse::<Ident>()?;
+            let join_to = parse_key_fields(&inside)?;
+            check_key_lengths(&inside, &join_from, &join_to)?;
             inside.parse::<Token![,]>()?;
             let self_ty = inside.parse::<Type>()?;
+            let options = Options::parse(&inside, OptionsFor::HasMany(join_to.len()))?;
 
             Ok(InputImpl::HasMany(HasMany {
                 join_ty,
@@ -121,6 +549,7 @@ impl Parse for InputImpl {
                 table,
                 join_to,
                 self_ty,
+                options,
             }))
         } else {
             input.parse::<Token![->]>()?;
@@ -129,13 +558,34 @@ impl Parse for InputImpl {
             parenthesized!(inside in input);
 
             let table = inside.parse::<Ident>()?;
+            let columns = if inside.peek(Token![.]) {
+                inside.parse::<Token![.]>()?;
+                let columns = parse_key_fields(&inside)?;
+                let id_arity = match &id_ty {
+                    Type::Tuple(tuple) => tuple.elems.len().max(1),
+                    _ => 1,
+                };
+                if id_arity != columns.len() {
+                    return Err(inside.error(format!(
+                        "Expected {} column(s) to match the id type, found {}",
+                        id_arity,
+                        columns.len()
+                    )));
+                }
+                Some(columns)
+            } else {
+                None
+            };
             inside.parse::<Token![,]>()?;
             let self_ty = inside.parse::<Type>()?;
+            let options = Options::parse(&inside, OptionsFor::HasOne)?;
 
             Ok(InputImpl::HasOne(HasOne {
                 id_ty,
                 table,
+                columns,
                 self_ty,
+                options,
             }))
         }
     }
@@ -146,6 +596,7 @@ impl InputImpl {
         match self {
             InputImpl::HasOne(has_one) => has_one.gen_tokens(input, backend, out),
             InputImpl::HasMany(has_many) => has_many.gen_tokens(input, backend, out),
+            InputImpl::Count(count) => count.gen_tokens(input, backend, out),
         }
     }
 }
@@ -159,34 +610,67 @@ impl HasOne {
         let self_ty = &self.self_ty;
         let table = &self.table;
 
-        let filter = match backend {
-            Backend::Pg => {
+        let arity = match &self.id_ty {
+            Type::Tuple(tuple) => tuple.elems.len().max(1),
+            _ => 1,
+        };
+        let chunk_size = backend.chunk_size(arity, self.options.filter_bind_params());
+
+        let (row_value_in, filter) = match (&self.columns, &self.id_ty) {
+            (Some(columns), _) => {
+                key_filter(table, columns, &format_ident!("ids"), backend, input.diesel)
+            }
+            (None, Type::Tuple(tuple)) if tuple.elems.len() > 1 => (
+                row_value_in(table, tuple.elems.len(), backend, input.diesel),
+                quote! {
+                    RowValueIn {
+                        columns: #table::table.primary_key(),
+                        ids,
+                    }
+                },
+            ),
+            (None, _) if backend.uses_any(input.diesel) => (
+                quote! {},
                 quote! {
                     #table::table.primary_key().eq(diesel::pg::expression::dsl::any(ids))
-                }
-            }
-            Backend::Mysql | Backend::Sqlite => {
+                },
+            ),
+            (None, _) => (
+                quote! {},
                 quote! {
                     #table::table.primary_key().eq_any(ids)
-                }
-            }
+                },
+            ),
         };
 
+        let args_ty = self.options.args_ty();
+        let field_args = self.options.field_args();
+        let field_args_filter = self.options.field_args_filter();
+        let let_connection = input.let_connection(&self.options);
+        let load_connection = load_connection(input.diesel);
+
         out.extend(quote! {
-            impl juniper_eager_loading::LoadFrom<#id_ty> for #self_ty {
+            impl juniper_eager_loading::LoadFrom<#id_ty, #args_ty> for #self_ty {
                 type Error = #error_ty;
                 type Context = #context_ty;
 
                 fn load(
                     ids: &[#id_ty],
-                    _field_args: &(),
+                    #field_args: &#args_ty,
                     ctx: &Self::Context,
                 ) -> Result<Vec<Self>, Self::Error> {
+                    #row_value_in
+
+                    #let_connection
+
                     #table::table
-                    .filter(#filter)
-                        .load::<#self_ty>(ctx.db())
+                        .filter(#filter)
+                        #field_args_filter
+                        .load::<#self_ty>(#load_connection)
                         .map_err(From::from)
                 }
+
+                #chunk_size
             }
         });
     }
@@ -198,26 +682,270 @@ impl HasMany {
         let context_ty = &input.context_ty;
 
         let join_ty = &self.join_ty;
-        let join_from = &self.join_from;
+        let from_id = key_of_other(&self.join_from);
         let table = &self.table;
-        let join_to = &self.join_to;
         let self_ty = &self.self_ty;
+        let (row_value_in, filter) = key_filter(
+            table,
+            &self.join_to,
+            &format_ident!("from_ids"),
+            backend,
+            input.diesel,
+        );
+        let let_connection = input.let_connection(&self.options);
+        let load_connection = load_connection(input.diesel);
 
-        let filter = match backend {
-            Backend::Pg => {
-                quote! {
-                    #table::#join_to.eq(diesel::pg::expression::dsl::any(from_ids))
+        if let Some(args_ty) = &self.options.paginate {
+            let row_number_window = self.row_number_window(backend, input.diesel);
+            // The limit of the window is bound as well.
+            let chunk_size = backend.chunk_size(self.join_to.len(), 1);
+            let order = match &self.options.order_by {
+                Some(column) => quote! {
+                    .order(#table::#column)
+                    .then_order_by(#table::table.primary_key())
+                },
+                None => quote! { .order(#table::table.primary_key()) },
+            };
+
+            out.extend(quote! {
+                impl juniper_eager_loading::LoadFrom<#join_ty, #args_ty> for #self_ty {
+                    type Error = #error_ty;
+                    type Context = #context_ty;
+
+                    fn load(
+                        froms: &[#join_ty],
+                        field_args: &#args_ty,
+                        ctx: &Self::Context,
+                    ) -> Result<Vec<Self>, Self::Error> {
+                        let from_ids = froms
+                            .iter()
+                            .map(|other| #from_id)
+                            .collect::<Vec<_>>();
+
+                        #let_connection
+
+                        let pagination = juniper_eager_loading::Pagination::new(
+                            field_args.first(),
+                            field_args.after(),
+                        );
+
+                        if let Some(limit) = pagination.limit() {
+                            #row_number_window
+
+                            #table::table
+                                .filter(RowNumberWindow {
+                                    ids: &from_ids,
+                                    limit: limit as i64,
+                                    primary_key: #table::table.primary_key(),
+                                    from_clause: diesel::QuerySource::from_clause(&#table::table),
+                                })
+                                #order
+                                .load(#load_connection)
+                                .map_err(From::from)
+                        } else {
+                            #table::table
+                                .filter(#filter)
+                                #order
+                                .load(#load_connection)
+                                .map_err(From::from)
+                        }
+                    }
+
+                    #chunk_size
                 }
+            });
+
+            return;
+        }
+
+        let chunk_size = backend.chunk_size(self.join_to.len(), self.options.filter_bind_params());
+        let args_ty = self.options.args_ty();
+        let field_args = self.options.field_args();
+        let field_args_filter = self.options.field_args_filter();
+
+        out.extend(quote! {
+            impl juniper_eager_loading::LoadFrom<#join_ty, #args_ty> for #self_ty {
+                type Error = #error_ty;
+                type Context = #context_ty;
+
+                fn load(
+                    froms: &[#join_ty],
+                    #field_args: &#args_ty,
+                    ctx: &Self::Context,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    let from_ids = froms
+                        .iter()
+                        .map(|other| #from_id)
+                        .collect::<Vec<_>>();
+
+                    #row_value_in
+
+                    #let_connection
+
+                    #table::table
+                        .filter(#filter)
+                        #field_args_filter
+                        .load(#load_connection)
+                        .map_err(From::from)
+                }
+
+                #chunk_size
             }
-            Backend::Mysql | Backend::Sqlite => {
-                quote! {
-                    #table::#join_to.eq_any(from_ids)
+        })
+    }
+
+    /// A filter that only keeps the first `limit` rows for each value of the `join_to` column,
+    /// ordered by the `order_by` column, if there is one, and then by primary key.
+    ///
+    /// Diesel doesn't support window functions so the SQL is written by hand:
+    ///
+    /// ```sql
+    /// pk IN (
+    ///     SELECT id FROM (
+    ///         SELECT pk AS id, ROW_NUMBER() OVER (PARTITION BY join_to ORDER BY order_by, pk) AS row_number
+    ///         FROM table
+    ///         WHERE join_to IN (ids)
+    ///     ) AS numbered
+    ///     WHERE row_number <= limit
+    /// )
+    /// ```
+    fn row_number_window(&self, backend: &Backend, diesel: DieselVersion) -> TokenStream {
+        let table = &self.table;
+        let join_to = &self.join_to[0];
+        let backend_ty = backend.diesel_type();
+        let walk_ast = walk_ast_signature(backend, diesel);
+        let non_aggregate =
+            impl_non_aggregate(diesel, quote! { 'a, T }, quote! { RowNumberWindow<'a, T> });
+
+        let bind_ids = match backend {
+            Backend::Pg => quote! {
+                out.push_sql(" = ANY(");
+                out.push_bind_param::<diesel::sql_types::Array<JoinToSqlType>, _>(self.ids)?;
+                out.push_sql(")");
+            },
+            Backend::Mysql | Backend::Sqlite => quote! {
+                out.push_sql(" IN (");
+                for (idx, id) in self.ids.iter().enumerate() {
+                    if idx != 0 {
+                        out.push_sql(", ");
+                    }
+                    out.push_bind_param::<JoinToSqlType, _>(id)?;
                 }
+                out.push_sql(")");
+            },
+        };
+
+        let order_by = self.options.order_by.as_ref().map(|column| {
+            quote! {
+                QueryFragment::walk_ast(&#table::#column, out.reborrow())?;
+                out.push_sql(", ");
             }
+        });
+
+        let empty_ids = match backend {
+            Backend::Pg => quote! {},
+            Backend::Mysql | Backend::Sqlite => quote! {
+                // `IN ()` isn't valid SQL.
+                if self.ids.is_empty() {
+                    out.push_sql("1 = 0");
+                    return Ok(());
+                }
+            },
         };
 
+        let ids_bound = match backend {
+            Backend::Pg => {
+                quote! { [T]: diesel::serialize::ToSql<diesel::sql_types::Array<JoinToSqlType>, #backend_ty> }
+            }
+            Backend::Mysql | Backend::Sqlite => {
+                quote! { T: diesel::serialize::ToSql<JoinToSqlType, #backend_ty> }
+            }
+        };
+
+        quote! {
+            type JoinToSqlType = <#table::#join_to as diesel::Expression>::SqlType;
+
+            struct RowNumberWindow<'a, T> {
+                ids: &'a [T],
+                limit: i64,
+                primary_key: <#table::table as diesel::Table>::PrimaryKey,
+                from_clause: <#table::table as diesel::QuerySource>::FromClause,
+            }
+
+            impl<'a, T> diesel::Expression for RowNumberWindow<'a, T> {
+                type SqlType = diesel::sql_types::Bool;
+            }
+
+            #non_aggregate
+
+            impl<'a, T> diesel::AppearsOnTable<#table::table> for RowNumberWindow<'a, T> {}
+
+            impl<'a, T> diesel::query_builder::QueryId for RowNumberWindow<'a, T> {
+                type QueryId = ();
+                const HAS_STATIC_QUERY_ID: bool = false;
+            }
+
+            impl<'a, T> diesel::query_builder::QueryFragment<#backend_ty> for RowNumberWindow<'a, T>
+            where
+                #ids_bound,
+            {
+                #walk_ast {
+                    use diesel::query_builder::QueryFragment;
+
+                    #empty_ids
+
+                    // Diesel 2 requires the fragments to live as long as `self`, so they are
+                    // stored on it or borrowed as constants.
+                    self.primary_key.walk_ast(out.reborrow())?;
+                    out.push_sql(" IN (SELECT juniper_eager_loading_id FROM (SELECT ");
+                    self.primary_key.walk_ast(out.reborrow())?;
+                    out.push_sql(" AS juniper_eager_loading_id, ROW_NUMBER() OVER (PARTITION BY ");
+                    QueryFragment::walk_ast(&#table::#join_to, out.reborrow())?;
+                    out.push_sql(" ORDER BY ");
+                    #order_by
+                    self.primary_key.walk_ast(out.reborrow())?;
+                    out.push_sql(") AS juniper_eager_loading_row_number FROM ");
+                    self.from_clause.walk_ast(out.reborrow())?;
+                    out.push_sql(" WHERE ");
+                    QueryFragment::walk_ast(&#table::#join_to, out.reborrow())?;
+                    #bind_ids
+                    out.push_sql(") AS juniper_eager_loading_numbered WHERE juniper_eager_loading_row_number <= ");
+                    out.push_bind_param::<diesel::sql_types::BigInt, _>(&self.limit)?;
+                    out.push_sql(")");
+
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+impl Count {
+    fn gen_tokens(&self, input: &Input, backend: &Backend, out: &mut TokenStream) {
+        let error_ty = &input.error_ty;
+        let context_ty = &input.context_ty;
+
+        let join_ty = &self.join_ty;
+        let from_id = key_of_other(&self.join_from);
+        let key_ty = &self.key_ty;
+        let table = &self.table;
+        let join_to = columns(table, &self.join_to);
+        let aggregate_ty = &self.aggregate_ty;
+        let (row_value_in, filter) = key_filter(
+            table,
+            &self.join_to,
+            &format_ident!("from_ids"),
+            backend,
+            input.diesel,
+        );
+        let let_connection = input.let_connection(&self.options);
+        let load_connection = load_connection(input.diesel);
+        let chunk_size = backend.chunk_size(self.join_to.len(), 0);
+
         out.extend(quote! {
-            impl juniper_eager_loading::LoadFrom<#join_ty> for #self_ty {
+            impl juniper_eager_loading::LoadAggregate<#join_ty> for #aggregate_ty {
+                type Key = #key_ty;
+                type Value = i64;
                 type Error = #error_ty;
                 type Context = #context_ty;
 
@@ -225,18 +953,199 @@ impl HasMany {
                     froms: &[#join_ty],
                     _field_args: &(),
                     ctx: &Self::Context,
-                ) -> Result<Vec<Self>, Self::Error> {
+                ) -> Result<Vec<(Self::Key, Self::Value)>, Self::Error> {
                     let from_ids = froms
                         .iter()
-                        .map(|other| other.#join_from)
+                        .map(|other| #from_id)
                         .collect::<Vec<_>>();
 
+                    #row_value_in
+
+                    #let_connection
+
+                    // Diesel doesn't allow selecting aggregates next to other columns, so the
+                    // count is written as SQL.
                     #table::table
                         .filter(#filter)
-                        .load(ctx.db())
+                        .group_by(#join_to)
+                        .select((
+                            #join_to,
+                            diesel::dsl::sql::<diesel::sql_types::BigInt>("COUNT(*)"),
+                        ))
+                        .load::<(#key_ty, i64)>(#load_connection)
                         .map_err(From::from)
                 }
+
+                #chunk_size
             }
         })
     }
 }
+
+/// The columns of a key, such as `table::user_id` or `(table::tenant_id, table::user_id)`.
+fn columns(table: &Ident, join_to: &[Ident]) -> TokenStream {
+    match join_to {
+        [column] => quote! { #table::#column },
+        columns => quote! { (#(#table::#columns,)*) },
+    }
+}
+
+/// A filter that keeps the rows whose `join_to` columns match one of `ids`, along with the
+/// `RowValueIn` type it needs for composite keys.
+fn key_filter(
+    table: &Ident,
+    join_to: &[Ident],
+    ids: &Ident,
+    backend: &Backend,
+    diesel: DieselVersion,
+) -> (TokenStream, TokenStream) {
+    match join_to {
+        [column] if backend.uses_any(diesel) => (
+            quote! {},
+            quote! {
+                #table::#column.eq(diesel::pg::expression::dsl::any(#ids))
+            },
+        ),
+        [column] => (
+            quote! {},
+            quote! {
+                #table::#column.eq_any(#ids)
+            },
+        ),
+        columns => {
+            let columns = quote! { (#(#table::#columns,)*) };
+            (
+                row_value_in(table, join_to.len(), backend, diesel),
+                quote! {
+                    RowValueIn {
+                        columns: #columns,
+                        ids: &#ids,
+                    }
+                },
+            )
+        }
+    }
+}
+
+/// A filter that compares several columns with a list of tuples.
+///
+/// Diesel doesn't support row values so the SQL is written by hand. It is supported by Postgres,
+/// MySQL, and SQLite:
+///
+/// ```sql
+/// (a, b) IN ((?, ?), (?, ?))
+/// ```
+///
+/// Older versions of SQLite only accept a list of row values from a `VALUES` clause, so SQLite
+/// gets `(a, b) IN (VALUES (?, ?), (?, ?))` instead.
+fn row_value_in(
+    table: &Ident,
+    arity: usize,
+    backend: &Backend,
+    diesel: DieselVersion,
+) -> TokenStream {
+    let backend_ty = backend.diesel_type();
+    let column_tys = (0..arity)
+        .map(|idx| format_ident!("C{}", idx))
+        .collect::<Vec<_>>();
+    let id_tys = (0..arity)
+        .map(|idx| format_ident!("T{}", idx))
+        .collect::<Vec<_>>();
+    let idxs = (0..arity).map(Index::from).collect::<Vec<_>>();
+
+    let values = match backend {
+        Backend::Sqlite => quote! { out.push_sql("VALUES "); },
+        Backend::Pg | Backend::Mysql => quote! {},
+    };
+
+    let walk_ast = walk_ast_signature(backend, diesel);
+    let non_aggregate = impl_non_aggregate(
+        diesel,
+        quote! { 'a, Columns, Id },
+        quote! { RowValueIn<'a, Columns, Id> },
+    );
+
+    let walk_columns = idxs.iter().enumerate().map(|(n, idx)| {
+        let separator = if n == 0 {
+            quote! {}
+        } else {
+            quote! { out.push_sql(", "); }
+        };
+        quote! {
+            #separator
+            self.columns.#idx.walk_ast(out.reborrow())?;
+        }
+    });
+
+    let bind_id = idxs
+        .iter()
+        .zip(&column_tys)
+        .enumerate()
+        .map(|(n, (idx, column_ty))| {
+            let separator = if n == 0 {
+                quote! {}
+            } else {
+                quote! { out.push_sql(", "); }
+            };
+            quote! {
+                #separator
+                out.push_bind_param::<<#column_ty as diesel::Expression>::SqlType, _>(&id.#idx)?;
+            }
+        });
+
+    quote! {
+        struct RowValueIn<'a, Columns, Id> {
+            columns: Columns,
+            ids: &'a [Id],
+        }
+
+        impl<'a, Columns, Id> diesel::Expression for RowValueIn<'a, Columns, Id> {
+            type SqlType = diesel::sql_types::Bool;
+        }
+
+        #non_aggregate
+
+        impl<'a, Columns, Id> diesel::AppearsOnTable<#table::table> for RowValueIn<'a, Columns, Id> {}
+
+        impl<'a, Columns, Id> diesel::query_builder::QueryId for RowValueIn<'a, Columns, Id> {
+            type QueryId = ();
+            const HAS_STATIC_QUERY_ID: bool = false;
+        }
+
+        impl<'a, #(#column_tys,)* #(#id_tys,)*> diesel::query_builder::QueryFragment<#backend_ty>
+            for RowValueIn<'a, (#(#column_tys,)*), (#(#id_tys,)*)>
+        where
+            #(
+                #column_tys: diesel::Expression + diesel::query_builder::QueryFragment<#backend_ty>,
+                #backend_ty: diesel::sql_types::HasSqlType<<#column_tys as diesel::Expression>::SqlType>,
+                #id_tys: diesel::serialize::ToSql<<#column_tys as diesel::Expression>::SqlType, #backend_ty>,
+            )*
+        {
+            #walk_ast {
+                use diesel::query_builder::QueryFragment;
+
+                // `IN ()` isn't valid SQL.
+                if self.ids.is_empty() {
+                    out.push_sql("1 = 0");
+                    return Ok(());
+                }
+
+                out.push_sql("(");
+                #(#walk_columns)*
+                out.push_sql(") IN (");
+                #values
+                for (idx, id) in self.ids.iter().enumerate() {
+                    if idx != 0 {
+                        out.push_sql(", ");
+                    }
+                    out.push_sql("(");
+                    #(#bind_id)*
+                    out.push_sql(")");
+                }
+                out.push_sql(")");
+
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use crate::impl_load_from_for_diesel::{inline_connection, parse_connection};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    braced, parenthesized,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    ExprClosure, Ident, Token, Type,
+};
+
+pub fn go(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = match syn::parse::<Input>(input) {
+        Ok(x) => x,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let mut tokens = TokenStream::new();
+
+    for impl_ in &input.impls {
+        impl_.gen_tokens(&input, &mut tokens);
+    }
+
+    tokens.into()
+}
+
+mod kw {
+    syn::custom_keyword!(error);
+    syn::custom_keyword!(context);
+    syn::custom_keyword!(database);
+    syn::custom_keyword!(connection);
+}
+
+#[derive(Debug)]
+enum Database {
+    Postgres,
+    Sqlite,
+}
+
+impl Database {
+    fn sqlx_type(&self) -> TokenStream {
+        match self {
+            Database::Postgres => quote! { sqlx::Postgres },
+            Database::Sqlite => quote! { sqlx::Sqlite },
+        }
+    }
+}
+
+impl Parse for Database {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        match ident.to_string().as_str() {
+            "Postgres" => Ok(Database::Postgres),
+            "Sqlite" => Ok(Database::Sqlite),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "Unsupported database. Expected `Postgres` or `Sqlite`",
+            )),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Input {
+    error_ty: Type,
+    context_ty: Type,
+    database: Database,
+    connection: Option<Box<ExprClosure>>,
+    impls: Punctuated<InputImpl, Token![,]>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let prelude;
+        parenthesized!(prelude in input);
+
+        prelude.parse::<kw::error>()?;
+        prelude.parse::<Token![=]>()?;
+        let error_ty = prelude.parse::<Type>()?;
+
+        prelude.parse::<Token![,]>()?;
+
+        prelude.parse::<kw::context>()?;
+        prelude.parse::<Token![=]>()?;
+        let context_ty = prelude.parse::<Type>()?;
+
+        prelude.parse::<Token![,]>()?;
+
+        prelude.parse::<kw::database>()?;
+        prelude.parse::<Token![=]>()?;
+        let database = prelude.parse::<Database>()?;
+
+        let connection = parse_connection_option(&prelude)?;
+
+        input.parse::<Token![=>]>()?;
+
+        let content;
+        braced!(content in input);
+        let impls = Punctuated::parse_terminated(&content)?;
+
+        Ok(Self {
+            error_ty,
+            context_ty,
+            database,
+            connection,
+            impls,
+        })
+    }
+}
+
+impl Input {
+    /// The `connection` of the impl, the one in the prelude, or `ctx.db()`.
+    fn connection(&self, connection: &Option<Box<ExprClosure>>) -> TokenStream {
+        inline_connection(connection.as_deref().or(self.connection.as_deref()))
+    }
+}
+
+/// Parse an optional `, connection = |ctx| ...` and a trailing comma.
+fn parse_connection_option(input: ParseStream) -> syn::parse::Result<Option<Box<ExprClosure>>> {
+    let mut connection = None;
+
+    if input.peek(Token![,]) {
+        input.parse::<Token![,]>()?;
+
+        if input.peek(kw::connection) {
+            connection = Some(parse_connection(input)?);
+
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+    }
+
+    Ok(connection)
+}
+
+#[derive(Debug)]
+enum InputImpl {
+    HasOne(HasOne),
+    HasMany(HasMany),
+}
+
+/// `i32 -> (users, User)` or `String -> (countries.iso_code, Country)`.
+#[derive(Debug)]
+struct HasOne {
+    id_ty: Type,
+    table: Ident,
+    column: Ident,
+    self_ty: Type,
+    connection: Option<Box<ExprClosure>>,
+}
+
+/// `User.id -> (cars.user_id, Car)`.
+#[derive(Debug)]
+struct HasMany {
+    join_ty: Type,
+    join_from: Ident,
+    table: Ident,
+    join_to: Ident,
+    self_ty: Type,
+    connection: Option<Box<ExprClosure>>,
+}
+
+impl Parse for InputImpl {
+    fn parse(input: ParseStream) -> syn::parse::Result<Self> {
+        let id_ty = input.parse::<Type>()?;
+
+        if input.peek(Token![.]) {
+            let join_ty = id_ty;
+            input.parse::<Token![.]>()?;
+            let join_from = input.parse::<Ident>()?;
+
+            input.parse::<Token![->]>()?;
+
+            let inside;
+            parenthesized!(inside in input);
+            let table = inside.parse::<Ident>()?;
+            inside.parse::<Token![.]>()?;
+            let join_to = inside.parse::<Ident>()?;
+            inside.parse::<Token![,]>()?;
+            let self_ty = inside.parse::<Type>()?;
+            let connection = parse_connection_option(&inside)?;
+
+            Ok(InputImpl::HasMany(HasMany {
+                join_ty,
+                join_from,
+                table,
+                join_to,
+                self_ty,
+                connection,
+            }))
+        } else {
+            input.parse::<Token![->]>()?;
+
+            let inside;
+            parenthesized!(inside in input);
+            let table = inside.parse::<Ident>()?;
+            // Without a column the rows are found by `id`, as sqlx doesn't know the primary key.
+            let column = if inside.peek(Token![.]) {
+                inside.parse::<Token![.]>()?;
+                inside.parse::<Ident>()?
+            } else {
+                Ident::new("id", table.span())
+            };
+            inside.parse::<Token![,]>()?;
+            let self_ty = inside.parse::<Type>()?;
+            let connection = parse_connection_option(&inside)?;
+
+            Ok(InputImpl::HasOne(HasOne {
+                id_ty,
+                table,
+                column,
+                self_ty,
+                connection,
+            }))
+        }
+    }
+}
+
+impl InputImpl {
+    fn gen_tokens(&self, input: &Input, out: &mut TokenStream) {
+        match self {
+            InputImpl::HasOne(has_one) => has_one.gen_tokens(input, out),
+            InputImpl::HasMany(has_many) => has_many.gen_tokens(input, out),
+        }
+    }
+}
+
+impl HasOne {
+    fn gen_tokens(&self, input: &Input, out: &mut TokenStream) {
+        let error_ty = &input.error_ty;
+        let context_ty = &input.context_ty;
+
+        let id_ty = &self.id_ty;
+        let self_ty = &self.self_ty;
+        let connection = input.connection(&self.connection);
+        let fetch = fetch_all(
+            &input.database,
+            &self.table,
+            &self.column,
+            self_ty,
+            connection,
+        );
+        let chunk_size = chunk_size(&input.database);
+
+        out.extend(quote! {
+            #[juniper_eager_loading::async_support::async_trait]
+            impl juniper_eager_loading::AsyncLoadFrom<#id_ty> for #self_ty {
+                type Error = #error_ty;
+                type Context = #context_ty;
+
+                async fn load(
+                    ids: &[#id_ty],
+                    _field_args: &(),
+                    ctx: &Self::Context,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    #fetch
+                }
+
+                #chunk_size
+            }
+        });
+    }
+}
+
+impl HasMany {
+    fn gen_tokens(&self, input: &Input, out: &mut TokenStream) {
+        let error_ty = &input.error_ty;
+        let context_ty = &input.context_ty;
+
+        let join_ty = &self.join_ty;
+        let join_from = &self.join_from;
+        let self_ty = &self.self_ty;
+        let connection = input.connection(&self.connection);
+        let fetch = fetch_all(
+            &input.database,
+            &self.table,
+            &self.join_to,
+            self_ty,
+            connection,
+        );
+        let chunk_size = chunk_size(&input.database);
+
+        out.extend(quote! {
+            #[juniper_eager_loading::async_support::async_trait]
+            impl juniper_eager_loading::AsyncLoadFrom<#join_ty> for #self_ty {
+                type Error = #error_ty;
+                type Context = #context_ty;
+
+                async fn load(
+                    froms: &[#join_ty],
+                    _field_args: &(),
+                    ctx: &Self::Context,
+                ) -> Result<Vec<Self>, Self::Error> {
+                    let from_ids = froms
+                        .iter()
+                        .map(|other| other.#join_from.clone())
+                        .collect::<Vec<_>>();
+                    let ids = from_ids.as_slice();
+
+                    #fetch
+                }
+
+                #chunk_size
+            }
+        });
+    }
+}
+
+/// Load the rows of `table` whose `column` is one of `ids`.
+///
+/// Postgres binds the ids as one array. SQLite doesn't have arrays, so a placeholder is added
+/// for each id. Without any ids the database isn't queried, as `IN ()` isn't valid SQL.
+fn fetch_all(
+    database: &Database,
+    table: &Ident,
+    column: &Ident,
+    self_ty: &Type,
+    connection: TokenStream,
+) -> TokenStream {
+    let database_ty = database.sqlx_type();
+
+    let fetch = match database {
+        Database::Postgres => {
+            let sql = format!("SELECT * FROM {} WHERE {} = ANY($1)", table, column);
+            quote! {
+                sqlx::query_as::<#database_ty, #self_ty>(#sql)
+                    .bind(ids)
+                    .fetch_all(connection)
+                    .await
+                    .map_err(From::from)
+            }
+        }
+        Database::Sqlite => {
+            let sql = format!("SELECT * FROM {} WHERE {} IN ({{}})", table, column);
+            quote! {
+                let sql = format!(#sql, vec!["?"; ids.len()].join(", "));
+                let mut query = sqlx::query_as::<#database_ty, #self_ty>(&sql);
+                for id in ids {
+                    query = query.bind(id);
+                }
+                query.fetch_all(connection).await.map_err(From::from)
+            }
+        }
+    };
+
+    quote! {
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let connection = #connection;
+        #fetch
+    }
+}
+
+/// SQLite allows at most 999 bound parameters in a query. Postgres binds a single array so the
+/// ids don't have to be chunked.
+fn chunk_size(database: &Database) -> TokenStream {
+    match database {
+        Database::Postgres => quote! {},
+        Database::Sqlite => quote! {
+            fn chunk_size() -> Option<usize> {
+                Some(999)
+            }
+        },
+    }
+}
@@ -1,26 +1,32 @@
 //! See the docs for "juniper-eager-loading" for more info about this.
 
 #![recursion_limit = "256"]
-#![deny(
-    unused_variables,
-    mutable_borrow_reservation_conflict,
-    dead_code,
-    unused_must_use,
-    unused_imports
-)]
+#![deny(unused_variables, dead_code, unused_must_use, unused_imports)]
 
 extern crate proc_macro;
 extern crate proc_macro2;
 
 mod derive_eager_loading;
 mod impl_load_from_for_diesel;
+mod impl_load_from_for_in_memory;
+mod impl_load_from_for_sqlx;
 
 use impl_load_from_for_diesel::Backend;
 use proc_macro_error::*;
 
 #[proc_macro_derive(
     EagerLoading,
-    attributes(eager_loading, has_one, option_has_one, has_many, has_many_through)
+    attributes(
+        eager_loading,
+        has_one,
+        option_has_one,
+        has_many,
+        has_many_through,
+        has_connection,
+        has_aggregate,
+        polymorphic_has_one,
+        has_many_from_ids
+    )
 )]
 #[proc_macro_error]
 pub fn derive_eager_loading(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -41,3 +47,13 @@ pub fn impl_load_from_for_diesel_mysql(input: proc_macro::TokenStream) -> proc_m
 pub fn impl_load_from_for_diesel_sqlite(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     impl_load_from_for_diesel::go(input, Backend::Sqlite)
 }
+
+#[proc_macro]
+pub fn impl_load_from_for_sqlx(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    impl_load_from_for_sqlx::go(input)
+}
+
+#[proc_macro]
+pub fn impl_load_from_for_in_memory(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    impl_load_from_for_in_memory::go(input)
+}
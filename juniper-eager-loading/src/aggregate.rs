@@ -0,0 +1,178 @@
+use crate::{AssociationType, Error};
+use std::{collections::HashMap, hash::Hash};
+
+/// An association that holds an aggregate of the children of a parent, such as how many there
+/// are, without loading the children themselves.
+///
+/// Imagine you have this GraphQL schema:
+///
+/// ```graphql
+/// type User {
+///     carCount: Int!
+/// }
+/// ```
+///
+/// Loading every car just to count them is wasteful, as is running a count query for every user.
+/// With `HasAggregate` the counts for all the users are loaded with one query through
+/// [`LoadAggregate`][]:
+///
+/// ```ignore
+/// pub struct CarCount;
+///
+/// impl LoadAggregate<models::User> for CarCount {
+///     type Key = i32;
+///     type Value = i64;
+///     type Error = diesel::result::Error;
+///     type Context = Context;
+///
+///     fn load(
+///         users: &[models::User],
+///         _: &(),
+///         ctx: &Self::Context,
+///     ) -> Result<Vec<(i32, i64)>, Self::Error> {
+///         // SELECT user_id, COUNT(*) FROM cars WHERE user_id IN (...) GROUP BY user_id
+///     }
+/// }
+///
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = diesel::result::Error)]
+/// pub struct User {
+///     user: models::User,
+///     #[has_aggregate(aggregate = CarCount)]
+///     car_count: HasCount,
+/// }
+/// ```
+///
+/// The Diesel macros can generate [`LoadAggregate`][] implementations for counts. See
+/// [`impl_load_from_for_diesel_pg`][] for more details.
+///
+/// # Attributes
+///
+/// | Name | Description | Default | Example |
+/// |---|---|---|---|
+/// | `aggregate` | The type implementing [`LoadAggregate`][] | N/A | `aggregate = CarCount` |
+/// | `key_field` | The field on the model that [`LoadAggregate::Key`][] is compared with | `primary_key_field` of `#[eager_loading]` | `key_field = identifier` |
+/// | `graphql_field` | The name of this field in your GraphQL schema | `{name of field}` | `graphql_field = car_count` |
+/// | `chunk_size` | The largest number of parents to pass to [`LoadAggregate`][] at once | [`LoadAggregate::chunk_size`][] | `chunk_size = 500` |
+/// | `skip` | Don't load the aggregate | Not set | `skip` |
+///
+/// Aggregates cannot be used with `#[eager_loading(asynchronous)]`.
+///
+/// # Errors
+///
+/// [`try_unwrap`][] will return an error if the aggregate wasn't loaded. Parents that
+/// [`LoadAggregate`][] didn't return a value for get `Value::default()`, such as `0` or `false`.
+///
+/// [`LoadAggregate`]: trait.LoadAggregate.html
+/// [`LoadAggregate::Key`]: trait.LoadAggregate.html#associatedtype.Key
+/// [`LoadAggregate::chunk_size`]: trait.LoadAggregate.html#method.chunk_size
+/// [`impl_load_from_for_diesel_pg`]: macro.impl_load_from_for_diesel_pg.html
+/// [`try_unwrap`]: struct.HasAggregate.html#method.try_unwrap
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct HasAggregate<T>(Option<T>);
+
+impl<T> Default for HasAggregate<T> {
+    fn default() -> Self {
+        HasAggregate(None)
+    }
+}
+
+impl<T> HasAggregate<T> {
+    /// Borrow the loaded value. If the value has not been loaded it will return an error.
+    pub fn try_unwrap(&self) -> Result<&T, Error> {
+        self.0
+            .as_ref()
+            .ok_or(Error::NotLoaded(AssociationType::HasAggregate))
+    }
+}
+
+/// A [`HasAggregate`][] holding the number of children.
+///
+/// [`HasAggregate`]: struct.HasAggregate.html
+pub type HasCount = HasAggregate<i64>;
+
+/// How to load an aggregate for a list of parents.
+///
+/// This is the [`HasAggregate`][] version of [`LoadFrom`][]. It is implemented for a type that
+/// names the aggregate, since a parent might have several aggregates with the same value type.
+///
+/// [`HasAggregate`]: struct.HasAggregate.html
+/// [`LoadFrom`]: trait.LoadFrom.html
+pub trait LoadAggregate<T, Args = ()> {
+    /// The key used to find the parent a value belongs to, usually the id of the parent.
+    type Key: Hash + Eq;
+
+    /// The aggregated value. Parents without any children get `Value::default()`.
+    type Value: Default + Clone;
+
+    /// The error type.
+    type Error;
+
+    /// Your Juniper context type.
+    type Context;
+
+    /// Load the values for the parents as `(parent_key, value)` pairs.
+    fn load(
+        parents: &[T],
+        args: &Args,
+        context: &Self::Context,
+    ) -> Result<Vec<(Self::Key, Self::Value)>, Self::Error>;
+
+    /// The largest number of parents to pass to [`load`][] at once.
+    ///
+    /// This works like [`LoadFrom::chunk_size`][]. The values loaded for each chunk are combined
+    /// before they're given to the parents.
+    ///
+    /// [`load`]: trait.LoadAggregate.html#tymethod.load
+    /// [`LoadFrom::chunk_size`]: trait.LoadFrom.html#method.chunk_size
+    fn chunk_size() -> Option<usize> {
+        None
+    }
+}
+
+/// Load an aggregate with [`LoadAggregate`][] and store the values on the nodes.
+///
+/// `nodes` must have been created from `models`, in the same order. This is used by the code
+/// generated for [`HasAggregate`][].
+///
+/// The models are passed to [`LoadAggregate::load`][] in chunks of `chunk_size`, which takes
+/// precedence over [`LoadAggregate::chunk_size`][]. If neither is set all the models are passed at
+/// once.
+///
+/// [`LoadAggregate`]: trait.LoadAggregate.html
+/// [`LoadAggregate::load`]: trait.LoadAggregate.html#tymethod.load
+/// [`LoadAggregate::chunk_size`]: trait.LoadAggregate.html#method.chunk_size
+/// [`HasAggregate`]: struct.HasAggregate.html
+pub fn load_aggregate<A, Node, Model, Args>(
+    nodes: &mut [Node],
+    models: &[Model],
+    args: &Args,
+    ctx: &A::Context,
+    chunk_size: Option<usize>,
+    parent_key: impl Fn(&Model) -> A::Key,
+    association: impl Fn(&mut Node) -> &mut HasAggregate<A::Value>,
+) -> Result<(), A::Error>
+where
+    A: LoadAggregate<Model, Args>,
+{
+    let values = match chunk_size.or_else(A::chunk_size) {
+        Some(0) => panic!("The chunk size of `LoadAggregate` must be greater than zero"),
+        Some(chunk_size) if models.len() > chunk_size => {
+            let mut values = HashMap::new();
+            for chunk in models.chunks(chunk_size) {
+                values.extend(A::load(chunk, args, ctx)?);
+            }
+            values
+        }
+        _ => A::load(models, args, ctx)?
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+    };
+
+    for (node, model) in nodes.iter_mut().zip(models) {
+        let value = values.get(&parent_key(model)).cloned().unwrap_or_default();
+        association(node).0 = Some(value);
+    }
+
+    Ok(())
+}
@@ -1,40 +1,124 @@
-use crate::{HasMany, HasManyThrough, HasOne, HasOneInner, OptionHasOne};
+use crate::{
+    encode_cursor, Edge, HasConnection, HasMany, HasManyFromIds, HasManyThrough, HasOne,
+    OptionHasOne, Pagination, PolymorphicHasOne, SharedHasMany, SharedHasOne,
+};
+use std::sync::Arc;
 
 /// Methods available for all association types.
 pub trait Association<T> {
     /// Store the loaded child on the association.
     fn loaded_child(&mut self, child: T);
 
+    /// Store a child that might also belong to other parents on the association.
+    ///
+    /// The child is only cloned if the association needs to own it and other parents are still
+    /// holding on to it.
+    fn loaded_shared_child(&mut self, child: Arc<T>);
+
+    /// Called before the children are stored if the association is paginated.
+    ///
+    /// `has_next_page` is `true` if the parent has more children after the page. Only associations
+    /// that expose page info, such as [`HasConnection`][], need to implement this.
+    ///
+    /// [`HasConnection`]: struct.HasConnection.html
+    fn loaded_page(&mut self, _pagination: &Pagination, _has_next_page: bool) {}
+
     /// The association should have been loaded by now, if not store an error inside the
     /// association (if applicable for the particular association).
     fn assert_loaded_otherwise_failed(&mut self);
 }
 
+/// Cloning the children of a [`HasMany`][].
+///
+/// Associations that own their children move a child into its parent when it belongs to a single
+/// parent, and only clone it when it's shared with other parents. Children of [`HasOne`][],
+/// [`OptionHasOne`][], [`HasManyThrough`][], and the other associations where several parents can
+/// point to the same child must implement `Clone`, or be wrapped in [`SharedHasOne`][] or
+/// [`SharedHasMany`][].
+///
+/// The children of a [`HasMany`][] each have a single parent so they only require this trait,
+/// which is implemented for all types that implement `Clone`. Children that don't implement
+/// `Clone` can implement it and return `None` instead. If the same parent is eager loaded more
+/// than once such a child can't be given to each copy, and [`HasMany::try_unwrap`][] returns
+/// [`Error::ChildNotCloneable`][].
+///
+/// ```
+/// # use juniper_eager_loading::CloneChild;
+/// struct Car {
+///     id: i32,
+/// }
+///
+/// impl CloneChild for Car {
+///     fn clone_child(&self) -> Option<Self> {
+///         None
+///     }
+/// }
+/// ```
+///
+/// Such a child can't be used in a [`HasOne`][]:
+///
+/// ```compile_fail
+/// # use juniper_eager_loading::{Association, HasOne};
+/// struct Country {
+///     id: i32,
+/// }
+///
+/// let mut country = HasOne::default();
+/// country.loaded_child(Country { id: 1 });
+/// ```
+///
+/// [`HasOne`]: struct.HasOne.html
+/// [`OptionHasOne`]: struct.OptionHasOne.html
+/// [`HasMany`]: struct.HasMany.html
+/// [`HasManyThrough`]: struct.HasManyThrough.html
+/// [`SharedHasOne`]: struct.SharedHasOne.html
+/// [`SharedHasMany`]: struct.SharedHasMany.html
+/// [`HasMany::try_unwrap`]: struct.HasMany.html#method.try_unwrap
+/// [`Error::ChildNotCloneable`]: enum.Error.html#variant.ChildNotCloneable
+pub trait CloneChild: Sized {
+    /// Clone the child, or return `None` if it can't be cloned.
+    fn clone_child(&self) -> Option<Self>;
+}
+
+impl<T: Clone> CloneChild for T {
+    fn clone_child(&self) -> Option<Self> {
+        Some(self.clone())
+    }
+}
+
 // --
 // -- impl for HasOne
 // --
-impl<T> Association<T> for HasOne<T> {
+impl<T: Clone> Association<T> for HasOne<T> {
     fn loaded_child(&mut self, child: T) {
         has_one_loaded_child(self, child)
     }
 
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        has_one_loaded_child(self, unwrap_or_clone(child))
+    }
+
     fn assert_loaded_otherwise_failed(&mut self) {
         has_one_assert_loaded_otherwise_failed(self)
     }
 }
 
-impl<T> Association<T> for HasOne<Box<T>> {
+impl<T: Clone> Association<T> for HasOne<Box<T>> {
     fn loaded_child(&mut self, child: T) {
         has_one_loaded_child(self, Box::new(child))
     }
 
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        has_one_loaded_child(self, Box::new(unwrap_or_clone(child)))
+    }
+
     fn assert_loaded_otherwise_failed(&mut self) {
         has_one_assert_loaded_otherwise_failed(self)
     }
 }
 
 fn has_one_loaded_child<T>(association: &mut HasOne<T>, child: T) {
-    association.0 = HasOneInner::Loaded(child);
+    association.0.loaded(child);
 }
 
 fn has_one_assert_loaded_otherwise_failed<T>(association: &mut HasOne<T>) {
@@ -44,9 +128,13 @@ fn has_one_assert_loaded_otherwise_failed<T>(association: &mut HasOne<T>) {
 // --
 // -- impl for OptionHasOne
 // --
-impl<T> Association<T> for OptionHasOne<T> {
+impl<T: Clone> Association<T> for OptionHasOne<T> {
     fn loaded_child(&mut self, child: T) {
-        option_has_one_loaded_child(self, Some(child));
+        option_has_one_loaded_child(self, child);
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        option_has_one_loaded_child(self, unwrap_or_clone(child));
     }
 
     fn assert_loaded_otherwise_failed(&mut self) {
@@ -54,9 +142,13 @@ impl<T> Association<T> for OptionHasOne<T> {
     }
 }
 
-impl<T> Association<T> for OptionHasOne<Box<T>> {
+impl<T: Clone> Association<T> for OptionHasOne<Box<T>> {
     fn loaded_child(&mut self, child: T) {
-        option_has_one_loaded_child(self, Some(Box::new(child)));
+        option_has_one_loaded_child(self, Box::new(child));
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        option_has_one_loaded_child(self, Box::new(unwrap_or_clone(child)));
     }
 
     fn assert_loaded_otherwise_failed(&mut self) {
@@ -64,44 +156,169 @@ impl<T> Association<T> for OptionHasOne<Box<T>> {
     }
 }
 
-fn option_has_one_loaded_child<T>(association: &mut OptionHasOne<T>, child: Option<T>) {
-    association.0 = child;
+fn option_has_one_loaded_child<T>(association: &mut OptionHasOne<T>, child: T) {
+    if association.child.is_some() {
+        association.too_many_children = true;
+    } else {
+        association.child = Some(child);
+    }
+}
+
+fn option_has_one_assert_loaded_otherwise_failed<T>(_association: &mut OptionHasOne<T>) {
+    // cannot fail, defaults to `None`
 }
 
-fn option_has_one_assert_loaded_otherwise_failed<T>(association: &mut OptionHasOne<T>) {
-    match association.0 {
-        Some(_) => {}
-        None => {
-            association.0 = None;
+// --
+// -- impl for HasMany
+// --
+impl<T: CloneChild> Association<T> for HasMany<T> {
+    fn loaded_child(&mut self, child: T) {
+        self.children.push(child);
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        let child = match Arc::try_unwrap(child) {
+            Ok(child) => Some(child),
+            Err(child) => T::clone_child(&child),
+        };
+
+        match child {
+            Some(child) => self.children.push(child),
+            None => self.child_not_cloneable = true,
         }
     }
+
+    fn assert_loaded_otherwise_failed(&mut self) {
+        // cannot fail, defaults to an empty vec
+    }
 }
 
 // --
-// -- impl for HasMany
+// -- impl for HasManyThrough
 // --
-impl<T> Association<T> for HasMany<T> {
+impl<T: Clone> Association<T> for HasManyThrough<T> {
     fn loaded_child(&mut self, child: T) {
         self.0.push(child);
     }
 
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        self.0.push(unwrap_or_clone(child));
+    }
+
     fn assert_loaded_otherwise_failed(&mut self) {
         // cannot fail, defaults to an empty vec
     }
 }
 
 // --
-// -- impl for HasManyThrough
+// -- impl for HasManyFromIds
 // --
-impl<T> Association<T> for HasManyThrough<T> {
+impl<T: Clone> Association<T> for HasManyFromIds<T> {
     fn loaded_child(&mut self, child: T) {
         self.0.push(child);
     }
 
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        self.0.push(unwrap_or_clone(child));
+    }
+
     fn assert_loaded_otherwise_failed(&mut self) {
         // cannot fail, defaults to an empty vec
     }
 }
 
-// NOTE: We don't have to implement Association for HasMany<Box<T>> or HasManyThrough<Box<T>>
-// because they already have indirection through the inner Vec. So recursive types are supported.
+// --
+// -- impl for SharedHasOne
+// --
+impl<T> Association<T> for SharedHasOne<T> {
+    fn loaded_child(&mut self, child: T) {
+        self.0.loaded(Arc::new(child));
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        self.0.loaded(child);
+    }
+
+    fn assert_loaded_otherwise_failed(&mut self) {
+        self.0.assert_loaded_otherwise_failed()
+    }
+}
+
+// --
+// -- impl for SharedHasMany
+// --
+impl<T> Association<T> for SharedHasMany<T> {
+    fn loaded_child(&mut self, child: T) {
+        self.0.push(Arc::new(child));
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        self.0.push(child);
+    }
+
+    fn assert_loaded_otherwise_failed(&mut self) {
+        // cannot fail, defaults to an empty vec
+    }
+}
+
+// --
+// -- impl for PolymorphicHasOne
+// --
+impl<T, V: Clone> Association<V> for PolymorphicHasOne<T>
+where
+    T: From<V>,
+{
+    fn loaded_child(&mut self, child: V) {
+        self.0.loaded(T::from(child));
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<V>) {
+        self.0.loaded(T::from(unwrap_or_clone(child)));
+    }
+
+    fn assert_loaded_otherwise_failed(&mut self) {
+        self.0.assert_loaded_otherwise_failed()
+    }
+}
+
+// --
+// -- impl for HasConnection
+// --
+impl<T: Clone> Association<T> for HasConnection<T> {
+    fn loaded_child(&mut self, child: T) {
+        has_connection_loaded_child(self, child)
+    }
+
+    fn loaded_shared_child(&mut self, child: Arc<T>) {
+        has_connection_loaded_child(self, unwrap_or_clone(child))
+    }
+
+    fn loaded_page(&mut self, pagination: &Pagination, has_next_page: bool) {
+        self.offset = pagination.offset();
+        self.connection.page_info.has_previous_page = pagination.has_previous_page();
+        self.connection.page_info.has_next_page = has_next_page;
+    }
+
+    fn assert_loaded_otherwise_failed(&mut self) {
+        // cannot fail, defaults to an empty connection
+    }
+}
+
+fn has_connection_loaded_child<T>(association: &mut HasConnection<T>, node: T) {
+    let connection = &mut association.connection;
+    let cursor = encode_cursor(association.offset + connection.edges.len());
+
+    if connection.page_info.start_cursor.is_none() {
+        connection.page_info.start_cursor = Some(cursor.clone());
+    }
+    connection.page_info.end_cursor = Some(cursor.clone());
+    connection.edges.push(Edge { node, cursor });
+}
+
+fn unwrap_or_clone<T: Clone>(child: Arc<T>) -> T {
+    Arc::try_unwrap(child).unwrap_or_else(|child| T::clone(&child))
+}
+
+// NOTE: We don't have to implement Association for HasMany<Box<T>>, HasManyThrough<Box<T>>, or
+// HasManyFromIds<Box<T>> because they already have indirection through the inner Vec. So
+// recursive types are supported.
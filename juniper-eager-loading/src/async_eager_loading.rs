@@ -0,0 +1,251 @@
+use crate::{load_children_into_nodes, Association, JoinKey, LoadChildrenOutput, Pagination};
+use async_trait::async_trait;
+use juniper_from_schema::{QueryTrail, Walked};
+use std::{cmp::Ordering, hash::Hash};
+
+/// The async version of [`EagerLoading`][].
+///
+/// Implemented by `#[derive(EagerLoading)]` when using `#[eager_loading(asynchronous)]`.
+///
+/// [`EagerLoading`]: trait.EagerLoading.html
+#[async_trait]
+pub trait AsyncEagerLoading: Sized + Send + Sync {
+    /// The model type.
+    type Model: Send + Sync;
+
+    /// The id type the model uses.
+    type Id: 'static + Hash + Eq;
+
+    /// Your Juniper context type.
+    type Context: Send + Sync;
+
+    /// The error type.
+    type Error: Send;
+
+    /// Create a new GraphQL type from a model.
+    fn new_from_model(model: &Self::Model) -> Self;
+
+    /// Create a list of GraphQL types from a list of models.
+    fn from_db_models(models: &[Self::Model]) -> Vec<Self> {
+        models
+            .iter()
+            .map(|model| Self::new_from_model(model))
+            .collect()
+    }
+
+    /// For each field in your GraphQL type that implements [`AsyncEagerLoadChildrenOfType`][]
+    /// load the children and store them on the nodes.
+    ///
+    /// The code generated by `#[derive(EagerLoading)]` loads all the associations concurrently.
+    ///
+    /// [`AsyncEagerLoadChildrenOfType`]: trait.AsyncEagerLoadChildrenOfType.html
+    async fn eager_load_each(
+        models: &[Self::Model],
+        ctx: &Self::Context,
+        trail: &QueryTrail<'_, Self, Walked>,
+    ) -> Result<Vec<Self>, Self::Error>;
+
+    /// Perform eager loading for a single GraphQL value.
+    async fn eager_load(
+        model: Self::Model,
+        ctx: &Self::Context,
+        trail: &QueryTrail<'_, Self, Walked>,
+    ) -> Result<Self, Self::Error> {
+        let mut nodes = Self::eager_load_each(&[model], ctx, trail).await?;
+
+        // This wont panic because we only passed one model into `eager_load_each`
+        Ok(nodes.remove(0))
+    }
+}
+
+/// The async version of [`EagerLoadChildrenOfType`][].
+///
+/// Loading is split into two steps. [`load_and_eager_load_children`][] loads the children
+/// without needing access to the parents, so the children of several associations can be loaded
+/// at the same time. [`assign_children`][] then stores the children on the parents they belong to.
+///
+/// [`EagerLoadChildrenOfType`]: trait.EagerLoadChildrenOfType.html
+/// [`load_and_eager_load_children`]: trait.AsyncEagerLoadChildrenOfType.html#method.load_and_eager_load_children
+/// [`assign_children`]: trait.AsyncEagerLoadChildrenOfType.html#method.assign_children
+#[async_trait]
+pub trait AsyncEagerLoadChildrenOfType<'a, Child, ImplContext, JoinModel = ()>
+where
+    Self: AsyncEagerLoading,
+    Child: AsyncEagerLoading<Context = Self::Context, Error = Self::Error>,
+    JoinModel: 'static + Send + Sync,
+{
+    /// The types of arguments the GraphQL field takes.
+    type FieldArguments: Sync;
+
+    /// Load the children from the data store.
+    async fn load_children(
+        models: &[Self::Model],
+        field_args: &Self::FieldArguments,
+        ctx: &Self::Context,
+    ) -> Result<LoadChildrenOutput<Child::Model, JoinModel>, Self::Error>;
+
+    /// Does this parent and this child belong together?
+    fn is_child_of(
+        parent: &Self,
+        child: &Child,
+        join_model: &JoinModel,
+        field_args: &Self::FieldArguments,
+        context: &Self::Context,
+    ) -> bool;
+
+    /// The key used to look up the children of a parent. See
+    /// [`EagerLoadChildrenOfType::parent_join_key`][] for more details.
+    ///
+    /// [`EagerLoadChildrenOfType::parent_join_key`]: trait.EagerLoadChildrenOfType.html#method.parent_join_key
+    fn parent_join_key(
+        _parent: &Self,
+        _field_args: &Self::FieldArguments,
+        _context: &Self::Context,
+    ) -> Option<JoinKey> {
+        None
+    }
+
+    /// The key used to find the parents of a child. See
+    /// [`EagerLoadChildrenOfType::child_join_key`][] for more details.
+    ///
+    /// [`EagerLoadChildrenOfType::child_join_key`]: trait.EagerLoadChildrenOfType.html#method.child_join_key
+    fn child_join_key(
+        _child: &Child,
+        _join_model: &JoinModel,
+        _field_args: &Self::FieldArguments,
+        _context: &Self::Context,
+    ) -> Option<JoinKey> {
+        None
+    }
+
+    /// Return the particular association type.
+    fn association(node: &mut Self) -> &mut dyn Association<Child>;
+
+    /// How to paginate the children of each parent. See
+    /// [`EagerLoadChildrenOfType::pagination`][] for more details.
+    ///
+    /// [`EagerLoadChildrenOfType::pagination`]: trait.EagerLoadChildrenOfType.html#method.pagination
+    fn pagination(_field_args: &Self::FieldArguments) -> Option<Pagination> {
+        None
+    }
+
+    /// Compare two children of the same parent. See
+    /// [`EagerLoadChildrenOfType::compare_children`][] for more details.
+    ///
+    /// [`EagerLoadChildrenOfType::compare_children`]: trait.EagerLoadChildrenOfType.html#method.compare_children
+    fn compare_children(
+        _a: (&Child, &JoinModel),
+        _b: (&Child, &JoinModel),
+        _field_args: &Self::FieldArguments,
+        _context: &Self::Context,
+    ) -> Ordering {
+        Ordering::Equal
+    }
+
+    /// Load the children and eager load their associations.
+    async fn load_and_eager_load_children(
+        models: &[Self::Model],
+        ctx: &Self::Context,
+        trail: &QueryTrail<'a, Child, Walked>,
+        field_args: &Self::FieldArguments,
+    ) -> Result<Vec<(Child, JoinModel)>, Self::Error> {
+        let child_models = Self::load_children(models, field_args, ctx)
+            .await?
+            .into_child_and_join_models();
+        let (child_models, join_models): (Vec<_>, Vec<_>) = child_models.into_iter().unzip();
+
+        let children = Child::eager_load_each(&child_models, ctx, trail).await?;
+
+        Ok(children.into_iter().zip(join_models).collect())
+    }
+
+    /// Store the loaded children on the parents they belong to.
+    fn assign_children(
+        nodes: &mut [Self],
+        children: Vec<(Child, JoinModel)>,
+        field_args: &Self::FieldArguments,
+        ctx: &Self::Context,
+    ) {
+        load_children_into_nodes(
+            nodes,
+            children,
+            |node, child, join_model| Self::is_child_of(node, child, join_model, field_args, ctx),
+            |node| Self::parent_join_key(node, field_args, ctx),
+            |child, join_model| Self::child_join_key(child, join_model, field_args, ctx),
+            |a, b| Self::compare_children(a, b, field_args, ctx),
+            Self::pagination(field_args),
+            Self::association,
+        );
+    }
+
+    /// Combine all the methods above to eager load the children for a list of GraphQL values and
+    /// models.
+    async fn eager_load_children(
+        nodes: &mut [Self],
+        models: &[Self::Model],
+        ctx: &Self::Context,
+        trail: &QueryTrail<'a, Child, Walked>,
+        field_args: &Self::FieldArguments,
+    ) -> Result<(), Self::Error> {
+        let children = Self::load_and_eager_load_children(models, ctx, trail, field_args).await?;
+        Self::assign_children(nodes, children, field_args, ctx);
+        Ok(())
+    }
+}
+
+/// The async version of [`LoadFrom`][].
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+#[async_trait]
+pub trait AsyncLoadFrom<T, Args = ()>: Sized {
+    /// The error type.
+    type Error;
+
+    /// Your Juniper context type.
+    type Context;
+
+    /// Perform the load.
+    async fn load(
+        ids: &[T],
+        args: &Args,
+        context: &Self::Context,
+    ) -> Result<Vec<Self>, Self::Error>;
+
+    /// The largest number of values to pass to [`load`][] at once. See
+    /// [`LoadFrom::chunk_size`][] for more details.
+    ///
+    /// [`load`]: trait.AsyncLoadFrom.html#tymethod.load
+    /// [`LoadFrom::chunk_size`]: trait.LoadFrom.html#method.chunk_size
+    fn chunk_size() -> Option<usize> {
+        None
+    }
+}
+
+/// The async version of [`load_from_in_chunks`][]. The chunks are loaded one after the other.
+///
+/// # Panics
+///
+/// Panics if the chunk size is zero.
+///
+/// [`load_from_in_chunks`]: fn.load_from_in_chunks.html
+pub async fn async_load_from_in_chunks<T, Args, Child>(
+    ids: &[T],
+    args: &Args,
+    ctx: &Child::Context,
+    chunk_size: Option<usize>,
+) -> Result<Vec<Child>, Child::Error>
+where
+    Child: AsyncLoadFrom<T, Args>,
+{
+    let chunk_size = match chunk_size.or_else(Child::chunk_size) {
+        Some(0) => panic!("The chunk size of `AsyncLoadFrom` must be greater than zero"),
+        Some(chunk_size) if ids.len() > chunk_size => chunk_size,
+        _ => return Child::load(ids, args, ctx).await,
+    };
+
+    let mut children = Vec::new();
+    for chunk in ids.chunks(chunk_size) {
+        children.extend(Child::load(chunk, args, ctx).await?);
+    }
+    Ok(children)
+}
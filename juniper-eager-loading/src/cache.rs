@@ -0,0 +1,155 @@
+use crate::{load_from_in_chunks, LoadFrom};
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    sync::Mutex,
+};
+
+/// A cache of loaded models that lives for the duration of a single request.
+///
+/// Models are keyed by their type, the name of the field they were looked up by, and the value of
+/// that field. When using `#[eager_loading(cache)]` the code generated for [`HasOne`][] and
+/// [`OptionHasOne`][] associations will look up models in the cache before calling [`LoadFrom`][],
+/// and only load the ids that are missing.
+///
+/// The field name keeps lookups through different fields apart, so a country found by its
+/// `numeric_code` is never returned for an `id` with the same value.
+///
+/// The cache should be stored in your Juniper context and exposed through [`ContextWithCache`][].
+/// Since models are never evicted a new cache should be created for each request.
+///
+/// [`HasOne`]: struct.HasOne.html
+/// [`OptionHasOne`]: struct.OptionHasOne.html
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`ContextWithCache`]: trait.ContextWithCache.html
+#[derive(Default)]
+pub struct Cache {
+    models: Mutex<HashMap<(TypeId, &'static str), Box<dyn Any + Send>>>,
+}
+
+impl Cache {
+    /// Create a new empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the model whose `key` field is `id` from the cache.
+    pub fn get<Model, Id>(&self, key: &'static str, id: &Id) -> Option<Model>
+    where
+        Model: Clone + Send + 'static,
+        Id: Hash + Eq + Send + 'static,
+    {
+        let models = self.models.lock().unwrap();
+        models
+            .get(&(TypeId::of::<HashMap<Id, Model>>(), key))
+            .and_then(|models| models.downcast_ref::<HashMap<Id, Model>>())
+            .and_then(|models| models.get(id))
+            .cloned()
+    }
+
+    /// Insert a model into the cache under the value `id` of its `key` field, replacing the
+    /// existing model with the same key.
+    pub fn insert<Model, Id>(&self, key: &'static str, id: Id, model: Model)
+    where
+        Model: Send + 'static,
+        Id: Hash + Eq + Send + 'static,
+    {
+        let mut models = self.models.lock().unwrap();
+        models
+            .entry((TypeId::of::<HashMap<Id, Model>>(), key))
+            .or_insert_with(|| Box::new(HashMap::<Id, Model>::new()))
+            .downcast_mut::<HashMap<Id, Model>>()
+            .expect("cache entry has the wrong type")
+            .insert(id, model);
+    }
+
+    /// Remove all models from the cache.
+    pub fn clear(&self) {
+        self.models.lock().unwrap().clear();
+    }
+}
+
+impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cache")
+            .field("model_types", &self.models.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// Juniper context types that hold a [`Cache`][].
+///
+/// This is required when using `#[eager_loading(cache)]`.
+///
+/// [`Cache`]: struct.Cache.html
+pub trait ContextWithCache {
+    /// Get the cache for the current request.
+    fn cache(&self) -> &Cache;
+}
+
+/// Load models by id, only calling [`LoadFrom`][] for the ids that aren't in the cache.
+///
+/// `key` is the name of the field the models are looked up by, and `id_of` is used to find the
+/// value of that field on each loaded model so it can be inserted into the cache. The missing ids
+/// are loaded in chunks of `chunk_size`, see [`load_from_in_chunks`][].
+///
+/// This is the function used by `#[eager_loading(cache)]`.
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`load_from_in_chunks`]: fn.load_from_in_chunks.html
+pub fn load_cached<Model, Id, F>(
+    ids: &[Id],
+    field_args: &(),
+    ctx: &<Model as LoadFrom<Id>>::Context,
+    chunk_size: Option<usize>,
+    key: &'static str,
+    id_of: F,
+) -> Result<Vec<Model>, <Model as LoadFrom<Id>>::Error>
+where
+    Model: LoadFrom<Id> + Clone + Send + 'static,
+    <Model as LoadFrom<Id>>::Context: ContextWithCache,
+    Id: Hash + Eq + Clone + Send + 'static,
+    F: Fn(&Model) -> Id,
+{
+    load_cached_with(ids, ctx, key, id_of, |missing_ids| {
+        load_from_in_chunks(missing_ids, field_args, ctx, chunk_size)
+    })
+}
+
+/// Look up the models in the cache and load the missing ones with `load`.
+pub(crate) fn load_cached_with<Model, Id, Ctx, E, F>(
+    ids: &[Id],
+    ctx: &Ctx,
+    key: &'static str,
+    id_of: F,
+    load: impl FnOnce(&[Id]) -> Result<Vec<Model>, E>,
+) -> Result<Vec<Model>, E>
+where
+    Model: Clone + Send + 'static,
+    Ctx: ContextWithCache,
+    Id: Hash + Eq + Clone + Send + 'static,
+    F: Fn(&Model) -> Id,
+{
+    let cache = ctx.cache();
+
+    let mut models = Vec::new();
+    let mut missing_ids = Vec::new();
+    for id in ids {
+        match cache.get::<Model, Id>(key, id) {
+            Some(model) => models.push(model),
+            None => missing_ids.push(id.clone()),
+        }
+    }
+
+    if !missing_ids.is_empty() {
+        let loaded_models = load(&missing_ids)?;
+        for model in &loaded_models {
+            cache.insert(key, id_of(model), model.clone());
+        }
+        models.extend(loaded_models);
+    }
+
+    Ok(models)
+}
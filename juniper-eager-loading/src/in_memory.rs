@@ -0,0 +1,154 @@
+//! In-memory tables for prototyping schemas and testing resolvers without a database.
+//!
+//! The tables count how many times they're read, which makes it easy to check that a query
+//! doesn't trigger N+1 loads. Use [`impl_load_from_for_in_memory`][] to implement
+//! [`LoadFrom`][] for models stored in them.
+//!
+//! [`LoadFrom`]: ../trait.LoadFrom.html
+//! [`impl_load_from_for_in_memory`]: ../macro.impl_load_from_for_in_memory.html
+
+use std::{
+    borrow::Borrow,
+    collections::HashMap,
+    hash::Hash,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A table of values keyed by their id.
+///
+/// Every lookup increments the reads count, regardless of how many values it returns.
+#[derive(Debug)]
+pub struct Table<K: Hash + Eq, V> {
+    map: HashMap<K, V>,
+    count: AtomicUsize,
+    name: &'static str,
+}
+
+impl<K: Hash + Eq, V> Table<K, V> {
+    /// Create a new empty table.
+    pub fn new(name: &'static str) -> Self {
+        Table {
+            map: HashMap::default(),
+            count: AtomicUsize::default(),
+            name,
+        }
+    }
+
+    /// The name of the table.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get the value with the given id.
+    pub fn get<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.increment_reads_count();
+        self.map.get(k)
+    }
+
+    /// Get the values with the given ids, skipping the ids that aren't in the table.
+    ///
+    /// Unlike calling [`get`][] for each id, this counts as a single read.
+    ///
+    /// [`get`]: #method.get
+    pub fn get_many<'a, Q, I>(&self, ids: I) -> Vec<&V>
+    where
+        K: Borrow<Q>,
+        Q: 'a + ?Sized + Hash + Eq,
+        I: IntoIterator<Item = &'a Q>,
+    {
+        self.increment_reads_count();
+        ids.into_iter().filter_map(|id| self.map.get(id)).collect()
+    }
+
+    /// Get the value with the given id mutably.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: ?Sized + Hash + Eq,
+    {
+        self.increment_reads_count();
+        self.map.get_mut(k)
+    }
+
+    /// Get all the values in the table, in no particular order.
+    pub fn all_values(&self) -> Vec<&V> {
+        self.increment_reads_count();
+        self.map.values().collect()
+    }
+
+    /// Insert a value, replacing the existing value with the same id.
+    ///
+    /// Inserting doesn't count as a read.
+    pub fn insert(&mut self, k: K, v: V) -> Option<V> {
+        self.map.insert(k, v)
+    }
+
+    /// The number of times the table has been read.
+    pub fn reads_count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Increment the reads count. Useful when implementing [`LoadFrom`][] by hand.
+    ///
+    /// [`LoadFrom`]: ../trait.LoadFrom.html
+    pub fn increment_reads_count(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// A table of values without ids, such as a join table.
+///
+/// Every read increments the reads count, like [`Table`][].
+///
+/// [`Table`]: struct.Table.html
+#[derive(Debug)]
+pub struct List<V> {
+    values: Vec<V>,
+    count: AtomicUsize,
+    name: &'static str,
+}
+
+impl<V> List<V> {
+    /// Create a new empty list.
+    pub fn new(name: &'static str) -> Self {
+        List {
+            values: Vec::new(),
+            count: AtomicUsize::default(),
+            name,
+        }
+    }
+
+    /// The name of the list.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Get all the values in the list, in the order they were pushed.
+    pub fn all_values(&self) -> Vec<&V> {
+        self.increment_reads_count();
+        self.values.iter().collect()
+    }
+
+    /// Add a value to the end of the list.
+    ///
+    /// Pushing doesn't count as a read.
+    pub fn push(&mut self, v: V) {
+        self.values.push(v);
+    }
+
+    /// The number of times the list has been read.
+    pub fn reads_count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+
+    /// Increment the reads count. Useful when implementing [`LoadFrom`][] by hand.
+    ///
+    /// [`LoadFrom`]: ../trait.LoadFrom.html
+    pub fn increment_reads_count(&self) {
+        self.count.fetch_add(1, Ordering::SeqCst);
+    }
+}
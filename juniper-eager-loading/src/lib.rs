@@ -24,7 +24,15 @@
 //!     - [Attributes supported on all associations](#attributes-supported-on-all-associations)
 //! - [Eager loading interfaces or unions](#eager-loading-interfaces-or-unions)
 //! - [Eager loading fields that take arguments](#eager-loading-fields-that-take-arguments)
+//! - [Pagination](#pagination)
+//! - [Aggregates](#aggregates)
+//! - [Trees](#trees)
+//! - [Composite keys](#composite-keys)
+//! - [Caching](#caching)
+//! - [Async eager loading](#async-eager-loading)
+//! - [Concurrent eager loading](#concurrent-eager-loading)
 //! - [Diesel helper](#diesel-helper)
+//! - [Testing for N+1 regressions](#testing-for-n1-regressions)
 //! - [When your GraphQL schema doesn't match your database schema](#when-your-graphql-schema-doesnt-match-your-database-schema)
 //!
 //! # What is N+1 query bugs?
@@ -378,8 +386,20 @@
 //! | `model` | The model type behind your GraphQL struct | `models::{name of struct}` | `model = crate::db::models::User` |
 //! | `id` | Which id type does your app use? | `i32` | `id = UUID` |
 //! | `root_model_field` | The name of the field has holds the backing model | `{name of struct}` in snakecase. | `root_model_field = user` |
-//! | `primary_key_field` | The field that holds the primary key of the model. This field is only used by code generated for `#[has_many]` and `#[has_many_through]` associations. | `id` | `primary_key_field = identifier` |
+//! | `primary_key_field` | The field that holds the primary key of the model. This field is only used by code generated for `#[has_many]` and `#[has_many_through]` associations. A tuple of fields can be used for a [composite key](#composite-keys). | `id` | `primary_key_field = identifier` |
 //! | `print` | If set it will print the generated implementation of `EagerLoading` | Not set | `print` |
+//! | `cache` | If set `HasOne` and `OptionHasOne` associations will look up models in a [`Cache`][] before loading them. More info [here](#caching). | Not set | `cache` |
+//! | `asynchronous` | If set [`AsyncEagerLoading`][] will be implemented instead of [`EagerLoading`][]. Requires the `async` cargo feature. More info [here](#async-eager-loading). | Not set | `asynchronous` |
+//! | `record_loads` | If set every call to [`LoadFrom`][], [`LoadAggregate`][], and [`LoadTree`][] is logged in a [`LoadRecorder`][] so tests can assert on the number of loads. More info [here](#testing-for-n1-regressions). | Not set | `record_loads` |
+//! | `concurrent` | If set sibling associations will be loaded on separate threads. Requires the `concurrent` cargo feature. More info [here](#concurrent-eager-loading). | Not set | `concurrent` |
+//! | `remote` | Implement the traits for another enum, such as one generated by juniper-from-schema. Only supported on enums. More info [here](#eager-loading-interfaces-or-unions). | Not set | `remote = SearchResult` |
+//!
+//! [`Cache`]: struct.Cache.html
+//! [`AsyncEagerLoading`]: trait.AsyncEagerLoading.html
+//! [`LoadFrom`]: trait.LoadFrom.html
+//! [`LoadAggregate`]: trait.LoadAggregate.html
+//! [`LoadTree`]: trait.LoadTree.html
+//! [`LoadRecorder`]: testing/struct.LoadRecorder.html
 //!
 //! # Associations
 //!
@@ -395,10 +415,18 @@
 //! - [`OptionHasOne`](struct.OptionHasOne.html)
 //! - [`HasMany`](struct.HasMany.html)
 //! - [`HasManyThrough`](struct.HasManyThrough.html)
+//! - [`HasConnection`](struct.HasConnection.html)
+//! - [`PolymorphicHasOne`](struct.PolymorphicHasOne.html)
+//! - [`HasManyFromIds`](struct.HasManyFromIds.html)
 //!
-//! For each field of your GraphQL struct that is one of these four types the trait
+//! For each field of your GraphQL struct that is one of these types the trait
 //! [`EagerLoadChildrenOfType`][] will be implemented by `#[derive(EagerLoading)]`.
 //!
+//! If many parents share the same children, for example thousands of users living in a handful of
+//! countries, each parent will get its own copy of the child. Use
+//! [`SharedHasOne`](struct.SharedHasOne.html) or [`SharedHasMany`](struct.SharedHasMany.html)
+//! instead to store the children in an `Arc` so each child is only built once.
+//!
 //! ## Attributes supported on all associations
 //!
 //! These are the attributes that are supported on all associations.
@@ -430,6 +458,17 @@
 //!
 //! [`EagerLoadChildrenOfType::FieldArguments`]: trait.EagerLoadChildrenOfType.html#associatedtype.FieldArguments
 //!
+//! ### `chunk_size`
+//!
+//! The largest number of ids or models to pass to [`LoadFrom`][] at once. If there are more,
+//! [`LoadFrom::load`][] is called once per chunk and the results are concatenated before the
+//! children are matched with their parents. This overrides [`LoadFrom::chunk_size`][].
+//!
+//! For example `#[has_many(root_model_field = car, chunk_size = 500)]`.
+//!
+//! [`LoadFrom::load`]: trait.LoadFrom.html#tymethod.load
+//! [`LoadFrom::chunk_size`]: trait.LoadFrom.html#method.chunk_size
+//!
 //! # Eager loading interfaces or unions
 //!
 //! Eager loading interfaces or unions is possible but it will require calling `.downcast()` on the
@@ -437,6 +476,40 @@
 //! info](https://docs.rs/juniper-from-schema/0.4.0/juniper_from_schema/#downcasting-for-interface-and-union-querytrails)
 //! fo more info.
 //!
+//! `#[derive(EagerLoading)]` can do that for you on an enum where each variant wraps a type that
+//! implements [`EagerLoading`][]. Its models are an enum with the same variants, by default
+//! `models::{name of enum}`. The models are split up by variant, each variant is eager loaded
+//! once with the downcasted `QueryTrail`, and the nodes are returned in the order of the models.
+//!
+//! Since juniper-from-schema generates the enums for interfaces and unions, you derive on a
+//! mirror of the enum and point `remote` to the generated one:
+//!
+//! ```ignore
+//! mod models {
+//!     #[derive(Clone)]
+//!     pub enum SearchResult {
+//!         Post(Post),
+//!         Issue(Issue),
+//!     }
+//! }
+//!
+//! #[derive(EagerLoading)]
+//! #[eager_loading(context = Context, error = Box<dyn Error>, remote = SearchResult)]
+//! enum SearchResultDef {
+//!     Post(Post),
+//!     Issue(Issue),
+//! }
+//! ```
+//!
+//! The enum can then be used as the child of an association. For `#[has_many]` set
+//! `foreign_key_method` to a method on the enum that returns the foreign key of each variant.
+//! The generated enums don't implement `Clone`, so store them in a
+//! [`SharedHasMany`](struct.SharedHasMany.html).
+//!
+//! If the type of a child is stored next to its id, such as `commentable_type` and
+//! `commentable_id`, use [`PolymorphicHasOne`](struct.PolymorphicHasOne.html). It loads each type
+//! of child separately and downcasts the `QueryTrail` for you.
+//!
 //! # Eager loading fields that take arguments
 //!
 //! If you have a GraphQL field that takes arguments you probably have to consider them for eager
@@ -475,16 +548,282 @@
 //! [`EagerLoadChildrenOfType::FieldArguments`]: trait.EagerLoadChildrenOfType.html#associatedtype.FieldArguments
 //! [`LoadFrom`]: trait.LoadFrom.html
 //!
+//! # Pagination
+//!
+//! `HasMany` and `HasManyThrough` associations can be paginated by adding `paginate` to the
+//! attribute. The field must take `first` and `after` arguments, and because juniper-from-schema
+//! only generates argument methods that don't panic for arguments with default values they should
+//! have defaults:
+//!
+//! ```graphql
+//! type User {
+//!     cars(first: Int = 100, after: String = ""): [Car!]!
+//! }
+//! ```
+//!
+//! ```ignore
+//! #[has_many(root_model_field = car, field_arguments = UserCarsArgs, paginate)]
+//! cars: HasMany<Car>,
+//! ```
+//!
+//! The pagination is applied to each parent separately, so every user gets their first 100 cars.
+//! `after` takes a cursor made with [`encode_cursor`][] from the position of a child in the list
+//! of its parent.
+//!
+//! If the association also has `order_by`, the children are ordered before they're paginated.
+//! The Diesel macros pick the first children of each parent by primary key unless they're given
+//! a matching `order_by = column`.
+//!
+//! For Relay style connections with cursors and `pageInfo` use
+//! [`HasConnection`](struct.HasConnection.html) instead.
+//!
+//! [`LoadFrom`][] still receives all the parents at once and may return more children than needed.
+//! The Diesel macros support `paginate = ArgsType` which uses a `ROW_NUMBER()` window function to
+//! only load the first [`Pagination::limit`][] children of each parent.
+//!
+//! [`encode_cursor`]: fn.encode_cursor.html
+//! [`Pagination::limit`]: struct.Pagination.html#method.limit
+//!
+//! # Aggregates
+//!
+//! Sometimes you only need something computed from the children, such as how many there are. A
+//! field of type [`HasAggregate`][] (or [`HasCount`][] for counts) is loaded with one call to
+//! [`LoadAggregate`][] for all the parents:
+//!
+//! ```ignore
+//! #[has_aggregate(aggregate = CarCount)]
+//! car_count: HasCount,
+//! ```
+//!
+//! The field is only loaded if it is part of the query. Parents without a value get
+//! `Default::default()`, so a user without cars has a count of `0`. See [`HasAggregate`][] for
+//! more details.
+//!
+//! [`HasAggregate`]: struct.HasAggregate.html
+//! [`HasCount`]: type.HasCount.html
+//! [`LoadAggregate`]: trait.LoadAggregate.html
+//!
+//! # Trees
+//!
+//! Self-referential associations, such as the children of a category, are eager loaded like any
+//! other association, so `categories { children { children { id } } }` makes one call to
+//! [`LoadFrom`][] for each level of the query. Marking the association with `tree` makes
+//! `#[derive(EagerLoading)]` load every level of the tree that the query asks for before building
+//! any nodes:
+//!
+//! ```ignore
+//! #[has_many(root_model_field = category, foreign_key_field = parent_id, tree)]
+//! children: HasMany<Category>,
+//!
+//! #[option_has_one(root_model_field = category, tree)]
+//! parent: OptionHasOne<Box<Category>>,
+//! ```
+//!
+//! The levels are still loaded with one [`LoadFrom`][] call each, which every parent on the level
+//! shares. Use `tree_loader` with a type that implements [`LoadTree`][] to load all the levels
+//! at once, for example with a `WITH RECURSIVE` query. See [`EagerLoadTree`][] for more details.
+//!
+//! [`LoadTree`]: trait.LoadTree.html
+//! [`EagerLoadTree`]: trait.EagerLoadTree.html
+//!
+//! # Composite keys
+//!
+//! `primary_key_field`, `foreign_key_field`, `child_primary_key_field`,
+//! `child_primary_key_field_on_join_model`, and `key_field` all accept a tuple of fields for
+//! models whose keys span several columns:
+//!
+//! ```ignore
+//! #[derive(Clone, EagerLoading)]
+//! #[eager_loading(
+//!     context = Context,
+//!     error = diesel::result::Error,
+//!     id = (i32, i32),
+//!     primary_key_field = (tenant_id, id),
+//! )]
+//! pub struct Car {
+//!     car: models::Car,
+//!     #[has_one(
+//!         foreign_key_field = (tenant_id, user_id),
+//!         child_primary_key_field = (tenant_id, id),
+//!     )]
+//!     user: HasOne<User>,
+//! }
+//! ```
+//!
+//! The ids passed to [`LoadFrom`][] are then tuples, such as `(i32, i32)`, and the Diesel macros
+//! support [tuples of columns](macro.impl_load_from_for_diesel_pg.html#syntax). Both sides of an
+//! association must have the same number of fields. Composite keys are not supported by
+//! `#[option_has_one]` or `foreign_key_optional`.
+//!
+//! # Caching
+//!
+//! Each level of nesting in a query is eager loaded separately, so the same model might be loaded
+//! more than once. For example `users { country { cities { country { id } } } }` will load the
+//! same countries twice.
+//!
+//! To avoid that, store a [`Cache`][] in your Juniper context, implement [`ContextWithCache`][]
+//! for the context, and add `cache` to `#[eager_loading]`:
+//!
+//! ```ignore
+//! pub struct Context {
+//!     db: DbConnection,
+//!     cache: juniper_eager_loading::Cache,
+//! }
+//!
+//! impl juniper_eager_loading::ContextWithCache for Context {
+//!     fn cache(&self) -> &juniper_eager_loading::Cache {
+//!         &self.cache
+//!     }
+//! }
+//!
+//! #[derive(Clone, EagerLoading)]
+//! #[eager_loading(context = Context, error = Box<dyn std::error::Error>, cache)]
+//! pub struct City {
+//!     city: models::City,
+//!     #[has_one(default)]
+//!     country: HasOne<Country>,
+//! }
+//! ```
+//!
+//! `HasOne` and `OptionHasOne` associations will then only call [`LoadFrom`][] with the ids that
+//! haven't been loaded yet. Models are keyed by their type and the field and value they were
+//! looked up by, so a model found through `child_primary_key_field = iso_code` is cached
+//! separately from the same model found by its id. The model type has to be
+//! `Clone + Send + 'static`. Associations that take arguments cannot be cached.
+//!
+//! Since models are never evicted from the cache you should create a new one for each request.
+//!
+//! [`Cache`]: struct.Cache.html
+//! [`ContextWithCache`]: trait.ContextWithCache.html
+//! [`LoadFrom`]: trait.LoadFrom.html
+//!
+//! # Async eager loading
+//!
+//! With the `async` cargo feature enabled you can use [`AsyncLoadFrom`][] to load models with an
+//! async database client. Add `asynchronous` to `#[eager_loading]` and the derive will implement
+//! [`AsyncEagerLoading`][] and [`AsyncEagerLoadChildrenOfType`][] instead of the blocking traits:
+//!
+//! ```ignore
+//! #[derive(Clone, EagerLoading)]
+//! #[eager_loading(context = Context, error = MyError, asynchronous)]
+//! pub struct User {
+//!     user: models::User,
+//!     #[has_one(default)]
+//!     country: HasOne<Country>,
+//!     #[has_many(root_model_field = car)]
+//!     cars: HasMany<Car>,
+//! }
+//!
+//! let users = User::eager_load_each(&user_models, ctx, trail).await?;
+//! ```
+//!
+//! Sibling associations don't depend on each other, so `country` and `cars` are loaded
+//! concurrently. The children are only stored on the parents once all the siblings have finished
+//! loading, and the first error is returned if any of them fail.
+//!
+//! Every type in the query must use `asynchronous`, and the context, error, and model types must
+//! be `Send + Sync`. `asynchronous` cannot be combined with `cache`.
+//!
+//! If you're using sqlx, [`impl_load_from_for_sqlx`][] implements [`AsyncLoadFrom`][] for your
+//! models.
+//!
+//! [`AsyncLoadFrom`]: trait.AsyncLoadFrom.html
+//! [`impl_load_from_for_sqlx`]: macro.impl_load_from_for_sqlx.html
+//! [`AsyncEagerLoading`]: trait.AsyncEagerLoading.html
+//! [`AsyncEagerLoadChildrenOfType`]: trait.AsyncEagerLoadChildrenOfType.html
+//!
+//! # Concurrent eager loading
+//!
+//! By default the associations of a type are loaded one after the other, so a `User` with four
+//! associations has to wait for four round trips to the database. With the `concurrent` cargo
+//! feature enabled you can add `concurrent` to `#[eager_loading]` to load sibling associations on
+//! separate threads, using [rayon](https://docs.rs/rayon):
+//!
+//! ```ignore
+//! #[derive(Clone, EagerLoading)]
+//! #[eager_loading(context = Context, error = MyError, concurrent)]
+//! pub struct User {
+//!     user: models::User,
+//!     #[has_one(default)]
+//!     country: HasOne<Country>,
+//!     #[has_many(root_model_field = car)]
+//!     cars: HasMany<Car>,
+//! }
+//! ```
+//!
+//! Each association is loaded with [`EagerLoadChildrenOfType::load_and_eager_load_children`][]
+//! and once they have all finished the children are stored on the parents with
+//! [`EagerLoadChildrenOfType::assign_children`][]. If any of them fail the error of the first
+//! failing field, in the order they're declared, is returned, just like when loading them one
+//! after the other.
+//!
+//! Since the context, models, and loaded children are shared between threads they have to be
+//! `Send + Sync`, as does the error type. Your context should therefore hold a connection pool
+//! rather than a single connection.
+//!
+//! [`EagerLoadChildrenOfType::load_and_eager_load_children`]: trait.EagerLoadChildrenOfType.html#method.load_and_eager_load_children
+//! [`EagerLoadChildrenOfType::assign_children`]: trait.EagerLoadChildrenOfType.html#method.assign_children
+//!
 //! # Diesel helper
 //!
 //! Implementing [`LoadFrom`][] for lots of model types might involve lots of boilerplate. If
 //! you're using Diesel it is recommend that you use one of [the macros to
 //! generate](index.html#macros) implementations.
 //!
+//! For prototyping a schema or testing resolvers without a database you can store your models in
+//! the tables of the [`in_memory`][] module and use [`impl_load_from_for_in_memory`][]. The tables
+//! count how many times they're read, so tests can check that a query doesn't cause N+1 loads.
+//!
 //! [`LoadFrom`]: trait.LoadFrom.html
+//! [`in_memory`]: in_memory/index.html
+//! [`impl_load_from_for_in_memory`]: macro.impl_load_from_for_in_memory.html
 //! [Diesel]: https://diesel.rs
 //! [`EagerLoadChildrenOfType`]: trait.EagerLoadChildrenOfType.html
 //!
+//! # Testing for N+1 regressions
+//!
+//! The [`testing`][] module can record the loads a query makes, so tests can check that a change
+//! doesn't reintroduce N+1 loads. Store a [`LoadRecorder`][] in your Juniper context, implement
+//! [`ContextWithLoadRecorder`][] for the context, and add `record_loads` to `#[eager_loading]` on
+//! every type in the query:
+//!
+//! ```ignore
+//! pub struct Context {
+//!     db: DbConnection,
+//!     load_recorder: juniper_eager_loading::testing::LoadRecorder,
+//! }
+//!
+//! impl juniper_eager_loading::testing::ContextWithLoadRecorder for Context {
+//!     fn load_recorder(&self) -> &juniper_eager_loading::testing::LoadRecorder {
+//!         &self.load_recorder
+//!     }
+//! }
+//!
+//! #[derive(Clone, EagerLoading)]
+//! #[eager_loading(context = Context, error = Box<dyn std::error::Error>, record_loads)]
+//! pub struct User {
+//!     user: models::User,
+//!     #[has_one(default)]
+//!     country: HasOne<Country>,
+//! }
+//! ```
+//!
+//! After running a query, assert on the recorder with methods such as
+//! [`assert_at_most_one_load_per_type`][] and [`assert_total_loads_at_most`][]. A failing
+//! assertion panics with the log of every recorded call, including the model type, the number of
+//! keys, and the field arguments formatted with `Debug`.
+//!
+//! Aggregates, trees, and loads through the cache are recorded as well. Hand-written code can
+//! call [`load_and_record`][] instead of [`LoadFrom::load`][] to be included in the log.
+//!
+//! [`testing`]: testing/index.html
+//! [`load_and_record`]: testing/fn.load_and_record.html
+//! [`LoadFrom::load`]: trait.LoadFrom.html#tymethod.load
+//! [`LoadRecorder`]: testing/struct.LoadRecorder.html
+//! [`ContextWithLoadRecorder`]: testing/trait.ContextWithLoadRecorder.html
+//! [`assert_at_most_one_load_per_type`]: testing/struct.LoadRecorder.html#method.assert_at_most_one_load_per_type
+//! [`assert_total_loads_at_most`]: testing/struct.LoadRecorder.html#method.assert_total_loads_at_most
+//!
 //! # When your GraphQL schema doesn't match your database schema
 //!
 //! This library supports eager loading most kinds of association setups, however it probably
@@ -503,7 +842,7 @@
 //! [juniper-from-schema]: https://github.com/davidpdrsn/juniper-from-schema
 
 #![doc(html_root_url = "https://docs.rs/juniper-eager-loading/0.5.1")]
-#![allow(clippy::single_match, clippy::type_complexity)]
+#![allow(clippy::single_match, clippy::type_complexity, clippy::derivable_impls)]
 // #![deny(
 //     missing_docs,
 //     dead_code,
@@ -520,21 +859,59 @@
 //     unused_variables
 // )]
 
+mod aggregate;
 mod association;
+#[cfg(feature = "async")]
+mod async_eager_loading;
+mod cache;
+pub mod in_memory;
 mod macros;
+mod pagination;
+pub mod testing;
+mod tree;
 
 use juniper_from_schema::{QueryTrail, Walked};
-use std::{hash::Hash, mem::transmute_copy};
+use std::{cmp::Ordering, collections::HashMap, hash::Hash, sync::Arc};
 
-pub use association::Association;
+pub use aggregate::{load_aggregate, HasAggregate, HasCount, LoadAggregate};
+pub use association::{Association, CloneChild};
+#[cfg(feature = "async")]
+pub use async_eager_loading::{
+    async_load_from_in_chunks, AsyncEagerLoadChildrenOfType, AsyncEagerLoading, AsyncLoadFrom,
+};
+pub use cache::{load_cached, Cache, ContextWithCache};
 pub use juniper_eager_loading_code_gen::EagerLoading;
+pub use pagination::{decode_cursor, encode_cursor, Connection, Edge, PageInfo, Pagination};
+pub use tree::{eager_load_tree, load_tree_breadth_first, EagerLoadTree, LoadTree};
+
+#[doc(hidden)]
+#[cfg(feature = "async")]
+pub mod async_support {
+    pub use async_trait::async_trait;
+    pub use futures::try_join;
+}
+
+#[doc(hidden)]
+#[cfg(feature = "concurrent")]
+pub mod concurrent_support {
+    pub use rayon::scope;
+}
 
 #[doc(hidden)]
 pub mod proc_macros {
     pub use juniper_eager_loading_code_gen::{
         impl_load_from_for_diesel_mysql, impl_load_from_for_diesel_pg,
-        impl_load_from_for_diesel_sqlite,
+        impl_load_from_for_diesel_sqlite, impl_load_from_for_in_memory, impl_load_from_for_sqlx,
     };
+
+    /// Call the `filter` given to the Diesel macros with the field arguments. Passing the closure
+    /// through a function lets Rust infer the type of its argument.
+    pub fn field_args_filter<'a, Args, F, R>(field_args: &'a Args, filter: F) -> R
+    where
+        F: FnOnce(&'a Args) -> R,
+    {
+        filter(field_args)
+    }
 }
 
 /// Re-exports the traits needed for doing eager loading. Meant to be glob imported.
@@ -559,6 +936,10 @@ pub enum AssociationType {
     HasMany,
     /// There was an error with a [`HasManyThrough`](struct.HasManyThrough.html).
     HasManyThrough,
+    /// There was an error with a [`HasAggregate`](struct.HasAggregate.html).
+    HasAggregate,
+    /// There was an error with a [`PolymorphicHasOne`](struct.PolymorphicHasOne.html).
+    PolymorphicHasOne,
 }
 
 /// A non-optional "has one" association.
@@ -583,6 +964,23 @@ pub enum AssociationType {
 ///
 /// You can find a complete example of `HasOne` [here](https://github.com/davidpdrsn/juniper-eager-loading/tree/master/examples/has_one.rs).
 ///
+/// # Foreign key on the child
+///
+/// If the foreign key lives on the child instead, such as a profile with a `user_id` where each
+/// user has at most one profile, mark the association with `inverse`. The children are then
+/// loaded with `LoadFrom<ParentModel>`, like a [`HasMany`][], and paired up with the parents by
+/// the foreign key on the child.
+///
+/// ```ignore
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+/// pub struct User {
+///     user: models::User,
+///     #[has_one(inverse, root_model_field = profile)]
+///     profile: HasOne<Profile>,
+/// }
+/// ```
+///
 /// # Attributes
 ///
 /// | Name | Description | Default | Example |
@@ -592,6 +990,9 @@ pub enum AssociationType {
 /// | `graphql_field` | The name of this field in your GraphQL schema | `{name of field}` | `graphql_field = country` |
 /// | `child_primary_key_field` | The name of the primary key field on the associated model | `id` | `child_primary_key_field = identifier` |
 /// | `default` | Use the default value for all unspecified attributes | N/A | `default` |
+/// | `tree` | The association points to the same type and should be loaded one level of the tree at a time. More info [here](/#trees). | Not set | `tree` |
+/// | `tree_loader` | A type implementing [`LoadTree`](trait.LoadTree.html) to load all the levels of the tree at once. Implies `tree`. | Not set | `tree_loader = ManagerChain` |
+/// | `inverse` | The foreign key lives on the child. `foreign_key_field` is then the field on the child and defaults to `{name of struct}_id` | Not set | `inverse` |
 ///
 /// Additionally it also supports the attributes `print`, `skip`, and `field_arguments`. See the [root model
 /// docs](/#attributes-supported-on-all-associations) for more into on those.
@@ -599,12 +1000,13 @@ pub enum AssociationType {
 /// # Errors
 ///
 /// When calling [`try_unwrap`][] to get the loaded value it will return an error if the value has
-/// not been loaded, or if the load failed.
+/// not been loaded, if the load failed, or if more than one child was found.
 ///
 /// For example if a user has a `country_id` of `10` but there is no `Country` with id `10` then
 /// [`try_unwrap`][] will return an error.
 ///
 /// [`try_unwrap`]: struct.HasOne.html#method.try_unwrap
+/// [`HasMany`]: struct.HasMany.html
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct HasOne<T>(HasOneInner<T>);
 
@@ -617,7 +1019,7 @@ impl<T> Default for HasOne<T> {
 impl<T> HasOne<T> {
     /// Borrow the loaded value. If the value has not been loaded it will return an error.
     pub fn try_unwrap(&self) -> Result<&T, Error> {
-        self.0.try_unwrap()
+        self.0.try_unwrap(AssociationType::HasOne)
     }
 }
 
@@ -626,6 +1028,7 @@ enum HasOneInner<T> {
     Loaded(T),
     NotLoaded,
     LoadFailed,
+    TooManyChildren,
 }
 
 impl<T> Default for HasOneInner<T> {
@@ -635,11 +1038,23 @@ impl<T> Default for HasOneInner<T> {
 }
 
 impl<T> HasOneInner<T> {
-    fn try_unwrap(&self) -> Result<&T, Error> {
+    fn try_unwrap(&self, association_type: AssociationType) -> Result<&T, Error> {
         match self {
             HasOneInner::Loaded(inner) => Ok(inner),
-            HasOneInner::NotLoaded => Err(Error::NotLoaded(AssociationType::HasOne)),
-            HasOneInner::LoadFailed => Err(Error::LoadFailed(AssociationType::HasOne)),
+            HasOneInner::NotLoaded => Err(Error::NotLoaded(association_type)),
+            HasOneInner::LoadFailed => Err(Error::LoadFailed(association_type)),
+            HasOneInner::TooManyChildren => Err(Error::TooManyChildren(association_type)),
+        }
+    }
+
+    fn loaded(&mut self, child: T) {
+        match self {
+            HasOneInner::Loaded(_) | HasOneInner::TooManyChildren => {
+                *self = HasOneInner::TooManyChildren;
+            }
+            _ => {
+                *self = HasOneInner::Loaded(child);
+            }
         }
     }
 
@@ -670,24 +1085,34 @@ impl<T> HasOneInner<T> {
 ///
 /// # Errors
 ///
-/// [`try_unwrap`][] will never error. If the association wasn't loaded or wasn't found it will
-/// return `Ok(None)`.
+/// If the association wasn't loaded or wasn't found [`try_unwrap`][] will return `Ok(None)`. Like
+/// [`HasOne`][] it will return an error if more than one child was found, such as with `inverse`.
 ///
 /// [`try_unwrap`]: struct.OptionHasOne.html#method.try_unwrap
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub struct OptionHasOne<T>(Option<T>);
+pub struct OptionHasOne<T> {
+    child: Option<T>,
+    too_many_children: bool,
+}
 
 impl<T> Default for OptionHasOne<T> {
     fn default() -> Self {
-        OptionHasOne(None)
+        OptionHasOne {
+            child: None,
+            too_many_children: false,
+        }
     }
 }
 
 impl<T> OptionHasOne<T> {
     /// Borrow the loaded value. If the value has not been loaded it will return `Ok(None)`. It
-    /// will not error.
+    /// will only error if more than one child was found.
     pub fn try_unwrap(&self) -> Result<&Option<T>, Error> {
-        Ok(&self.0)
+        if self.too_many_children {
+            Err(Error::TooManyChildren(AssociationType::OptionHasOne))
+        } else {
+            Ok(&self.child)
+        }
     }
 }
 
@@ -721,33 +1146,50 @@ impl<T> OptionHasOne<T> {
 /// |---|---|---|---|
 /// | `foreign_key_field` | The name of the foreign key field | `{name of struct}_id` | `foreign_key_field = user_id` |
 /// | `foreign_key_optional` | The foreign key type is optional | Not set | `foreign_key_optional` |
+/// | `foreign_key_method` | A method on the child that returns the foreign key. Used when the child isn't a struct, such as the enum of a union. More info [here](/#eager-loading-interfaces-or-unions). | Not set | `foreign_key_method = user_id` |
 /// | `root_model_field` | The name of the field on the associated GraphQL type that holds the database model | N/A (unless using `skip`) | `root_model_field = car` |
 /// | `graphql_field` | The name of this field in your GraphQL schema | `{name of field}` | `graphql_field = country` |
 /// | `predicate_method` | Method used to filter child associations. This can be used if you only want to include a subset of the models | N/A (attribute is optional) | `predicate_method = a_predicate_method` |
+/// | `paginate` | Only give each parent the children selected by the `first` and `after` arguments of the field. Requires `field_arguments`. More info [here](/#pagination). | Not set | `paginate` |
+/// | `order_by` | How to order the children of each parent. Either a key method on the child model or a closure comparing two child models. | Order returned by `LoadFrom` | `order_by = name` or `order_by = \|a, b\| b.id.cmp(&a.id)` |
+/// | `tree` | The children have the same type as the parent and should be loaded one level of the tree at a time. More info [here](/#trees). | Not set | `tree` |
+/// | `tree_loader` | A type implementing [`LoadTree`](trait.LoadTree.html) to load all the levels of the tree at once. Implies `tree`. | Not set | `tree_loader = CategoryTree` |
 ///
 /// Additionally it also supports the attributes `print`, `skip`, and `field_arguments`. See the [root model
 /// docs](/#attributes-supported-on-all-associations) for more into on those.
 ///
 /// # Errors
 ///
-/// [`try_unwrap`][] will never error. If the association wasn't loaded or wasn't found it will
-/// return `Ok(vec![])`.
+/// If the association wasn't loaded or wasn't found [`try_unwrap`][] will return `Ok(vec![])`.
+/// It only errors if the children don't implement `Clone` and one of them had to be given to more
+/// than one parent, which happens if the same parent is eager loaded twice. See
+/// [`CloneChild`](trait.CloneChild.html).
 ///
 /// [`try_unwrap`]: struct.HasMany.html#method.try_unwrap
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub struct HasMany<T>(Vec<T>);
+pub struct HasMany<T> {
+    children: Vec<T>,
+    child_not_cloneable: bool,
+}
 
 impl<T> Default for HasMany<T> {
     fn default() -> Self {
-        HasMany(Vec::new())
+        HasMany {
+            children: Vec::new(),
+            child_not_cloneable: false,
+        }
     }
 }
 
 impl<T> HasMany<T> {
     /// Borrow the loaded values. If no values have been loaded it will return an empty list.
-    /// It will not return an error.
+    /// It will only error if a child that can't be cloned belonged to more than one parent.
     pub fn try_unwrap(&self) -> Result<&Vec<T>, Error> {
-        Ok(&self.0)
+        if self.child_not_cloneable {
+            Err(Error::ChildNotCloneable(AssociationType::HasMany))
+        } else {
+            Ok(&self.children)
+        }
     }
 }
 
@@ -795,6 +1237,8 @@ impl<T> HasMany<T> {
 /// | `child_primary_key_field` | The field on the child model that holds its primary key | `id` | `foreign_key_field = identifier` |
 /// | `graphql_field` | The name of this field in your GraphQL schema | `{name of field}` | `graphql_field = country` |
 /// | `predicate_method` | Method used to filter child associations. This can be used if you only want to include a subset of the models. This method will be called to filter the join models. | N/A (attribute is optional) | `predicate_method = a_predicate_method` |
+/// | `paginate` | Only give each parent the children selected by the `first` and `after` arguments of the field. Requires `field_arguments`. More info [here](/#pagination). | Not set | `paginate` |
+/// | `order_by` | How to order the children of each parent. Either a key method on the child model, a field on the join model, or a closure comparing two child models. | Order returned by `LoadFrom` | `order_by = employment.started_at` |
 ///
 /// Additionally it also supports the attributes `print`, `skip`, and `field_arguments`. See the [root model
 /// docs](/#attributes-supported-on-all-associations) for more into on those.
@@ -822,6 +1266,362 @@ impl<T> HasManyThrough<T> {
     }
 }
 
+/// A "has many" association where the parent holds the ids of its children.
+///
+/// Imagine you have these models:
+///
+/// ```
+/// struct Post {
+///     id: i32,
+///     tag_ids: Vec<i32>,
+/// }
+///
+/// struct Tag {
+///     id: i32,
+/// }
+/// ```
+///
+/// This is how an array column such as `tag_ids INTEGER[]` is used instead of a join table. The
+/// ids of all the posts are collected, deduplicated, and loaded with one call to
+/// `LoadFrom<i32>`, the same as for a [`HasOne`][]. Each post then gets its tags in the order of
+/// its `tag_ids`, and an id that appears twice gives the post the tag twice.
+///
+/// ```ignore
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+/// pub struct Post {
+///     post: models::Post,
+///     #[has_many_from_ids(root_model_field = tag)]
+///     tags: HasManyFromIds<Tag>,
+/// }
+/// ```
+///
+/// # Attributes
+///
+/// | Name | Description | Default | Example |
+/// |---|---|---|---|
+/// | `foreign_key_field` | The name of the field holding the ids of the children | `{name of contained type}_ids` in snakecase | `foreign_key_field = tag_ids` |
+/// | `child_primary_key_field` | The name of the primary key field on the associated model | `id` | `child_primary_key_field = identifier` |
+/// | `root_model_field` | The name of the field on the associated GraphQL type that holds the database model | N/A (unless using `skip`) | `root_model_field = tag` |
+/// | `graphql_field` | The name of this field in your GraphQL schema | `{name of field}` | `graphql_field = tags` |
+///
+/// Additionally it also supports the attributes `print`, `skip`, and `field_arguments`. See the [root model
+/// docs](/#attributes-supported-on-all-associations) for more into on those.
+///
+/// # Errors
+///
+/// [`try_unwrap`][] will never error. Ids without a child are skipped, and if the association
+/// wasn't loaded it will return `Ok(vec![])`.
+///
+/// [`HasOne`]: struct.HasOne.html
+/// [`try_unwrap`]: struct.HasManyFromIds.html#method.try_unwrap
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct HasManyFromIds<T>(Vec<T>);
+
+impl<T> Default for HasManyFromIds<T> {
+    fn default() -> Self {
+        HasManyFromIds(Vec::new())
+    }
+}
+
+impl<T> HasManyFromIds<T> {
+    /// Borrow the loaded values. If no values have been loaded it will return an empty list.
+    /// It will not return an error.
+    pub fn try_unwrap(&self) -> Result<&Vec<T>, Error> {
+        Ok(&self.0)
+    }
+}
+
+/// A [`HasOne`][] association where the child is shared between all the parents it belongs to.
+///
+/// [`HasOne`][] gives each parent its own copy of the child, including everything that was eager
+/// loaded for it. `SharedHasOne` stores the child in an `Arc` instead, so a child that belongs to
+/// many parents is only built once and no clones are made.
+///
+/// # Attributes
+///
+/// It supports the same attributes as [`HasOne`], which are set with `#[has_one(...)]`.
+///
+/// ```ignore
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+/// pub struct User {
+///     user: models::User,
+///     #[has_one(default)]
+///     country: SharedHasOne<Country>,
+/// }
+/// ```
+///
+/// # Errors
+///
+/// [`try_unwrap`][] errors in the same cases as [`HasOne::try_unwrap`][].
+///
+/// [`HasOne`]: struct.HasOne.html
+/// [`try_unwrap`]: struct.SharedHasOne.html#method.try_unwrap
+/// [`HasOne::try_unwrap`]: struct.HasOne.html#method.try_unwrap
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct SharedHasOne<T>(HasOneInner<Arc<T>>);
+
+// Implemented manually so `T` doesn't have to implement `Clone`.
+impl<T> Clone for SharedHasOne<T> {
+    fn clone(&self) -> Self {
+        SharedHasOne(self.0.clone())
+    }
+}
+
+impl<T> Default for SharedHasOne<T> {
+    fn default() -> Self {
+        SharedHasOne(HasOneInner::default())
+    }
+}
+
+impl<T> SharedHasOne<T> {
+    /// Borrow the loaded value. If the value has not been loaded it will return an error.
+    pub fn try_unwrap(&self) -> Result<&T, Error> {
+        self.0
+            .try_unwrap(AssociationType::HasOne)
+            .map(|child| &**child)
+    }
+
+    /// Borrow the `Arc` holding the loaded value. If the value has not been loaded it will return
+    /// an error.
+    pub fn try_unwrap_arc(&self) -> Result<&Arc<T>, Error> {
+        self.0.try_unwrap(AssociationType::HasOne)
+    }
+}
+
+/// A [`HasMany`][] association where the children are shared between all the parents they belong
+/// to.
+///
+/// This is the [`HasMany`][] version of [`SharedHasOne`][]. The children are stored in `Arc`s so
+/// they don't have to be cloned if they belong to more than one parent.
+///
+/// # Attributes
+///
+/// It supports the same attributes as [`HasMany`], which are set with `#[has_many(...)]`.
+///
+/// # Errors
+///
+/// [`try_unwrap`][] will never error. If the association wasn't loaded or wasn't found it will
+/// return an empty list.
+///
+/// [`HasMany`]: struct.HasMany.html
+/// [`SharedHasOne`]: struct.SharedHasOne.html
+/// [`try_unwrap`]: struct.SharedHasMany.html#method.try_unwrap
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct SharedHasMany<T>(Vec<Arc<T>>);
+
+// Implemented manually so `T` doesn't have to implement `Clone`.
+impl<T> Clone for SharedHasMany<T> {
+    fn clone(&self) -> Self {
+        SharedHasMany(self.0.clone())
+    }
+}
+
+impl<T> Default for SharedHasMany<T> {
+    fn default() -> Self {
+        SharedHasMany(Vec::new())
+    }
+}
+
+impl<T> SharedHasMany<T> {
+    /// Borrow the loaded values. If no values have been loaded it will return an empty list.
+    /// It will not return an error.
+    ///
+    /// The return type matches fields using `@juniper(ownership: "as_ref")` in
+    /// [juniper-from-schema](https://docs.rs/juniper-from-schema).
+    pub fn try_unwrap(&self) -> Result<Vec<&T>, Error> {
+        Ok(self.0.iter().map(|child| &**child).collect())
+    }
+
+    /// Borrow the `Arc`s holding the loaded values. It will not return an error.
+    pub fn try_unwrap_arcs(&self) -> Result<&Vec<Arc<T>>, Error> {
+        Ok(&self.0)
+    }
+}
+
+/// A "has one" association where the child can be one of several types.
+///
+/// Imagine you have these models:
+///
+/// ```
+/// struct Comment {
+///     id: i32,
+///     commentable_type: String,
+///     commentable_id: i32,
+/// }
+///
+/// struct Post {
+///     id: i32,
+/// }
+///
+/// struct Issue {
+///     id: i32,
+/// }
+/// ```
+///
+/// `commentable_type` says which table `commentable_id` points into. In GraphQL the child is a
+/// union:
+///
+/// ```graphql
+/// type Comment {
+///     commentable: Commentable!
+/// }
+///
+/// union Commentable = Post | Issue
+/// ```
+///
+/// The parents are grouped by `commentable_type` and the children of each type are loaded with
+/// one call to [`LoadFrom`][] and eager loaded with the [downcasted][] `QueryTrail`. They're then
+/// converted into the enum juniper-from-schema generates for the union:
+///
+/// ```ignore
+/// #[derive(EagerLoading)]
+/// #[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+/// pub struct Comment {
+///     comment: models::Comment,
+///     #[polymorphic_has_one(variants = [Post, Issue])]
+///     commentable: PolymorphicHasOne<Commentable>,
+/// }
+/// ```
+///
+/// `#[derive(EagerLoading)]` implements [`EagerLoadChildrenOfType`][] once for each variant.
+/// Since the trait used for downcasting is generated by juniper-from-schema, it must be in scope
+/// where the struct is defined.
+///
+/// # Attributes
+///
+/// | Name | Description | Default | Example |
+/// |---|---|---|---|
+/// | `variants` | The GraphQL types of the child and the value of the type field for each | N/A | `variants = [Post, Issue = "issue"]` |
+/// | `type_field` | The name of the field holding the type of the child | `{name of field}_type` | `type_field = commentable_type` |
+/// | `foreign_key_field` | The name of the foreign key field | `{name of field}_id` | `foreign_key_field = commentable_id` |
+/// | `graphql_field` | The name of this field in your GraphQL schema | `{name of field}` | `graphql_field = commentable` |
+/// | `child_primary_key_field` | The name of the primary key field on the associated models | `id` | `child_primary_key_field = identifier` |
+///
+/// The value of the type field defaults to the name of the GraphQL type, so `Post` is loaded for
+/// comments with a `commentable_type` of `"Post"`. The model of each variant is expected to be
+/// in the field named after the type, such as `post`, which is the default of
+/// `#[derive(EagerLoading)]`.
+///
+/// Additionally it also supports the attributes `print`, `skip`, and `field_arguments`. See the
+/// [root model docs](/#attributes-supported-on-all-associations) for more into on those.
+///
+/// # Errors
+///
+/// [`try_unwrap`][] errors in the same cases as [`HasOne::try_unwrap`][], which includes parents
+/// with a type that isn't one of the variants.
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [downcasted]: https://docs.rs/juniper-from-schema/0.5.2/juniper_from_schema/#downcasting-for-interface-and-union-querytrails
+/// [`EagerLoadChildrenOfType`]: trait.EagerLoadChildrenOfType.html
+/// [`try_unwrap`]: struct.PolymorphicHasOne.html#method.try_unwrap
+/// [`HasOne::try_unwrap`]: struct.HasOne.html#method.try_unwrap
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct PolymorphicHasOne<T>(HasOneInner<T>);
+
+impl<T> Default for PolymorphicHasOne<T> {
+    fn default() -> Self {
+        PolymorphicHasOne(HasOneInner::default())
+    }
+}
+
+impl<T> PolymorphicHasOne<T> {
+    /// Borrow the loaded value. If the value has not been loaded it will return an error.
+    pub fn try_unwrap(&self) -> Result<&T, Error> {
+        self.0.try_unwrap(AssociationType::PolymorphicHasOne)
+    }
+}
+
+/// A paginated "has many" or "has many through" association in the shape of a [Relay
+/// connection](https://relay.dev/graphql/connections.htm).
+///
+/// Imagine you have this GraphQL schema:
+///
+/// ```graphql
+/// type User {
+///     cars(first: Int = 10, after: String = ""): CarConnection!
+/// }
+///
+/// type CarConnection {
+///     edges: [CarEdge!]!
+///     pageInfo: PageInfo!
+/// }
+///
+/// type CarEdge {
+///     node: Car!
+///     cursor: String!
+/// }
+///
+/// type PageInfo {
+///     hasNextPage: Boolean!
+///     hasPreviousPage: Boolean!
+///     startCursor: String
+///     endCursor: String
+/// }
+/// ```
+///
+/// `HasConnection` loads the children like [`HasMany`][], or like [`HasManyThrough`][] if
+/// `join_model` is set, paginated with the `first` and `after` arguments of the field. Each
+/// parent gets a [`Connection`][] with the [`Edge`][]s of its page and its [`PageInfo`][].
+///
+/// ```ignore
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+/// pub struct User {
+///     user: models::User,
+///     #[has_connection(root_model_field = car, field_arguments = UserCarsArgs)]
+///     cars: HasConnection<Car>,
+///     #[has_connection(join_model = models::Employment, field_arguments = UserCompaniesArgs)]
+///     companies: HasConnection<Company>,
+/// }
+/// ```
+///
+/// Since juniper-from-schema generates the GraphQL types in your crate, `CarConnection`, `CarEdge`,
+/// and `PageInfo` have to be your own types, for example wrapping [`Connection`][], [`Edge`][],
+/// and [`PageInfo`][]. Their accessors return references so they can be returned directly from
+/// resolvers.
+///
+/// # Attributes
+///
+/// It supports the attributes of [`HasMany`][] when `join_model` isn't set, and the attributes
+/// of [`HasManyThrough`][] when it is. `field_arguments` is required and the arguments must
+/// include `first` and `after`. `paginate` is implied.
+///
+/// # Errors
+///
+/// [`try_unwrap`][] will never error. If the association wasn't loaded or wasn't found it will
+/// return an empty connection.
+///
+/// [`HasMany`]: struct.HasMany.html
+/// [`HasManyThrough`]: struct.HasManyThrough.html
+/// [`Connection`]: struct.Connection.html
+/// [`Edge`]: struct.Edge.html
+/// [`PageInfo`]: struct.PageInfo.html
+/// [`try_unwrap`]: struct.HasConnection.html#method.try_unwrap
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct HasConnection<T> {
+    connection: Connection<T>,
+    offset: usize,
+}
+
+impl<T> Default for HasConnection<T> {
+    fn default() -> Self {
+        HasConnection {
+            connection: Connection::default(),
+            offset: 0,
+        }
+    }
+}
+
+impl<T> HasConnection<T> {
+    /// Borrow the loaded connection. If no values have been loaded it will return an empty
+    /// connection. It will not return an error.
+    pub fn try_unwrap(&self) -> Result<&Connection<T>, Error> {
+        Ok(&self.connection)
+    }
+}
+
 /// Perform eager loading for a single association of a GraphQL struct.
 ///
 /// `#[derive(EagerLoading)]` will implement this trait for each [association field][] your GraphQL
@@ -953,7 +1753,7 @@ impl<T> HasManyThrough<T> {
 ///             <Country as EagerLoading>::Model as juniper_eager_loading::LoadFrom<Self::Id>
 ///         >::load(&ids, field_args, ctx)?;
 ///
-///         Ok(juniper_eager_loading::LoadChildrenOutput::ChildModels(children))
+///         Ok(juniper_eager_loading::LoadChildrenOutput::child_models(children))
 ///     }
 ///
 ///     fn is_child_of(
@@ -1041,8 +1841,8 @@ impl<T> HasManyThrough<T> {
 pub trait EagerLoadChildrenOfType<'a, Child, ImplContext, JoinModel = ()>
 where
     Self: EagerLoading,
-    Child: EagerLoading<Context = Self::Context, Error = Self::Error> + EagerLoading + Clone,
-    JoinModel: 'static + Clone + ?Sized,
+    Child: EagerLoading<Context = Self::Context, Error = Self::Error> + EagerLoading,
+    JoinModel: 'static,
 {
     /// The types of arguments the GraphQL field takes. The type used by the code generation can be
     /// customized with [`field_arguments = SomeType`][].
@@ -1079,6 +1879,109 @@ where
     /// ```
     fn association(node: &mut Self) -> &mut dyn Association<Child>;
 
+    /// The key used to look up the children of a parent.
+    ///
+    /// Together with [`child_join_key`][] this allows pairing up parents and children by hashing
+    /// rather than calling [`is_child_of`][] for every combination of parent and child. A parent
+    /// and a child whose keys are equal are still checked with [`is_child_of`][] before being
+    /// paired up, so keys only have to be equal whenever [`is_child_of`][] would return `true`.
+    ///
+    /// The default implementation returns `None` which means every child will be checked with
+    /// [`is_child_of`][].
+    ///
+    /// [`child_join_key`]: trait.EagerLoadChildrenOfType.html#method.child_join_key
+    /// [`is_child_of`]: trait.EagerLoadChildrenOfType.html#tymethod.is_child_of
+    fn parent_join_key(
+        _parent: &Self,
+        _field_args: &Self::FieldArguments,
+        _context: &Self::Context,
+    ) -> Option<JoinKey> {
+        None
+    }
+
+    /// The key used to find the parents of a child. See [`parent_join_key`][] for more details.
+    ///
+    /// If this returns `None` for any child, all children will be checked with
+    /// [`is_child_of`][].
+    ///
+    /// [`parent_join_key`]: trait.EagerLoadChildrenOfType.html#method.parent_join_key
+    /// [`is_child_of`]: trait.EagerLoadChildrenOfType.html#tymethod.is_child_of
+    fn child_join_key(
+        _child: &Child,
+        _join_model: &JoinModel,
+        _field_args: &Self::FieldArguments,
+        _context: &Self::Context,
+    ) -> Option<JoinKey> {
+        None
+    }
+
+    /// How to paginate the children of each parent. `#[derive(EagerLoading)]` implements this for
+    /// associations with the `paginate` attribute.
+    ///
+    /// The default implementation returns `None` which means all children are kept.
+    fn pagination(_field_args: &Self::FieldArguments) -> Option<Pagination> {
+        None
+    }
+
+    /// Compare two children of the same parent. The children of each parent are sorted with this
+    /// after being matched with [`is_child_of`][] and before [`pagination`][] is applied.
+    /// `#[derive(EagerLoading)]` implements this for associations with the `order_by` attribute.
+    ///
+    /// The sort is stable so the default implementation, which considers all children equal,
+    /// keeps the order returned by [`load_children`][].
+    ///
+    /// [`is_child_of`]: trait.EagerLoadChildrenOfType.html#tymethod.is_child_of
+    /// [`pagination`]: trait.EagerLoadChildrenOfType.html#method.pagination
+    /// [`load_children`]: trait.EagerLoadChildrenOfType.html#tymethod.load_children
+    fn compare_children(
+        _a: (&Child, &JoinModel),
+        _b: (&Child, &JoinModel),
+        _field_args: &Self::FieldArguments,
+        _context: &Self::Context,
+    ) -> Ordering {
+        Ordering::Equal
+    }
+
+    /// Load the children and eager load their associations.
+    ///
+    /// This doesn't need access to the parents, so the children of several associations can be
+    /// loaded at the same time. Use [`assign_children`][] to store the children on the parents.
+    ///
+    /// [`assign_children`]: trait.EagerLoadChildrenOfType.html#method.assign_children
+    fn load_and_eager_load_children(
+        models: &[Self::Model],
+        ctx: &Self::Context,
+        trail: &QueryTrail<'a, Child, Walked>,
+        field_args: &Self::FieldArguments,
+    ) -> Result<Vec<(Child, JoinModel)>, Self::Error> {
+        let child_models =
+            Self::load_children(models, field_args, ctx)?.into_child_and_join_models();
+        let (child_models, join_models): (Vec<_>, Vec<_>) = child_models.into_iter().unzip();
+
+        let children = Child::eager_load_each(&child_models, ctx, trail)?;
+
+        Ok(children.into_iter().zip(join_models).collect())
+    }
+
+    /// Store the loaded children on the parents they belong to.
+    fn assign_children(
+        nodes: &mut [Self],
+        children: Vec<(Child, JoinModel)>,
+        field_args: &Self::FieldArguments,
+        ctx: &Self::Context,
+    ) {
+        load_children_into_nodes(
+            nodes,
+            children,
+            |node, child, join_model| Self::is_child_of(node, child, join_model, field_args, ctx),
+            |node| Self::parent_join_key(node, field_args, ctx),
+            |child, join_model| Self::child_join_key(child, join_model, field_args, ctx),
+            |a, b| Self::compare_children(a, b, field_args, ctx),
+            Self::pagination(field_args),
+            Self::association,
+        );
+    }
+
     /// Combine all the methods above to eager load the children for a list of GraphQL values and
     /// models.
     fn eager_load_children(
@@ -1088,83 +1991,160 @@ where
         trail: &QueryTrail<'a, Child, Walked>,
         field_args: &Self::FieldArguments,
     ) -> Result<(), Self::Error> {
-        let child_models = match Self::load_children(models, field_args, ctx)? {
-            LoadChildrenOutput::ChildModels(child_models) => {
-                assert!(same_type::<JoinModel, ()>());
-
-                child_models
-                    .into_iter()
-                    .map(|model| {
-                        // SAFETY: This branch will only ever be called if `JoinModel` is `()`. That
-                        // happens for all the `Has*` types except `HasManyThrough`.
-                        //
-                        // `HasManyThrough` requires something to join the two types on,
-                        // therefore `child_ids` will return a variant of `LoadChildrenOutput::Models`
-                        #[allow(unsafe_code)]
-                        let join_model = unsafe { transmute_copy::<(), JoinModel>(&()) };
-
-                        (model, join_model)
-                    })
-                    .collect::<Vec<_>>()
+        let children = Self::load_and_eager_load_children(models, ctx, trail, field_args)?;
+        Self::assign_children(nodes, children, field_args, ctx);
+        Ok(())
+    }
+}
+
+/// Store each child on the association of the parents it belongs to.
+///
+/// Children that belong to a single parent are moved into it. Children that belong to several
+/// parents are shared through an `Arc`, and the last parent gets the last reference, so an
+/// association that needs to own the child only clones it for the other parents.
+#[allow(clippy::too_many_arguments)]
+fn load_children_into_nodes<Parent, Child, JoinModel>(
+    nodes: &mut [Parent],
+    children: Vec<(Child, JoinModel)>,
+    is_child_of: impl Fn(&Parent, &Child, &JoinModel) -> bool,
+    parent_join_key: impl Fn(&Parent) -> Option<JoinKey>,
+    child_join_key: impl Fn(&Child, &JoinModel) -> Option<JoinKey>,
+    compare_children: impl Fn((&Child, &JoinModel), (&Child, &JoinModel)) -> Ordering,
+    pagination: Option<Pagination>,
+    association: impl Fn(&mut Parent) -> &mut dyn Association<Child>,
+) {
+    let children_by_key = children
+        .iter()
+        .enumerate()
+        .map(|(idx, child)| child_join_key(&child.0, &child.1).map(|key| (key, idx)))
+        .collect::<Option<Vec<_>>>()
+        .map(|keys| {
+            let mut children_by_key = HashMap::<JoinKey, Vec<usize>>::new();
+            for (key, idx) in keys {
+                children_by_key.entry(key).or_default().push(idx);
             }
-            LoadChildrenOutput::ChildAndJoinModels(model_and_join_pairs) => model_and_join_pairs,
-        };
+            children_by_key
+        });
 
-        let children = child_models
-            .iter()
-            .map(|child_model| (Child::new_from_model(&child_model.0), child_model.1.clone()))
-            .collect::<Vec<_>>();
+    let children_of_nodes = nodes
+        .iter()
+        .map(|node| {
+            let is_child_of_node = |idx: &usize| {
+                let (child, join_model) = &children[*idx];
+                is_child_of(node, child, join_model)
+            };
 
-        // let mut children_without_join_models =
-        //     children.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+            let mut idxs = match (&children_by_key, parent_join_key(node)) {
+                (Some(children_by_key), Some(key)) => children_by_key
+                    .get(&key)
+                    .map(|idxs| {
+                        idxs.iter()
+                            .copied()
+                            .filter(is_child_of_node)
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default(),
+                _ => (0..children.len())
+                    .filter(is_child_of_node)
+                    .collect::<Vec<_>>(),
+            };
 
-        let child_models_without_join_models =
-            child_models.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+            idxs.sort_by(|a, b| {
+                let (a_child, a_join_model) = &children[*a];
+                let (b_child, b_join_model) = &children[*b];
+                compare_children((a_child, a_join_model), (b_child, b_join_model))
+            });
 
-        let len_before = child_models_without_join_models.len();
+            match &pagination {
+                Some(pagination) => {
+                    let has_next_page = pagination.has_next_page(idxs.len());
+                    (pagination.paginate(idxs).collect(), has_next_page)
+                }
+                None => (idxs, false),
+            }
+        })
+        .collect::<Vec<_>>();
 
-        let children_without_join_models = Child::eager_load_each(
-            // &mut children_without_join_models,
-            &child_models_without_join_models,
-            ctx,
-            trail,
-        )?;
+    let children = children
+        .into_iter()
+        .map(|(child, _join_model)| child)
+        .collect();
 
-        assert_eq!(len_before, child_models_without_join_models.len());
+    give_children_to_nodes(
+        nodes,
+        children,
+        children_of_nodes,
+        pagination.as_ref(),
+        association,
+    );
+}
 
-        let children = children_without_join_models
-            .into_iter()
-            .enumerate()
-            .map(|(idx, child)| {
-                let join_model = &children[idx].1;
-                (child, join_model)
-            })
-            .collect::<Vec<_>>();
+/// Give each node the children at the indices in `children_of_nodes`, along with whether the
+/// node has a next page.
+///
+/// Children that belong to a single parent are moved into it. Children that belong to several
+/// parents, or to the same parent several times, are shared through an `Arc`.
+fn give_children_to_nodes<Parent, Child>(
+    nodes: &mut [Parent],
+    children: Vec<Child>,
+    children_of_nodes: Vec<(Vec<usize>, bool)>,
+    pagination: Option<&Pagination>,
+    association: impl Fn(&mut Parent) -> &mut dyn Association<Child>,
+) {
+    let mut parents_remaining = vec![0_usize; children.len()];
+    for (idxs, _) in &children_of_nodes {
+        for idx in idxs {
+            parents_remaining[*idx] += 1;
+        }
+    }
 
-        for node in nodes {
-            let matching_children = children
-                .iter()
-                .filter(|child_model| {
-                    Self::is_child_of(node, &child_model.0, &child_model.1, field_args, ctx)
-                })
-                .cloned()
-                .collect::<Vec<_>>();
-
-            for child in matching_children {
-                Self::association(node).loaded_child(child.0);
-            }
+    let mut children = children
+        .into_iter()
+        .map(SharedChild::Owned)
+        .collect::<Vec<_>>();
 
-            Self::association(node).assert_loaded_otherwise_failed();
+    for (node, (idxs, has_next_page)) in nodes.iter_mut().zip(children_of_nodes) {
+        if let Some(pagination) = pagination {
+            association(node).loaded_page(pagination, has_next_page);
         }
 
-        Ok(())
+        for idx in idxs {
+            parents_remaining[idx] -= 1;
+            let is_last_parent = parents_remaining[idx] == 0;
+
+            match (children[idx].take(), is_last_parent) {
+                (SharedChild::Owned(child), true) => association(node).loaded_child(child),
+                (SharedChild::Owned(child), false) => {
+                    let child = Arc::new(child);
+                    children[idx] = SharedChild::Shared(Arc::clone(&child));
+                    association(node).loaded_shared_child(child);
+                }
+                (SharedChild::Shared(child), true) => association(node).loaded_shared_child(child),
+                (SharedChild::Shared(child), false) => {
+                    children[idx] = SharedChild::Shared(Arc::clone(&child));
+                    association(node).loaded_shared_child(child);
+                }
+                (SharedChild::Taken, _) => {
+                    unreachable!("child was given to more parents than it matched")
+                }
+            }
+        }
+
+        association(node).assert_loaded_otherwise_failed();
     }
 }
 
-/// Are two types the same?
-fn same_type<A: 'static, B: 'static>() -> bool {
-    use std::any::TypeId;
-    TypeId::of::<A>() == TypeId::of::<B>()
+/// A loaded child on its way to its parents.
+enum SharedChild<T> {
+    Owned(T),
+    Shared(Arc<T>),
+    Taken,
+}
+
+impl<T> SharedChild<T> {
+    fn take(&mut self) -> Self {
+        std::mem::replace(self, SharedChild::Taken)
+    }
 }
 
 /// The result of loading child models.
@@ -1174,6 +2154,16 @@ fn same_type<A: 'static, B: 'static>() -> bool {
 /// model has any of the foreign keys. Only the join model does. So we have to include those in the
 /// result.
 ///
+/// [`child_models`][] is only available when the join model is `()`, so forgetting to include
+/// the join models is a compile error:
+///
+/// ```compile_fail
+/// # use juniper_eager_loading::LoadChildrenOutput;
+/// struct Employment;
+///
+/// let output: LoadChildrenOutput<i32, Employment> = LoadChildrenOutput::child_models(vec![1]);
+/// ```
+///
 /// Unless you're customizing [`EagerLoadChildrenOfType`] you shouldn't have to worry about this.
 ///
 /// [`HasOne`]: struct.HasOne.html
@@ -1181,13 +2171,35 @@ fn same_type<A: 'static, B: 'static>() -> bool {
 /// [`HasMany`]: struct.HasMany.html
 /// [`HasManyThrough`]: struct.HasManyThrough.html
 /// [`EagerLoadChildrenOfType`]: trait.EagerLoadChildrenOfType.html
+/// [`child_models`]: struct.LoadChildrenOutput.html#method.child_models
 #[derive(Debug)]
-pub enum LoadChildrenOutput<ChildModel, JoinModel = ()> {
+pub struct LoadChildrenOutput<ChildModel, JoinModel = ()> {
+    child_and_join_models: Vec<(ChildModel, JoinModel)>,
+}
+
+impl<ChildModel> LoadChildrenOutput<ChildModel, ()> {
     /// Child models were loaded.
-    ChildModels(Vec<ChildModel>),
+    pub fn child_models(child_models: Vec<ChildModel>) -> Self {
+        Self {
+            child_and_join_models: child_models.into_iter().map(|model| (model, ())).collect(),
+        }
+    }
+}
 
+impl<ChildModel, JoinModel> LoadChildrenOutput<ChildModel, JoinModel> {
     /// Child models along with the respective join model was loaded.
-    ChildAndJoinModels(Vec<(ChildModel, JoinModel)>),
+    pub fn child_and_join_models(child_and_join_models: Vec<(ChildModel, JoinModel)>) -> Self {
+        Self {
+            child_and_join_models,
+        }
+    }
+
+    /// Get the child models paired with their join models.
+    ///
+    /// Child models loaded without a join model are paired with `()`.
+    pub fn into_child_and_join_models(self) -> Vec<(ChildModel, JoinModel)> {
+        self.child_and_join_models
+    }
 }
 
 /// The main entry point trait for doing eager loading.
@@ -1195,7 +2207,7 @@ pub enum LoadChildrenOutput<ChildModel, JoinModel = ()> {
 /// You shouldn't need to implement this trait yourself even when customizing eager loading.
 pub trait EagerLoading: Sized {
     /// The model type.
-    type Model: Clone;
+    type Model;
 
     /// The id type the model uses.
     type Id: 'static + Hash + Eq;
@@ -1273,6 +2285,56 @@ pub trait LoadFrom<T, Args = ()>: Sized {
 
     /// Perform the load.
     fn load(ids: &[T], args: &Args, context: &Self::Context) -> Result<Vec<Self>, Self::Error>;
+
+    /// The largest number of values to pass to [`load`][] at once.
+    ///
+    /// Eager loading splits the values into chunks of this size, calls [`load`][] once per chunk,
+    /// and concatenates the results. This is useful for databases that limit the number of bound
+    /// parameters in a query. The default is `None` which loads all the values at once.
+    ///
+    /// It can be overridden for a single association with `chunk_size` in `#[derive(EagerLoading)]`.
+    ///
+    /// [`load`]: trait.LoadFrom.html#tymethod.load
+    fn chunk_size() -> Option<usize> {
+        None
+    }
+}
+
+/// Call [`LoadFrom::load`][] once per chunk of `ids` and concatenate the results.
+///
+/// `chunk_size` takes precedence over [`LoadFrom::chunk_size`][]. If neither is set all the ids
+/// are loaded at once.
+///
+/// This is the function used by the code generated by `#[derive(EagerLoading)]` to call
+/// [`LoadFrom`][].
+///
+/// # Panics
+///
+/// Panics if the chunk size is zero.
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`LoadFrom::load`]: trait.LoadFrom.html#tymethod.load
+/// [`LoadFrom::chunk_size`]: trait.LoadFrom.html#method.chunk_size
+pub fn load_from_in_chunks<T, Args, Child>(
+    ids: &[T],
+    args: &Args,
+    ctx: &Child::Context,
+    chunk_size: Option<usize>,
+) -> Result<Vec<Child>, Child::Error>
+where
+    Child: LoadFrom<T, Args>,
+{
+    let chunk_size = match chunk_size.or_else(Child::chunk_size) {
+        Some(0) => panic!("The chunk size of `LoadFrom` must be greater than zero"),
+        Some(chunk_size) if ids.len() > chunk_size => chunk_size,
+        _ => return Child::load(ids, args, ctx),
+    };
+
+    let mut children = Vec::new();
+    for chunk in ids.chunks(chunk_size) {
+        children.extend(Child::load(chunk, args, ctx)?);
+    }
+    Ok(children)
 }
 
 /// The kinds of errors that can happen when doing eager loading.
@@ -1291,6 +2353,54 @@ pub enum Error {
     /// [`HasOne`](struct.HasOne.html). All the other association types have defaults.
     #[error("Failed to load `{0:?}`")]
     LoadFailed(AssociationType),
+
+    /// More than one child was found for an association that should only have one. This happens
+    /// when the children of a [`HasOne`](struct.HasOne.html) or
+    /// [`OptionHasOne`](struct.OptionHasOne.html) are loaded by a column that isn't unique, or
+    /// when the foreign key lives on the child and several children point to the same parent.
+    #[error("Found more than one child for `{0:?}`")]
+    TooManyChildren(AssociationType),
+
+    /// A child that doesn't implement `Clone` had to be given to more than one parent. See
+    /// [`CloneChild`](trait.CloneChild.html).
+    #[error("A child of `{0:?}` belongs to more than one parent but cannot be cloned")]
+    ChildNotCloneable(AssociationType),
+}
+
+/// A hashed key used to pair up parents and children during eager loading.
+///
+/// See [`EagerLoadChildrenOfType::parent_join_key`][] for more details.
+///
+/// [`EagerLoadChildrenOfType::parent_join_key`]: trait.EagerLoadChildrenOfType.html#method.parent_join_key
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct JoinKey(u64);
+
+impl JoinKey {
+    /// Create a key by hashing a value.
+    ///
+    /// Values that are equal must hash to the same key, so the parent and child keys should be
+    /// created from values of the same type.
+    pub fn new<T: Hash + ?Sized>(value: &T) -> Self {
+        use std::{collections::hash_map::DefaultHasher, hash::Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        JoinKey(hasher.finish())
+    }
+}
+
+/// A walked trail that doesn't select any fields.
+///
+/// It is used to load the children of a [`HasConnection`][] when only `pageInfo` is queried. No
+/// associations of the children will be eager loaded.
+///
+/// [`HasConnection`]: struct.HasConnection.html
+pub fn unselected_trail<'a, T>() -> QueryTrail<'a, T, Walked> {
+    QueryTrail {
+        look_ahead: None,
+        node_type: std::marker::PhantomData,
+        walked: Walked,
+    }
 }
 
 /// Remove duplicates from a list.
@@ -1307,12 +2417,107 @@ pub fn unique<T: Hash + Eq>(items: Vec<T>) -> Vec<T> {
         .collect::<Vec<_>>()
 }
 
+/// Store the children on the parents whose list of ids includes them.
+///
+/// Each parent gets its children in the order of its ids, once for every time an id appears.
+/// Ids without a child are skipped.
+///
+/// This function is used by the code generated for
+/// [`HasManyFromIds`](struct.HasManyFromIds.html) associations.
+pub fn assign_children_by_ids<Parent, Child, Id>(
+    nodes: &mut [Parent],
+    children: Vec<(Child, ())>,
+    parent_ids: impl Fn(&Parent) -> &[Id],
+    child_id: impl Fn(&Child) -> Id,
+    association: impl Fn(&mut Parent) -> &mut dyn Association<Child>,
+) where
+    Id: Hash + Eq,
+{
+    let child_idx_by_id = children
+        .iter()
+        .enumerate()
+        .map(|(idx, (child, ()))| (child_id(child), idx))
+        .collect::<HashMap<_, _>>();
+
+    let children_of_nodes = nodes
+        .iter()
+        .map(|node| {
+            let idxs = parent_ids(node)
+                .iter()
+                .filter_map(|id| child_idx_by_id.get(id).copied())
+                .collect();
+            (idxs, false)
+        })
+        .collect();
+
+    let children = children.into_iter().map(|(child, ())| child).collect();
+
+    give_children_to_nodes(nodes, children, children_of_nodes, None, association);
+}
+
+/// Pair up each join model with the child model it points to.
+///
+/// Join models that don't point to any of the child models are dropped. A child model is only
+/// cloned if more than one join model points to it.
+///
+/// This function is used by the code generated for
+/// [`HasManyThrough`](struct.HasManyThrough.html) associations.
+pub fn pair_with_join_models<ChildModel, JoinModel, Key>(
+    child_models: Vec<ChildModel>,
+    join_models: Vec<JoinModel>,
+    child_key: impl Fn(&ChildModel) -> Key,
+    join_model_key: impl Fn(&JoinModel) -> Key,
+) -> Vec<(ChildModel, JoinModel)>
+where
+    ChildModel: Clone,
+    Key: Hash + Eq,
+{
+    let mut child_idx_by_key = HashMap::with_capacity(child_models.len());
+    for (idx, child_model) in child_models.iter().enumerate() {
+        child_idx_by_key
+            .entry(child_key(child_model))
+            .or_insert(idx);
+    }
+
+    let join_models = join_models
+        .into_iter()
+        .filter_map(|join_model| {
+            let idx = child_idx_by_key.get(&join_model_key(&join_model)).copied();
+            idx.map(|idx| (idx, join_model))
+        })
+        .collect::<Vec<_>>();
+
+    let mut join_models_remaining = vec![0_usize; child_models.len()];
+    for (idx, _) in &join_models {
+        join_models_remaining[*idx] += 1;
+    }
+
+    let mut child_models = child_models.into_iter().map(Some).collect::<Vec<_>>();
+
+    join_models
+        .into_iter()
+        .map(|(idx, join_model)| {
+            join_models_remaining[idx] -= 1;
+            let child_model = if join_models_remaining[idx] == 0 {
+                child_models[idx].take()
+            } else {
+                child_models[idx].clone()
+            };
+            let child_model = child_model.expect("child model was paired after being moved");
+            (child_model, join_model)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     #[test]
     fn ui() {
         let t = trybuild::TestCases::new();
+
         t.pass("tests/compile_pass/*.rs");
+        #[cfg(feature = "diesel2")]
+        t.pass("tests/compile_pass_diesel2/*.rs");
 
         // We currently don't have any compile tests that should fail to build
         // t.compile_fail("tests/compile_fail/*.rs");
@@ -106,6 +106,16 @@
 /// The first syntax implements `LoadFrom<i32> for User`, meaning from a `Vec<i32>` we can load a
 /// `Vec<User>`. It just takes the id type, the table, and the model struct.
 ///
+/// The rows are found by the primary key of the table. To find them by another unique column,
+/// such as a natural key, name the column after the table:
+///
+/// ```text
+/// String -> (countries.iso_code, Country),
+/// ```
+///
+/// This is the loading side of `child_primary_key_field` in `#[derive(EagerLoading)]`, such as
+/// `#[has_one(foreign_key_field = country_code, child_primary_key_field = iso_code)]`.
+///
 /// ```text
 /// User.id -> (employments.user_id, Employment),
 /// ```
@@ -115,8 +125,89 @@
 /// `Vec<Employment>`. It does this by loading the users, mapping the list to the user ids,
 /// then finding the employments with those ids.
 ///
+/// The second syntax also takes an optional arguments type for associations using `paginate`:
+///
+/// ```text
+/// User.id -> (cars.user_id, Car, paginate = UserCarsArgs<'_>),
+/// ```
+///
+/// This implements `LoadFrom<User, UserCarsArgs<'_>> for Car`. The arguments type must have
+/// `first` and `after` methods, like the ones generated by juniper-from-schema. Only the first
+/// [`Pagination::limit`][] cars of each user, ordered by primary key, are loaded using a
+/// `ROW_NUMBER()` window function.
+///
+/// If the field orders its children with `order_by`, give the window a matching column so it
+/// picks the same children:
+///
+/// ```text
+/// User.id -> (cars.user_id, Car, paginate = UserCarsArgs<'_>, order_by = name),
+/// ```
+///
+/// The cars are then ordered by `name`, and by primary key for cars with the same name.
+///
+/// Both syntaxes take an arguments type and a filter for fields that take arguments:
+///
+/// ```text
+/// Country.id -> (
+///     users.country_id,
+///     User,
+///     args = CountryUsersArgs<'_>,
+///     filter = |args| users::active_since.gt(args.active_since()),
+/// ),
+/// ```
+///
+/// This implements `LoadFrom<Country, CountryUsersArgs<'_>> for User`. `filter` is a closure or
+/// function that is given the arguments and returns a Diesel expression, which is added to the
+/// query with `.filter(_)`. `args` can be used without `filter` if the arguments don't change
+/// the query. They cannot be combined with `paginate`.
+///
+/// There is also a syntax for counting the children of each parent, for use with
+/// [`HasCount`][]:
+///
+/// ```text
+/// User.id: i32 -> count(cars.user_id, CarCount),
+/// ```
+///
+/// This implements `LoadAggregate<User> for CarCount`, where `CarCount` is a type you define. The
+/// type after the field is the type of `User.id`, which is also the [`LoadAggregate::Key`][].
+///
+/// Composite keys are written as tuples of fields and columns:
+///
+/// ```text
+/// (i32, i32) -> (users, User),
+/// User.(tenant_id, id) -> (cars.(tenant_id, user_id), Car),
+/// User.(tenant_id, id): (i32, i32) -> count(cars.(tenant_id, user_id), CarCount),
+/// ```
+///
+/// The first line loads users by the primary key of the `users` table, which must have as many
+/// columns as the tuple. The rows are found with a row value comparison such as
+/// `(cars.tenant_id, cars.user_id) IN ((?, ?), (?, ?))`. `paginate` cannot be used with
+/// composite keys.
+///
+/// The generated implementations set [`LoadFrom::chunk_size`][] so a query never binds more
+/// parameters than the backend allows, which is 65,535 for Postgres and MySQL and 999 for SQLite.
+/// Impls with a `filter` leave room for 100 parameters bound by the filter, so SQLite loads 899
+/// ids per query. A filter that binds more than 100 parameters, such as `eq_any` on a list
+/// argument, must say how many it can bind with `filter_params`:
+///
+/// ```text
+/// Country.id -> (
+///     users.country_id,
+///     User,
+///     args = CountryUsersArgs<'_>,
+///     filter = |args| users::id.eq_any(args.ids()),
+///     filter_params = 500,
+/// ),
+/// ```
+///
+/// Use `chunk_size` on the association to load smaller chunks.
+///
 /// [`HasMany`]: trait.HasMany.html
 /// [`HasManyThrough`]: trait.HasManyThrough.html
+/// [`Pagination::limit`]: struct.Pagination.html#method.limit
+/// [`HasCount`]: type.HasCount.html
+/// [`LoadAggregate::Key`]: trait.LoadAggregate.html#associatedtype.Key
+/// [`LoadFrom::chunk_size`]: trait.LoadFrom.html#method.chunk_size
 ///
 /// # `Context::db`
 ///
@@ -142,6 +233,64 @@
 ///     .load::<User>(ctx.db())
 /// ```
 ///
+/// # `connection`
+///
+/// If your context doesn't have a `db` method, such as when it holds a connection pool, set
+/// `connection` to a closure that is given the context and returns the connection:
+///
+/// ```text
+/// (
+///     error = Error,
+///     context = Context,
+///     connection = |ctx| ctx.pool.get()?,
+/// ) => {
+///     i32 -> (users, User),
+///     User.id -> (cars.user_id, Car, connection = |ctx| ctx.replica_pool.get()?),
+/// }
+/// ```
+///
+/// The closure is inlined into the generated `load` method, so `?` converts errors into the
+/// error type with `From`. The returned value is dereferenced and passed to `.load(&*connection)`,
+/// so both references to connections and pooled connections work.
+///
+/// The `connection` in the prelude is used by every impl in the macro call. It can be overridden
+/// for a single impl, including counts, by setting `connection` after the model type.
+///
+/// # Diesel 2
+///
+/// The macros generate code for Diesel 1 by default. Enable the `diesel2` cargo feature and set
+/// `diesel = 2` in the prelude to generate code for Diesel 2 instead. The feature only makes the
+/// Diesel 2 code path available, so macro calls without `diesel = 2` keep generating code for
+/// Diesel 1 and crates using either version can be compiled together.
+///
+/// ```rust,ignore
+/// impl_load_from_for_diesel_pg! {
+///     (
+///         error = diesel::result::Error,
+///         context = Context,
+///         diesel = 2,
+///     ) => {
+///         i32 -> (users, User),
+///     }
+/// }
+/// ```
+///
+/// Diesel 2 loads with `&mut` connections, so the generated code calls `.load(&mut *connection)`.
+/// `ctx.db()`, or the `connection` closure, must return something that dereferences mutably to
+/// the connection, such as a `RefMut`, a `MutexGuard`, or a pooled connection:
+///
+/// ```rust,ignore
+/// struct Context {
+///     db: RefCell<PgConnection>,
+/// }
+///
+/// impl Context {
+///     fn db(&self) -> RefMut<'_, PgConnection> {
+///         self.db.borrow_mut()
+///     }
+/// }
+/// ```
+///
 /// # What gets generated
 ///
 /// The two syntaxes generates code like this:
@@ -204,6 +353,10 @@
 ///             .load::<User>(ctx.db())
 ///             .map_err(From::from)
 ///     }
+///
+///     fn chunk_size() -> Option<usize> {
+///         Some(65535)
+///     }
 /// }
 ///
 /// // User.id -> (employments.user_id, Employment),
@@ -220,6 +373,10 @@
 ///             .load(ctx.db())
 ///             .map_err(From::from)
 ///     }
+///
+///     fn chunk_size() -> Option<usize> {
+///         Some(65535)
+///     }
 /// }
 /// ```
 #[macro_export]
@@ -410,3 +567,223 @@ macro_rules! impl_load_from_for_diesel_sqlite {
         $crate::proc_macros::impl_load_from_for_diesel_sqlite!($($token)*);
     }
 }
+
+/// This macro will implement [`AsyncLoadFrom`][] for [sqlx][] models using Postgres or SQLite.
+/// It requires the `async` cargo feature.
+///
+/// # Example usage
+///
+/// ```rust,ignore
+/// use juniper_eager_loading::impl_load_from_for_sqlx;
+///
+/// #[derive(sqlx::FromRow)]
+/// pub struct User {
+///     id: i32,
+///     country_code: String,
+/// }
+///
+/// #[derive(sqlx::FromRow)]
+/// pub struct Country {
+///     id: i32,
+///     iso_code: String,
+/// }
+///
+/// #[derive(sqlx::FromRow)]
+/// pub struct Car {
+///     id: i32,
+///     user_id: i32,
+/// }
+///
+/// pub struct Context {
+///     db: sqlx::PgPool,
+/// }
+///
+/// impl Context {
+///     fn db(&self) -> &sqlx::PgPool {
+///         &self.db
+///     }
+/// }
+///
+/// impl_load_from_for_sqlx! {
+///     (
+///         error = sqlx::Error,
+///         context = Context,
+///         database = Postgres,
+///     ) => {
+///         i32 -> (users, User),
+///         String -> (countries.iso_code, Country),
+///         User.id -> (cars.user_id, Car),
+///     }
+/// }
+/// ```
+///
+/// # Syntax
+///
+/// The prelude takes the error type, the context type, and the database, which is either
+/// `Postgres` or `Sqlite`. The impls use the same syntax as
+/// [`impl_load_from_for_diesel_pg`][]:
+///
+/// - `i32 -> (users, User)` implements `AsyncLoadFrom<i32> for User` by loading the users whose
+///   `id` column matches. sqlx doesn't know the primary key of the table, so the column is always
+///   `id`.
+/// - `String -> (countries.iso_code, Country)` loads the countries by the `iso_code` column.
+/// - `User.id -> (cars.user_id, Car)` implements `AsyncLoadFrom<User> for Car` by loading the
+///   cars whose `user_id` matches the `id` of one of the users.
+///
+/// The models must implement [`sqlx::FromRow`][] and the rows are selected with `SELECT *`. On
+/// Postgres the ids are bound as an array with `= ANY($1)`. SQLite doesn't have arrays, so they
+/// are bound one by one with `IN (?, ?, ...)` and [`AsyncLoadFrom::chunk_size`][] is set to 999,
+/// SQLite's limit on bound parameters. The database isn't queried when there are no ids.
+///
+/// # `Context::db`
+///
+/// Your context type must have a method called `db` that returns something that implements
+/// [`sqlx::Executor`][] for the database, such as `&PgPool` or `&SqlitePool`.
+///
+/// # `connection`
+///
+/// As with the Diesel macros, `connection` can be set to a closure that is given the context and
+/// returns the executor, either in the prelude for every impl or after the model type for a
+/// single impl:
+///
+/// ```text
+/// (
+///     error = sqlx::Error,
+///     context = Context,
+///     database = Postgres,
+///     connection = |ctx| &ctx.pool,
+/// ) => {
+///     i32 -> (users, User),
+///     User.id -> (cars.user_id, Car, connection = |ctx| &ctx.replica_pool),
+/// }
+/// ```
+///
+/// The closure is inlined into the generated `load` method, so `?` and `.await` can be used.
+///
+/// [`AsyncLoadFrom`]: trait.AsyncLoadFrom.html
+/// [`AsyncLoadFrom::chunk_size`]: trait.AsyncLoadFrom.html#method.chunk_size
+/// [`impl_load_from_for_diesel_pg`]: macro.impl_load_from_for_diesel_pg.html
+/// [sqlx]: https://docs.rs/sqlx
+/// [`sqlx::FromRow`]: https://docs.rs/sqlx/latest/sqlx/trait.FromRow.html
+/// [`sqlx::Executor`]: https://docs.rs/sqlx/latest/sqlx/trait.Executor.html
+#[cfg(feature = "async")]
+#[macro_export]
+macro_rules! impl_load_from_for_sqlx {
+    ( $($token:tt)* ) => {
+        $crate::proc_macros::impl_load_from_for_sqlx!($($token)*);
+    }
+}
+
+/// This macro will implement [`LoadFrom`][] for models stored in the tables of the
+/// [`in_memory`][] module.
+///
+/// It is meant for prototyping schemas and testing resolvers without a database.
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`in_memory`]: in_memory/index.html
+///
+/// # Example usage
+///
+/// ```
+/// use juniper_eager_loading::{
+///     impl_load_from_for_in_memory,
+///     in_memory::{List, Table},
+///     LoadFrom,
+/// };
+///
+/// #[derive(Clone)]
+/// pub struct User {
+///     id: i32,
+///     country_code: String,
+/// }
+///
+/// #[derive(Clone)]
+/// pub struct Country {
+///     id: i32,
+///     iso_code: String,
+/// }
+///
+/// #[derive(Clone)]
+/// pub struct Car {
+///     id: i32,
+///     user_id: i32,
+/// }
+///
+/// pub struct Db {
+///     users: Table<i32, User>,
+///     countries: Table<i32, Country>,
+///     cars: List<Car>,
+/// }
+///
+/// pub struct Context {
+///     db: Db,
+/// }
+///
+/// impl Context {
+///     fn db(&self) -> &Db {
+///         &self.db
+///     }
+/// }
+///
+/// impl_load_from_for_in_memory! {
+///     (
+///         error = Box<dyn std::error::Error>,
+///         context = Context,
+///     ) => {
+///         i32 -> (users, User),
+///         String -> (countries.iso_code, Country),
+///         User.id -> (cars.user_id, Car),
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut users = Table::new("users");
+/// users.insert(1, User { id: 1, country_code: "DK".to_string() });
+/// let mut cars = List::new("cars");
+/// cars.push(Car { id: 1, user_id: 1 });
+/// cars.push(Car { id: 2, user_id: 1 });
+///
+/// let ctx = Context {
+///     db: Db { users, countries: Table::new("countries"), cars },
+/// };
+///
+/// let users = User::load(&[1], &(), &ctx).unwrap();
+/// let cars = Car::load(&users, &(), &ctx).unwrap();
+/// assert_eq!(cars.len(), 2);
+/// assert_eq!(ctx.db().users.reads_count(), 1);
+/// assert_eq!(ctx.db().cars.reads_count(), 1);
+/// # }
+/// ```
+///
+/// # Syntax
+///
+/// The impls use the same syntax as [`impl_load_from_for_sqlx`][] except the names on the right
+/// refer to fields rather than columns:
+///
+/// - `i32 -> (users, User)` implements `LoadFrom<i32> for User` by looking up the ids in the
+///   `users` [`Table`][], so the users must be inserted with their ids as keys.
+/// - `String -> (countries.iso_code, Country)` finds the countries by their `iso_code` field.
+/// - `User.id -> (cars.user_id, Car)` implements `LoadFrom<User> for Car` by finding the cars
+///   whose `user_id` matches the `id` of one of the users.
+///
+/// The models must implement `Clone` and the ids must implement `Hash` and `Eq`.
+///
+/// # `Context::db`
+///
+/// Your context type must have a method called `db` that returns a reference to a struct whose
+/// fields are the tables. A table can be either a [`Table`][] or a [`List`][], except that
+/// `i32 -> (users, User)` requires a `Table`.
+///
+/// Each load reads the table once, so the reads count of the table goes up by exactly one per
+/// call to [`LoadFrom::load`][]. Check the counts after running a query to catch N+1 loads.
+///
+/// [`impl_load_from_for_sqlx`]: macro.impl_load_from_for_sqlx.html
+/// [`Table`]: in_memory/struct.Table.html
+/// [`List`]: in_memory/struct.List.html
+/// [`LoadFrom::load`]: trait.LoadFrom.html#tymethod.load
+#[macro_export]
+macro_rules! impl_load_from_for_in_memory {
+    ( $($token:tt)* ) => {
+        $crate::proc_macros::impl_load_from_for_in_memory!($($token)*);
+    }
+}
@@ -0,0 +1,180 @@
+/// How to paginate the children of each parent.
+///
+/// The pagination is applied per parent after the children have been matched to their parents
+/// with [`EagerLoadChildrenOfType::is_child_of`][]. So with `first: 10` every parent gets at most
+/// ten children, rather than ten children being split between all the parents.
+///
+/// A [`LoadFrom`][] implementation can use [`limit`][] to avoid loading more children than
+/// required, but it must load at least the first `limit` children of each parent, in order.
+///
+/// [`EagerLoadChildrenOfType::is_child_of`]: trait.EagerLoadChildrenOfType.html#tymethod.is_child_of
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`limit`]: struct.Pagination.html#method.limit
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Pagination {
+    first: Option<usize>,
+    offset: usize,
+}
+
+impl Pagination {
+    /// Create a new `Pagination` from the GraphQL `first` and `after` arguments.
+    ///
+    /// `after` is a cursor returned by [`encode_cursor`][]. Cursors that cannot be decoded are
+    /// ignored and negative values for `first` are treated as `0`.
+    ///
+    /// [`encode_cursor`]: fn.encode_cursor.html
+    pub fn new(first: impl Into<Option<i32>>, after: impl Into<Option<String>>) -> Self {
+        let first = first.into().map(|first| first.max(0) as usize);
+        let offset = after
+            .into()
+            .and_then(|cursor| decode_cursor(&cursor))
+            .map(|position| position + 1)
+            .unwrap_or(0);
+
+        Self { first, offset }
+    }
+
+    /// The maximum number of children each parent should get. `None` means no limit.
+    pub fn first(&self) -> Option<usize> {
+        self.first
+    }
+
+    /// The number of children to skip for each parent.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The number of children that have to be loaded for each parent. `None` means all of them.
+    ///
+    /// This includes one child more than will be kept, which is used to tell if there is a next
+    /// page.
+    pub fn limit(&self) -> Option<usize> {
+        self.first.map(|first| first + self.offset + 1)
+    }
+
+    /// Whether a parent with `count` matching children has more children after the page.
+    pub fn has_next_page(&self, count: usize) -> bool {
+        match self.first {
+            Some(first) => count > self.offset + first,
+            None => false,
+        }
+    }
+
+    /// Whether there are children before the page.
+    pub fn has_previous_page(&self) -> bool {
+        self.offset > 0
+    }
+
+    /// Apply the pagination to the children of a single parent.
+    pub fn paginate<T>(&self, children: impl IntoIterator<Item = T>) -> impl Iterator<Item = T> {
+        children
+            .into_iter()
+            .skip(self.offset)
+            .take(self.first.unwrap_or(usize::MAX))
+    }
+}
+
+/// Create the cursor for the child at `position` in the list of children of its parent.
+///
+/// Passing the cursor to [`Pagination::new`][] as `after` will start with the child after it.
+///
+/// [`Pagination::new`]: struct.Pagination.html#method.new
+pub fn encode_cursor(position: usize) -> String {
+    position.to_string()
+}
+
+/// Get the position from a cursor created with [`encode_cursor`][].
+///
+/// [`encode_cursor`]: fn.encode_cursor.html
+pub fn decode_cursor(cursor: &str) -> Option<usize> {
+    cursor.parse().ok()
+}
+
+/// A page of children in the shape of a [Relay connection](https://relay.dev/graphql/connections.htm).
+///
+/// This is what [`HasConnection`][] holds once it has been loaded.
+///
+/// [`HasConnection`]: struct.HasConnection.html
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Connection<T> {
+    pub(crate) edges: Vec<Edge<T>>,
+    pub(crate) page_info: PageInfo,
+}
+
+impl<T> Default for Connection<T> {
+    fn default() -> Self {
+        Connection {
+            edges: Vec::new(),
+            page_info: PageInfo::default(),
+        }
+    }
+}
+
+impl<T> Connection<T> {
+    /// The edges of the page.
+    pub fn edges(&self) -> &Vec<Edge<T>> {
+        &self.edges
+    }
+
+    /// The information about the page.
+    pub fn page_info(&self) -> &PageInfo {
+        &self.page_info
+    }
+
+    /// The nodes of the page, without their cursors.
+    pub fn nodes(&self) -> Vec<&T> {
+        self.edges.iter().map(|edge| &edge.node).collect()
+    }
+}
+
+/// A child and its cursor.
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub struct Edge<T> {
+    pub(crate) node: T,
+    pub(crate) cursor: String,
+}
+
+impl<T> Edge<T> {
+    /// The child.
+    pub fn node(&self) -> &T {
+        &self.node
+    }
+
+    /// The cursor of the child. Pass it as `after` to get the children following it.
+    pub fn cursor(&self) -> &String {
+        &self.cursor
+    }
+}
+
+/// The `pageInfo` of a [`Connection`][].
+///
+/// [`Connection`]: struct.Connection.html
+#[derive(Debug, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct PageInfo {
+    pub(crate) has_next_page: bool,
+    pub(crate) has_previous_page: bool,
+    pub(crate) start_cursor: Option<String>,
+    pub(crate) end_cursor: Option<String>,
+}
+
+impl PageInfo {
+    /// Does the parent have more children after this page?
+    pub fn has_next_page(&self) -> &bool {
+        &self.has_next_page
+    }
+
+    /// Does the parent have children before this page?
+    pub fn has_previous_page(&self) -> &bool {
+        &self.has_previous_page
+    }
+
+    /// The cursor of the first edge. `None` if the page is empty.
+    pub fn start_cursor(&self) -> &Option<String> {
+        &self.start_cursor
+    }
+
+    /// The cursor of the last edge. `None` if the page is empty.
+    pub fn end_cursor(&self) -> &Option<String> {
+        &self.end_cursor
+    }
+}
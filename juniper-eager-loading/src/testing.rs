@@ -0,0 +1,417 @@
+//! Recording the loads made by a query, to catch N+1 regressions in tests.
+//!
+//! Store a [`LoadRecorder`][] in your Juniper context, implement [`ContextWithLoadRecorder`][] for
+//! the context, and add `record_loads` to `#[eager_loading]`. Every call the generated code makes
+//! to [`LoadFrom`][], [`LoadAggregate`][], and [`LoadTree`][] is then logged with the model type,
+//! the number of keys, and the field arguments. Loads answered by the cache of
+//! `#[eager_loading(cache)]` are only logged when some ids were missing from the cache. After
+//! running a query you can assert on the log:
+//!
+//! ```ignore
+//! let ctx = Context::new(db);
+//! run_query("query Test { users { country { id } cars { id } } }", &ctx);
+//!
+//! ctx.load_recorder().assert_at_most_one_load_per_type();
+//! ctx.load_recorder().assert_total_loads_at_most(3);
+//! ```
+//!
+//! A failing assertion panics with the recorded call log, so you can see which association made
+//! the extra loads.
+//!
+//! Code that calls [`LoadFrom`][] by hand, such as a hand-written [`EagerLoadChildrenOfType`][]
+//! implementation or a resolver, can use [`load_and_record`][] instead of [`LoadFrom::load`][] to
+//! include its loads in the log.
+//!
+//! [`LoadRecorder`]: struct.LoadRecorder.html
+//! [`ContextWithLoadRecorder`]: trait.ContextWithLoadRecorder.html
+//! [`load_and_record`]: fn.load_and_record.html
+//! [`LoadFrom`]: ../trait.LoadFrom.html
+//! [`LoadFrom::load`]: ../trait.LoadFrom.html#tymethod.load
+//! [`LoadAggregate`]: ../trait.LoadAggregate.html
+//! [`LoadTree`]: ../trait.LoadTree.html
+//! [`EagerLoadChildrenOfType`]: ../trait.EagerLoadChildrenOfType.html
+
+use crate::{
+    cache::load_cached_with, load_aggregate, load_from_in_chunks, ContextWithCache, HasAggregate,
+    LoadAggregate, LoadFrom, LoadTree,
+};
+use std::{any::type_name, fmt, hash::Hash, sync::Mutex};
+
+/// A single recorded load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadCall {
+    /// The type of the loaded models, or of the aggregate for [`LoadAggregate`][].
+    ///
+    /// [`LoadAggregate`]: ../trait.LoadAggregate.html
+    pub model: &'static str,
+    /// The type the models were loaded from, such as an id or a parent model.
+    pub from: &'static str,
+    /// The number of ids or models the models were loaded from.
+    pub keys: usize,
+    /// The field arguments formatted with `Debug`. This is `()` for fields without arguments.
+    ///
+    /// The code generated by `#[eager_loading(record_loads)]` uses the type name of the field
+    /// arguments if they don't implement `Debug`. Trees are recorded with their depth, such as
+    /// `depth: 3`.
+    pub field_args: String,
+}
+
+impl LoadCall {
+    /// A load of `Model` from `keys` values of type `T`.
+    pub fn new<Model, T>(keys: usize, field_args: impl Into<String>) -> Self {
+        LoadCall {
+            model: type_name::<Model>(),
+            from: type_name::<T>(),
+            keys,
+            field_args: field_args.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} from {} ({} keys, field args: {})",
+            self.model, self.from, self.keys, self.field_args
+        )
+    }
+}
+
+/// A log of the loads made while running a query.
+///
+/// A load that is split into chunks is recorded as a single call. Since the calls are never
+/// cleared automatically you should create a new recorder for each query, or call
+/// [`clear`][] in between.
+///
+/// [`LoadFrom`]: ../trait.LoadFrom.html
+/// [`clear`]: #method.clear
+#[derive(Default)]
+pub struct LoadRecorder {
+    calls: Mutex<Vec<LoadCall>>,
+}
+
+impl LoadRecorder {
+    /// Create a new empty recorder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a load of `Model` from `keys` values of type `T` with the given field arguments.
+    ///
+    /// Use this for loads that don't go through [`load_and_record`][], such as a query made
+    /// directly in a resolver.
+    ///
+    /// [`load_and_record`]: fn.load_and_record.html
+    pub fn record<Model, T, Args>(&self, keys: usize, field_args: &Args)
+    where
+        Args: fmt::Debug,
+    {
+        self.record_call(LoadCall::new::<Model, T>(keys, format!("{:?}", field_args)));
+    }
+
+    /// Add a call to the log.
+    pub fn record_call(&self, call: LoadCall) {
+        self.calls.lock().unwrap().push(call);
+    }
+
+    /// All the calls recorded so far, in the order they were made.
+    pub fn calls(&self) -> Vec<LoadCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// The total number of recorded calls.
+    pub fn loads_count(&self) -> usize {
+        self.calls.lock().unwrap().len()
+    }
+
+    /// The number of recorded calls that loaded models of type `Model`.
+    pub fn loads_of<Model>(&self) -> usize {
+        let model = type_name::<Model>();
+        self.calls
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|call| call.model == model)
+            .count()
+    }
+
+    /// Remove all the recorded calls.
+    pub fn clear(&self) {
+        self.calls.lock().unwrap().clear();
+    }
+
+    /// Assert that each model type was loaded at most once.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the call log if a model type was loaded more than once.
+    pub fn assert_at_most_one_load_per_type(&self) {
+        let calls = self.calls();
+
+        let mut repeated = Vec::new();
+        for (idx, call) in calls.iter().enumerate() {
+            let is_first = calls[..idx].iter().all(|other| other.model != call.model);
+            if !is_first {
+                continue;
+            }
+
+            let count = calls
+                .iter()
+                .filter(|other| other.model == call.model)
+                .count();
+            if count > 1 {
+                repeated.push(format!("{} was loaded {} times", call.model, count));
+            }
+        }
+
+        if !repeated.is_empty() {
+            panic!(
+                "Expected each model type to be loaded at most once\n{}\n\n{}",
+                repeated.join("\n"),
+                self
+            );
+        }
+    }
+
+    /// Assert that at most `max` calls were made in total.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the call log if more than `max` calls were made.
+    pub fn assert_total_loads_at_most(&self, max: usize) {
+        let count = self.loads_count();
+        if count > max {
+            panic!(
+                "Expected at most {} loads but {} were made\n\n{}",
+                max, count, self
+            );
+        }
+    }
+
+    /// Assert that models of type `Model` were loaded at most `max` times.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the call log if `Model` was loaded more than `max` times.
+    pub fn assert_loads_of_at_most<Model>(&self, max: usize) {
+        let count = self.loads_of::<Model>();
+        if count > max {
+            panic!(
+                "Expected {} to be loaded at most {} times but it was loaded {} times\n\n{}",
+                type_name::<Model>(),
+                max,
+                count,
+                self
+            );
+        }
+    }
+}
+
+impl fmt::Display for LoadRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let calls = self.calls.lock().unwrap();
+
+        writeln!(f, "Recorded loads:")?;
+        if calls.is_empty() {
+            write!(f, "  (none)")?;
+        }
+        for (idx, call) in calls.iter().enumerate() {
+            if idx != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "  {}. {}", idx + 1, call)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for LoadRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadRecorder")
+            .field("calls", &*self.calls.lock().unwrap())
+            .finish()
+    }
+}
+
+/// Juniper context types that hold a [`LoadRecorder`][].
+///
+/// This is required when using `#[eager_loading(record_loads)]`.
+///
+/// [`LoadRecorder`]: struct.LoadRecorder.html
+pub trait ContextWithLoadRecorder {
+    /// Get the recorder for the current query.
+    fn load_recorder(&self) -> &LoadRecorder;
+}
+
+/// Record a call to [`LoadFrom`][] in the context's [`LoadRecorder`][] and then make it.
+///
+/// This wraps any [`LoadFrom`][] implementation, so hand-written code can call it instead of
+/// [`LoadFrom::load`][] to include its loads in the log.
+///
+/// [`LoadFrom`]: ../trait.LoadFrom.html
+/// [`LoadFrom::load`]: ../trait.LoadFrom.html#tymethod.load
+/// [`LoadRecorder`]: struct.LoadRecorder.html
+pub fn load_and_record<T, Args, Child>(
+    ids: &[T],
+    args: &Args,
+    ctx: &Child::Context,
+) -> Result<Vec<Child>, Child::Error>
+where
+    Child: LoadFrom<T, Args>,
+    Child::Context: ContextWithLoadRecorder,
+    Args: fmt::Debug,
+{
+    record_load_from_in_chunks(ids, args, ctx, None, format!("{:?}", args))
+}
+
+/// Record the call in the context's [`LoadRecorder`][] and then call [`load_from_in_chunks`][].
+///
+/// `field_args` is how the field arguments are shown in the log. This is the function used by
+/// `#[eager_loading(record_loads)]`.
+///
+/// [`LoadRecorder`]: struct.LoadRecorder.html
+/// [`load_from_in_chunks`]: ../fn.load_from_in_chunks.html
+pub fn record_load_from_in_chunks<T, Args, Child>(
+    ids: &[T],
+    args: &Args,
+    ctx: &Child::Context,
+    chunk_size: Option<usize>,
+    field_args: String,
+) -> Result<Vec<Child>, Child::Error>
+where
+    Child: LoadFrom<T, Args>,
+    Child::Context: ContextWithLoadRecorder,
+{
+    ctx.load_recorder()
+        .record_call(LoadCall::new::<Child, T>(ids.len(), field_args));
+    load_from_in_chunks(ids, args, ctx, chunk_size)
+}
+
+/// The recording version of [`load_cached`][]. Only the ids missing from the cache are loaded,
+/// so nothing is recorded if all the models are cached.
+///
+/// [`load_cached`]: ../fn.load_cached.html
+pub fn record_load_cached<Model, Id, F>(
+    ids: &[Id],
+    field_args: &(),
+    ctx: &<Model as LoadFrom<Id>>::Context,
+    chunk_size: Option<usize>,
+    key: &'static str,
+    id_of: F,
+) -> Result<Vec<Model>, <Model as LoadFrom<Id>>::Error>
+where
+    Model: LoadFrom<Id> + Clone + Send + 'static,
+    <Model as LoadFrom<Id>>::Context: ContextWithCache + ContextWithLoadRecorder,
+    Id: Hash + Eq + Clone + Send + 'static,
+    F: Fn(&Model) -> Id,
+{
+    load_cached_with(ids, ctx, key, id_of, |missing_ids| {
+        record_load_from_in_chunks(missing_ids, field_args, ctx, chunk_size, "()".to_string())
+    })
+}
+
+/// The recording version of [`load_aggregate`][]. The call is recorded with the aggregate as the
+/// model type.
+///
+/// [`load_aggregate`]: ../fn.load_aggregate.html
+#[allow(clippy::too_many_arguments)]
+pub fn record_load_aggregate<A, Node, Model, Args>(
+    nodes: &mut [Node],
+    models: &[Model],
+    args: &Args,
+    ctx: &A::Context,
+    chunk_size: Option<usize>,
+    parent_key: impl Fn(&Model) -> A::Key,
+    association: impl Fn(&mut Node) -> &mut HasAggregate<A::Value>,
+    field_args: String,
+) -> Result<(), A::Error>
+where
+    A: LoadAggregate<Model, Args>,
+    A::Context: ContextWithLoadRecorder,
+{
+    ctx.load_recorder()
+        .record_call(LoadCall::new::<A, Model>(models.len(), field_args));
+    load_aggregate::<A, _, _, _>(
+        nodes,
+        models,
+        args,
+        ctx,
+        chunk_size,
+        parent_key,
+        association,
+    )
+}
+
+/// Record a call to [`LoadTree`][] and then make it. The call is recorded with the depth as the
+/// field arguments.
+///
+/// [`LoadTree`]: ../trait.LoadTree.html
+pub fn record_load_tree<L, T>(
+    parents: &[T],
+    depth: usize,
+    ctx: &L::Context,
+) -> Result<Vec<Vec<T>>, L::Error>
+where
+    L: LoadTree<T>,
+    L::Context: ContextWithLoadRecorder,
+{
+    ctx.load_recorder().record_call(LoadCall::new::<T, T>(
+        parents.len(),
+        format!("depth: {}", depth),
+    ));
+    L::load(parents, depth, ctx)
+}
+
+/// Format field arguments with `Debug`, or with their type name if they don't implement it.
+///
+/// Used by the code generated for `#[eager_loading(record_loads)]`, which calls
+/// `(&FieldArgs(field_args)).render()` with both [`RenderDebug`][] and [`RenderTypeName`][] in
+/// scope. Method resolution picks `RenderDebug` when the concrete type implements `Debug`.
+///
+/// [`RenderDebug`]: trait.RenderDebug.html
+/// [`RenderTypeName`]: trait.RenderTypeName.html
+#[doc(hidden)]
+pub struct FieldArgs<'a, T>(pub &'a T);
+
+#[doc(hidden)]
+pub trait RenderDebug {
+    fn render(&self) -> String;
+}
+
+impl<T: fmt::Debug> RenderDebug for FieldArgs<'_, T> {
+    fn render(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+#[doc(hidden)]
+pub trait RenderTypeName {
+    fn render(&self) -> String;
+}
+
+impl<T> RenderTypeName for &FieldArgs<'_, T> {
+    fn render(&self) -> String {
+        type_name::<T>().to_string()
+    }
+}
+
+/// The async version of [`record_load_from_in_chunks`][].
+///
+/// [`record_load_from_in_chunks`]: fn.record_load_from_in_chunks.html
+#[cfg(feature = "async")]
+pub async fn async_record_load_from_in_chunks<T, Args, Child>(
+    ids: &[T],
+    args: &Args,
+    ctx: &Child::Context,
+    chunk_size: Option<usize>,
+    field_args: String,
+) -> Result<Vec<Child>, Child::Error>
+where
+    Child: crate::AsyncLoadFrom<T, Args>,
+    Child::Context: ContextWithLoadRecorder,
+{
+    ctx.load_recorder()
+        .record_call(LoadCall::new::<Child, T>(ids.len(), field_args));
+    crate::async_load_from_in_chunks(ids, args, ctx, chunk_size).await
+}
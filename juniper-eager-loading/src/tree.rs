@@ -0,0 +1,199 @@
+use crate::{EagerLoadChildrenOfType, EagerLoading};
+use juniper_from_schema::{QueryTrail, Walked};
+
+/// How to load several levels of a self-referential tree at once, such as with a recursive query.
+///
+/// This is the tree version of [`LoadFrom`][]. It is implemented for a type that names the tree,
+/// since a model might be part of several trees, such as the children and the parent of a
+/// category.
+///
+/// Without a `LoadTree` implementation the levels are loaded one at a time with [`LoadFrom`][].
+/// See [`EagerLoadTree`][] for more details.
+///
+/// # Example
+///
+/// ```ignore
+/// pub struct CategoryTree;
+///
+/// impl LoadTree<models::Category> for CategoryTree {
+///     type Error = diesel::result::Error;
+///     type Context = Context;
+///
+///     fn load(
+///         categories: &[models::Category],
+///         depth: usize,
+///         ctx: &Self::Context,
+///     ) -> Result<Vec<Vec<models::Category>>, Self::Error> {
+///         // WITH RECURSIVE tree AS (
+///         //     SELECT categories.*, 1 AS depth FROM categories WHERE parent_id IN (...)
+///         //     UNION ALL
+///         //     SELECT categories.*, tree.depth + 1 FROM categories
+///         //     INNER JOIN tree ON categories.parent_id = tree.id
+///         //     WHERE tree.depth < $depth
+///         // )
+///         // SELECT * FROM tree
+///     }
+/// }
+///
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = diesel::result::Error)]
+/// pub struct Category {
+///     category: models::Category,
+///     #[has_many(root_model_field = category, foreign_key_field = parent_id, tree_loader = CategoryTree)]
+///     children: HasMany<Category>,
+/// }
+/// ```
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`EagerLoadTree`]: trait.EagerLoadTree.html
+pub trait LoadTree<T> {
+    /// The error type.
+    type Error;
+
+    /// Your Juniper context type.
+    type Context;
+
+    /// Load at most `depth` levels of the tree below `parents`.
+    ///
+    /// The first list holds the models associated with `parents`, the second list the models
+    /// associated with those, and so on. Fewer lists can be returned if the tree isn't that deep.
+    fn load(
+        parents: &[T],
+        depth: usize,
+        context: &Self::Context,
+    ) -> Result<Vec<Vec<T>>, Self::Error>;
+}
+
+/// Eager loading of self-referential associations, such as `Category.children` or
+/// `Employee.manager`, one level of the tree at a time.
+///
+/// Normally each level of nesting in a query is loaded by eager loading the children, which then
+/// eager load their own children, and so on. For associations marked with `tree`,
+/// `#[derive(EagerLoading)]` instead looks at how deep the `QueryTrail` goes, loads all the levels
+/// up front, and then builds the nodes from the bottom of the tree and up. The levels are loaded
+/// with one [`LoadFrom`][] call each, or all at once through a [`LoadTree`][] implementation.
+///
+/// ```ignore
+/// #[derive(Clone, EagerLoading)]
+/// #[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+/// pub struct Employee {
+///     employee: models::Employee,
+///     #[option_has_one(root_model_field = employee, tree)]
+///     manager: OptionHasOne<Box<Employee>>,
+///     #[has_many(root_model_field = employee, foreign_key_field = manager_id, tree)]
+///     reports: HasMany<Employee>,
+/// }
+/// ```
+///
+/// `tree` is supported on `#[has_one]`, `#[option_has_one]`, and `#[has_many]` associations whose
+/// children have the same type as the parent. They cannot take `field_arguments` and cannot be
+/// used with `#[eager_loading(asynchronous)]` or `#[eager_loading(concurrent)]`.
+///
+/// `#[derive(EagerLoading)]` implements this trait for types that have tree associations.
+///
+/// [`LoadFrom`]: trait.LoadFrom.html
+/// [`LoadTree`]: trait.LoadTree.html
+pub trait EagerLoadTree: EagerLoading {
+    /// Eager load all the associations of the nodes, except the tree association named
+    /// `skip_tree` which is being loaded by [`eager_load_tree`][].
+    ///
+    /// [`eager_load_tree`]: fn.eager_load_tree.html
+    fn eager_load_associations(
+        nodes: &mut [Self],
+        models: &[Self::Model],
+        ctx: &Self::Context,
+        trail: &QueryTrail<'_, Self, Walked>,
+        skip_tree: Option<&str>,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Eager load a tree association of the nodes.
+///
+/// `trail` is the trail of the first level below the nodes and `child_trail` walks from one level
+/// to the next. `load_levels` is given the models and the depth of the trail and should return
+/// the models of each level, such as [`load_tree_breadth_first`][] does.
+///
+/// This is the function used by the code generated for associations marked with `tree`.
+///
+/// [`load_tree_breadth_first`]: fn.load_tree_breadth_first.html
+#[allow(clippy::too_many_arguments)]
+pub fn eager_load_tree<'a, Node, ImplContext>(
+    nodes: &mut [Node],
+    models: &[Node::Model],
+    ctx: &Node::Context,
+    trail: QueryTrail<'a, Node, Walked>,
+    field: &str,
+    child_trail: impl Fn(&QueryTrail<'a, Node, Walked>) -> Option<QueryTrail<'a, Node, Walked>>,
+    load_levels: impl FnOnce(&[Node::Model], usize) -> Result<Vec<Vec<Node::Model>>, Node::Error>,
+) -> Result<(), Node::Error>
+where
+    Node: EagerLoadTree + EagerLoadChildrenOfType<'a, Node, ImplContext, (), FieldArguments = ()>,
+{
+    let mut trails = vec![trail];
+    while let Some(trail) = trails.last().and_then(&child_trail) {
+        trails.push(trail);
+    }
+
+    let levels = load_levels(models, trails.len())?;
+
+    let mut children = Vec::new();
+    for (depth, level_models) in levels.iter().enumerate().rev() {
+        let mut level_nodes = Node::from_db_models(level_models);
+        Node::eager_load_associations(
+            &mut level_nodes,
+            level_models,
+            ctx,
+            &trails[depth],
+            Some(field),
+        )?;
+
+        if depth + 1 < trails.len() {
+            let level_children = std::mem::take(&mut children)
+                .into_iter()
+                .map(|child| (child, ()))
+                .collect();
+            Node::assign_children(&mut level_nodes, level_children, &(), ctx);
+        }
+
+        children = level_nodes;
+    }
+
+    let children = children.into_iter().map(|child| (child, ())).collect();
+    Node::assign_children(nodes, children, &(), ctx);
+
+    Ok(())
+}
+
+/// Load the levels of a tree one at a time with [`EagerLoadChildrenOfType::load_children`][].
+///
+/// Each level is loaded with a single call for all the models of the level above it, so a tree
+/// that is `depth` levels deep takes `depth` calls to [`LoadFrom`][].
+///
+/// [`EagerLoadChildrenOfType::load_children`]: trait.EagerLoadChildrenOfType.html#tymethod.load_children
+/// [`LoadFrom`]: trait.LoadFrom.html
+pub fn load_tree_breadth_first<'a, Node, ImplContext>(
+    models: &[Node::Model],
+    depth: usize,
+    ctx: &Node::Context,
+) -> Result<Vec<Vec<Node::Model>>, Node::Error>
+where
+    Node: EagerLoadChildrenOfType<'a, Node, ImplContext, (), FieldArguments = ()>,
+{
+    let mut levels: Vec<Vec<Node::Model>> = Vec::with_capacity(depth);
+
+    while levels.len() < depth {
+        let parents = levels.last().map(|level| &level[..]).unwrap_or(models);
+        if parents.is_empty() {
+            break;
+        }
+
+        let level = Node::load_children(parents, &(), ctx)?
+            .into_child_and_join_models()
+            .into_iter()
+            .map(|(model, ())| model)
+            .collect();
+        levels.push(level);
+    }
+
+    Ok(levels)
+}
@@ -0,0 +1,305 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasAggregate, HasCount,
+    LoadAggregate,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::cell::Cell;
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        carCount: Int! @juniper(ownership: "owned")
+        hasEmployments: Boolean! @juniper(ownership: "owned")
+    }
+}
+
+mod db_schema {
+    table! {
+        users (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        cars (id) {
+            id -> Integer,
+            user_id -> Integer,
+        }
+    }
+
+    table! {
+        employments (id) {
+            id -> Integer,
+            user_id -> Integer,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+    }
+}
+
+use db_schema::{cars, employments, users};
+
+pub struct CarCount;
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        models::User.id: i32 -> count(cars.user_id, CarCount),
+    }
+}
+
+pub struct HasEmployments;
+
+thread_local! {
+    static HAS_EMPLOYMENTS_LOADS: Cell<usize> = const { Cell::new(0) };
+}
+
+impl LoadAggregate<models::User> for HasEmployments {
+    type Key = i32;
+    type Value = bool;
+    type Error = diesel::result::Error;
+    type Context = Context;
+
+    fn load(
+        users: &[models::User],
+        _: &(),
+        ctx: &Self::Context,
+    ) -> Result<Vec<(i32, bool)>, Self::Error> {
+        HAS_EMPLOYMENTS_LOADS.with(|loads| loads.set(loads.get() + 1));
+
+        let user_ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+
+        let employed_user_ids = employments::table
+            .filter(employments::user_id.eq_any(user_ids))
+            .select(employments::user_id)
+            .distinct()
+            .load::<i32>(ctx.db())?;
+
+        Ok(employed_user_ids
+            .into_iter()
+            .map(|user_id| (user_id, true))
+            .collect())
+    }
+}
+
+pub struct Context {
+    db: SqliteConnection,
+}
+
+impl Context {
+    fn db(&self) -> &SqliteConnection {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order(users::id)
+            .load::<models::User>(ctx.db())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct User {
+    user: models::User,
+    #[has_aggregate(aggregate = CarCount)]
+    car_count: HasCount,
+    #[has_aggregate(aggregate = HasEmployments, key_field = id, chunk_size = 2)]
+    has_employments: HasAggregate<bool>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_car_count(&self, _: &Executor<'_, Context>) -> FieldResult<i32> {
+        Ok(*self.car_count.try_unwrap()? as i32)
+    }
+
+    fn field_has_employments(&self, _: &Executor<'_, Context>) -> FieldResult<bool> {
+        Ok(*self.has_employments.try_unwrap()?)
+    }
+}
+
+fn setup_db() -> Context {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (id INTEGER PRIMARY KEY)",
+        "CREATE TABLE cars (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL)",
+        "CREATE TABLE employments (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL)",
+        "INSERT INTO users (id) VALUES (1), (2), (3)",
+        "INSERT INTO cars (id, user_id) VALUES (1, 1), (2, 2), (3, 1), (4, 1)",
+        "INSERT INTO employments (id, user_id) VALUES (1, 2), (2, 3), (3, 3)",
+    ] {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+
+    Context { db }
+}
+
+#[test]
+fn loading_counts() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                carCount
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "carCount": 3 },
+                { "id": 2, "carCount": 1 },
+                { "id": 3, "carCount": 0 },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_custom_aggregates() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                hasEmployments
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "hasEmployments": false },
+                { "id": 2, "hasEmployments": true },
+                { "id": 3, "hasEmployments": true },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn aggregates_are_loaded_in_chunks() {
+    HAS_EMPLOYMENTS_LOADS.with(|loads| loads.set(0));
+
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                hasEmployments
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "hasEmployments": false },
+                { "id": 2, "hasEmployments": true },
+                { "id": 3, "hasEmployments": true },
+            ]
+        }),
+        json,
+    );
+
+    // Three users in chunks of two.
+    assert_eq!(2, HAS_EMPLOYMENTS_LOADS.with(Cell::get));
+
+    // The counts generated by the Diesel macros stay within the bound parameter limit of SQLite.
+    assert_eq!(
+        Some(999),
+        <CarCount as LoadAggregate<models::User>>::chunk_size()
+    );
+}
+
+#[test]
+fn aggregates_are_not_loaded_unless_queried() {
+    let ctx = setup_db();
+    let user_models = users::table
+        .order(users::id)
+        .load::<models::User>(ctx.db())
+        .unwrap();
+
+    let users = User::eager_load_each(
+        &user_models,
+        &ctx,
+        &juniper_eager_loading::unselected_trail(),
+    )
+    .unwrap();
+
+    assert!(users[0].car_count.try_unwrap().is_err());
+    assert!(users[0].has_employments.try_unwrap().is_err());
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
@@ -0,0 +1,541 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    in_memory::Table, prelude::*, Cache, ContextWithCache, EagerLoading, HasMany, HasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        city: City!
+        birthCountry: Country!
+    }
+
+    type Country {
+        id: Int!
+        cities: [City!]!
+    }
+
+    type City {
+        id: Int!
+        country: Country!
+    }
+}
+
+mod models {
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+        pub city_id: i32,
+        pub birth_country_code: i32,
+    }
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct Country {
+        pub id: i32,
+        pub numeric_code: i32,
+    }
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct City {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    // Countries are looked up by both their id and their numeric code, so this loads the
+    // countries matching either.
+    impl juniper_eager_loading::LoadFrom<i32> for Country {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let models = ctx
+                .db
+                .countries
+                .all_values()
+                .into_iter()
+                .filter(|value| ids.contains(&value.id) || ids.contains(&value.numeric_code))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<i32> for City {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let models = ctx
+                .db
+                .cities
+                .all_values()
+                .into_iter()
+                .filter(|value| ids.contains(&value.id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<Country> for City {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(
+            countries: &[Country],
+            _: &(),
+            ctx: &Self::Context,
+        ) -> Result<Vec<Self>, Self::Error> {
+            let country_ids = countries
+                .iter()
+                .map(|country| country.id)
+                .collect::<Vec<_>>();
+            let models = ctx
+                .db
+                .cities
+                .all_values()
+                .into_iter()
+                .filter(|city| country_ids.contains(&city.country_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+}
+
+pub struct Db {
+    users: Table<i32, models::User>,
+    countries: Table<i32, models::Country>,
+    cities: Table<i32, models::City>,
+}
+
+pub struct Context {
+    db: Db,
+    cache: Cache,
+}
+
+impl juniper::Context for Context {}
+
+impl ContextWithCache for Context {
+    fn cache(&self) -> &Cache {
+        &self.cache
+    }
+}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let mut user_models = ctx
+            .db
+            .users
+            .all_values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        user_models.sort_by_key(|user| user.id);
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>, cache)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_one(default)]
+    city: HasOne<City>,
+    #[has_one(
+        foreign_key_field = birth_country_code,
+        child_primary_key_field = numeric_code,
+        root_model_field = country
+    )]
+    birth_country: HasOne<Country>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_city(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, City, Walked>,
+    ) -> FieldResult<&City> {
+        Ok(self.city.try_unwrap()?)
+    }
+
+    fn field_birth_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.birth_country.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>, cache)]
+pub struct Country {
+    country: models::Country,
+    #[has_many(root_model_field = city)]
+    cities: HasMany<City>,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_cities(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, City, Walked>,
+    ) -> FieldResult<&Vec<City>> {
+        Ok(self.cities.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>, cache)]
+pub struct City {
+    city: models::City,
+    #[has_one(default)]
+    country: HasOne<Country>,
+}
+
+impl CityFields for City {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.city.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+}
+
+#[test]
+fn models_are_only_loaded_once() {
+    let mut users = Table::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
+
+    countries.insert(
+        1,
+        models::Country {
+            id: 1,
+            numeric_code: 100,
+        },
+    );
+    cities.insert(
+        2,
+        models::City {
+            id: 2,
+            country_id: 1,
+        },
+    );
+    users.insert(
+        3,
+        models::User {
+            id: 3,
+            country_id: 1,
+            city_id: 2,
+            birth_country_code: 100,
+        },
+    );
+
+    let db = Db {
+        users,
+        countries,
+        cities,
+    };
+
+    let (json, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country {
+                    id
+                    cities {
+                        id
+                        country { id }
+                    }
+                }
+                city {
+                    id
+                    country { id }
+                }
+            }
+        }
+    "#,
+        db,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 3,
+                    "country": {
+                        "id": 1,
+                        "cities": [
+                            {
+                                "id": 2,
+                                "country": { "id": 1 }
+                            },
+                        ],
+                    },
+                    "city": {
+                        "id": 2,
+                        "country": { "id": 1 }
+                    },
+                },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, counts.user_reads);
+    assert_eq!(1, counts.country_reads);
+    assert_eq!(2, counts.city_reads);
+}
+
+#[test]
+fn only_missing_ids_are_loaded() {
+    let mut users = Table::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
+
+    countries.insert(
+        1,
+        models::Country {
+            id: 1,
+            numeric_code: 100,
+        },
+    );
+    countries.insert(
+        2,
+        models::Country {
+            id: 2,
+            numeric_code: 200,
+        },
+    );
+    cities.insert(
+        3,
+        models::City {
+            id: 3,
+            country_id: 1,
+        },
+    );
+    cities.insert(
+        4,
+        models::City {
+            id: 4,
+            country_id: 2,
+        },
+    );
+    users.insert(
+        5,
+        models::User {
+            id: 5,
+            country_id: 1,
+            city_id: 4,
+            birth_country_code: 100,
+        },
+    );
+
+    let db = Db {
+        users,
+        countries,
+        cities,
+    };
+
+    let cache = Cache::new();
+    cache.insert(
+        "id",
+        1,
+        models::Country {
+            id: 1,
+            numeric_code: 100,
+        },
+    );
+
+    let (json, counts) = run_query_with_cache(
+        r#"
+        query Test {
+            users {
+                country { id }
+                city {
+                    country { id }
+                }
+            }
+        }
+    "#,
+        db,
+        cache,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "country": { "id": 1 },
+                    "city": {
+                        "country": { "id": 2 }
+                    },
+                },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, counts.country_reads);
+}
+
+#[test]
+fn models_found_through_different_fields_are_cached_separately() {
+    let mut users = Table::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
+
+    // The id of each country is the numeric code of the other one.
+    countries.insert(
+        1,
+        models::Country {
+            id: 1,
+            numeric_code: 2,
+        },
+    );
+    countries.insert(
+        2,
+        models::Country {
+            id: 2,
+            numeric_code: 1,
+        },
+    );
+    cities.insert(
+        3,
+        models::City {
+            id: 3,
+            country_id: 1,
+        },
+    );
+    users.insert(
+        4,
+        models::User {
+            id: 4,
+            country_id: 1,
+            city_id: 3,
+            birth_country_code: 1,
+        },
+    );
+
+    let db = Db {
+        users,
+        countries,
+        cities,
+    };
+
+    let (json, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                country { id }
+                birthCountry { id }
+            }
+        }
+    "#,
+        db,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "country": { "id": 1 },
+                    "birthCountry": { "id": 2 },
+                },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(2, counts.country_reads);
+}
+
+struct DbStats {
+    user_reads: usize,
+    country_reads: usize,
+    city_reads: usize,
+}
+
+fn run_query(query: &str, db: Db) -> (Value, DbStats) {
+    run_query_with_cache(query, db, Cache::new())
+}
+
+fn run_query_with_cache(query: &str, db: Db, cache: Cache) -> (Value, DbStats) {
+    let ctx = Context { db, cache };
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json: Value = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+
+    (
+        json,
+        DbStats {
+            user_reads: ctx.db.users.reads_count(),
+            country_reads: ctx.db.countries.reads_count(),
+            city_reads: ctx.db.cities.reads_count(),
+        },
+    )
+}
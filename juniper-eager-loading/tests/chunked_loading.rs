@@ -0,0 +1,272 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasMany, HasOne};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        cars: [Car!]!
+    }
+
+    type Country {
+        id: Int!
+    }
+
+    type Car {
+        id: Int!
+    }
+}
+
+mod models {
+    use juniper_eager_loading::LoadFrom;
+
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Country {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+
+    impl LoadFrom<i32> for Country {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            ctx.country_chunks.lock().unwrap().push(ids.len());
+
+            Ok(ctx
+                .db
+                .countries
+                .all_values()
+                .into_iter()
+                .filter(|country| ids.contains(&country.id))
+                .cloned()
+                .collect())
+        }
+
+        fn chunk_size() -> Option<usize> {
+            Some(2)
+        }
+    }
+
+    impl LoadFrom<User> for Car {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(users: &[User], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            ctx.car_chunks.lock().unwrap().push(users.len());
+            let user_ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+
+            let mut cars = ctx
+                .db
+                .cars
+                .all_values()
+                .into_iter()
+                .filter(|car| user_ids.contains(&car.user_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            cars.sort_by_key(|car| car.id);
+            Ok(cars)
+        }
+
+        fn chunk_size() -> Option<usize> {
+            Some(100)
+        }
+    }
+}
+
+pub struct Db {
+    users: Vec<models::User>,
+    countries: Table<i32, models::Country>,
+    cars: Table<i32, models::Car>,
+}
+
+pub struct Context {
+    db: Db,
+    country_chunks: Mutex<Vec<usize>>,
+    car_chunks: Mutex<Vec<usize>>,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let users = User::eager_load_each(&ctx.db.users, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_many(root_model_field = car, chunk_size = 3)]
+    cars: HasMany<Car>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+}
+
+fn setup_db() -> Db {
+    let users = (1..=5)
+        .map(|id| models::User { id, country_id: id })
+        .collect();
+
+    let mut countries = Table::new("countries");
+    for id in 1..=5 {
+        countries.insert(id, models::Country { id });
+    }
+
+    let mut cars = Table::new("cars");
+    for id in 1..=5 {
+        cars.insert(
+            id,
+            models::Car {
+                id,
+                user_id: 6 - id,
+            },
+        );
+    }
+
+    Db {
+        users,
+        countries,
+        cars,
+    }
+}
+
+#[test]
+fn loading_in_chunks() {
+    let ctx = Context {
+        db: setup_db(),
+        country_chunks: Mutex::new(Vec::new()),
+        car_chunks: Mutex::new(Vec::new()),
+    };
+
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id }
+                cars { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "country": { "id": 1 }, "cars": [{ "id": 5 }] },
+                { "id": 2, "country": { "id": 2 }, "cars": [{ "id": 4 }] },
+                { "id": 3, "country": { "id": 3 }, "cars": [{ "id": 3 }] },
+                { "id": 4, "country": { "id": 4 }, "cars": [{ "id": 2 }] },
+                { "id": 5, "country": { "id": 5 }, "cars": [{ "id": 1 }] },
+            ]
+        }),
+        json,
+    );
+
+    // `LoadFrom::chunk_size` is used by default.
+    assert_eq!(vec![2, 2, 1], *ctx.country_chunks.lock().unwrap());
+    // `chunk_size` on the association takes precedence.
+    assert_eq!(vec![3, 2], *ctx.car_chunks.lock().unwrap());
+}
+
+fn run_query(query: &str, ctx: &Context) -> Value {
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
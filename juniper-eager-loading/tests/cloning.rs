@@ -0,0 +1,342 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasMany, HasOne};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        city: City!
+    }
+
+    type Country {
+        id: Int!
+        cities: [City!]!
+    }
+
+    type City {
+        id: Int!
+    }
+}
+
+mod models {
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+        pub city_id: i32,
+    }
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct Country {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct City {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    impl juniper_eager_loading::LoadFrom<i32> for Country {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let models = ctx
+                .db
+                .countries
+                .all_values()
+                .into_iter()
+                .filter(|value| ids.contains(&value.id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<i32> for City {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let models = ctx
+                .db
+                .cities
+                .all_values()
+                .into_iter()
+                .filter(|value| ids.contains(&value.id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<Country> for City {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(
+            countries: &[Country],
+            _: &(),
+            ctx: &Self::Context,
+        ) -> Result<Vec<Self>, Self::Error> {
+            let country_ids = countries
+                .iter()
+                .map(|country| country.id)
+                .collect::<Vec<_>>();
+            let models = ctx
+                .db
+                .cities
+                .all_values()
+                .into_iter()
+                .filter(|city| country_ids.contains(&city.country_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+}
+
+pub struct Db {
+    users: Table<i32, models::User>,
+    countries: Table<i32, models::Country>,
+    cities: Table<i32, models::City>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let mut user_models = ctx
+            .db
+            .users
+            .all_values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        user_models.sort_by_key(|user| user.id);
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_one(default)]
+    city: HasOne<City>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_city(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, City, Walked>,
+    ) -> FieldResult<&City> {
+        Ok(self.city.try_unwrap()?)
+    }
+}
+
+static COUNTRY_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Country {
+    country: models::Country,
+    #[has_many(root_model_field = city)]
+    cities: HasMany<City>,
+}
+
+impl Clone for Country {
+    fn clone(&self) -> Self {
+        COUNTRY_CLONES.fetch_add(1, Ordering::SeqCst);
+        Self {
+            country: self.country.clone(),
+            cities: self.cities.clone(),
+        }
+    }
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_cities(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, City, Walked>,
+    ) -> FieldResult<&Vec<City>> {
+        Ok(self.cities.try_unwrap()?)
+    }
+}
+
+static CITY_CLONES: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct City {
+    city: models::City,
+}
+
+impl Clone for City {
+    fn clone(&self) -> Self {
+        CITY_CLONES.fetch_add(1, Ordering::SeqCst);
+        Self {
+            city: self.city.clone(),
+        }
+    }
+}
+
+impl CityFields for City {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.city.id)
+    }
+}
+
+#[test]
+fn children_are_only_cloned_when_shared_between_parents() {
+    let mut users = Table::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
+
+    countries.insert(1, models::Country { id: 1 });
+    countries.insert(2, models::Country { id: 2 });
+    for id in 1..=2 {
+        cities.insert(
+            id,
+            models::City {
+                id,
+                country_id: id % 2 + 1,
+            },
+        );
+    }
+    for id in 1..=4 {
+        users.insert(
+            id,
+            models::User {
+                id,
+                country_id: id % 2 + 1,
+                city_id: 1,
+            },
+        );
+    }
+
+    let db = Db {
+        users,
+        countries,
+        cities,
+    };
+
+    let (json, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country {
+                    id
+                    cities { id }
+                }
+            }
+        }
+    "#,
+        db,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "country": { "id": 2, "cities": [{ "id": 1 }] } },
+                { "id": 2, "country": { "id": 1, "cities": [{ "id": 2 }] } },
+                { "id": 3, "country": { "id": 2, "cities": [{ "id": 1 }] } },
+                { "id": 4, "country": { "id": 1, "cities": [{ "id": 2 }] } },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, counts.country_reads);
+    assert_eq!(1, counts.city_reads);
+
+    // Each country belongs to two users so it is moved into one of them and cloned for the other.
+    // The cities each belong to a single country so they are moved into it, and only cloned along
+    // with the country.
+    assert_eq!(2, COUNTRY_CLONES.load(Ordering::SeqCst));
+    assert_eq!(2, CITY_CLONES.load(Ordering::SeqCst));
+}
+
+struct DbStats {
+    user_reads: usize,
+    country_reads: usize,
+    city_reads: usize,
+}
+
+fn run_query(query: &str, db: Db) -> (Value, DbStats) {
+    let ctx = Context { db };
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json: Value = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+
+    (
+        json,
+        DbStats {
+            user_reads: ctx.db.users.reads_count(),
+            country_reads: ctx.db.countries.reads_count(),
+            city_reads: ctx.db.cities.reads_count(),
+        },
+    )
+}
@@ -3,7 +3,7 @@ extern crate diesel;
 
 use static_assertions::assert_impl_all;
 use diesel::prelude::*;
-use juniper_eager_loading::{LoadFrom, impl_load_from_for_diesel_mysql};
+use juniper_eager_loading::{LoadAggregate, LoadFrom, impl_load_from_for_diesel_mysql};
 
 table! {
     users (id) {
@@ -25,6 +25,28 @@ table! {
     }
 }
 
+table! {
+    tenant_users (tenant_id, id) {
+        tenant_id -> Integer,
+        id -> Integer,
+    }
+}
+
+table! {
+    tenant_cars (tenant_id, id) {
+        tenant_id -> Integer,
+        id -> Integer,
+        user_id -> Integer,
+    }
+}
+
+table! {
+    cars (id) {
+        id -> Integer,
+        user_id -> Integer,
+    }
+}
+
 #[derive(Queryable)]
 struct User {
     id: i32,
@@ -42,6 +64,35 @@ struct Employment {
     company_id: i32,
 }
 
+#[derive(Queryable)]
+struct TenantUser {
+    tenant_id: i32,
+    id: i32,
+}
+
+#[derive(Queryable)]
+struct TenantCar {
+    tenant_id: i32,
+    id: i32,
+    user_id: i32,
+}
+
+#[derive(Queryable)]
+struct Car {
+    id: i32,
+    user_id: i32,
+}
+
+struct CarCount;
+
+struct UserArgs {
+    min_id: i32,
+}
+
+fn users_above_min_id(args: &UserArgs) -> diesel::dsl::Gt<users::id, i32> {
+    users::id.gt(args.min_id)
+}
+
 struct Context {
     db: MysqlConnection,
 }
@@ -64,11 +115,52 @@ impl_load_from_for_diesel_mysql! {
         Company.id -> (employments.company_id, Employment),
         Employment.company_id -> (companies.id, Company),
         Employment.user_id -> (users.id, User),
+        (i32, i32) -> (tenant_users, TenantUser),
+        TenantUser.(tenant_id, id) -> (tenant_cars.(tenant_id, user_id), TenantCar),
+        TenantCar.(tenant_id, user_id) -> (tenant_users.(tenant_id, id), TenantUser),
+        i32 -> (tenant_cars.user_id, TenantCar),
+        (i32, i32) -> (tenant_cars.(tenant_id, user_id), TenantCar),
+        i32 -> (users, User, args = UserArgs, filter = users_above_min_id),
+        Company.id -> (
+            employments.company_id,
+            Employment,
+            args = UserArgs,
+            filter = |args| employments::user_id.gt(args.min_id),
+        ),
+    }
+}
+
+struct PoolContext {
+    primary: MysqlConnection,
+    replica: MysqlConnection,
+}
+
+impl PoolContext {
+    fn primary(&self) -> Result<&MysqlConnection, diesel::result::Error> {
+        Ok(&self.primary)
+    }
+}
+
+impl_load_from_for_diesel_mysql! {
+    (
+        error = diesel::result::Error,
+        context = PoolContext,
+        connection = |ctx| ctx.primary()?,
+    ) => {
+        i32 -> (cars, Car),
+        User.id -> (cars.user_id, Car, connection = |ctx| &ctx.replica),
+        User.id: i32 -> count(cars.user_id, CarCount, connection = |ctx| &ctx.replica),
     }
 }
 
 assert_impl_all!(User: LoadFrom<i32>, LoadFrom<Employment>, LoadFrom<Employment>);
 assert_impl_all!(Company: LoadFrom<i32>);
 assert_impl_all!(Employment: LoadFrom<i32>, LoadFrom<User>, LoadFrom<Company>);
+assert_impl_all!(TenantUser: LoadFrom<(i32, i32)>, LoadFrom<TenantCar>);
+assert_impl_all!(TenantCar: LoadFrom<TenantUser>, LoadFrom<i32>, LoadFrom<(i32, i32)>);
+assert_impl_all!(User: LoadFrom<i32, UserArgs>);
+assert_impl_all!(Employment: LoadFrom<Company, UserArgs>);
+assert_impl_all!(Car: LoadFrom<i32>, LoadFrom<User>);
+assert_impl_all!(CarCount: LoadAggregate<User>);
 
 fn main() {}
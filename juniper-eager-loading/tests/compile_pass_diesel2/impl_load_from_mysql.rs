@@ -0,0 +1,187 @@
+extern crate diesel2 as diesel;
+
+use static_assertions::assert_impl_all;
+use diesel::prelude::*;
+use juniper_eager_loading::{LoadAggregate, LoadFrom, impl_load_from_for_diesel_mysql};
+use std::cell::{RefCell, RefMut};
+use std::sync::{Mutex, MutexGuard};
+
+table! {
+    users (id) {
+        id -> Integer,
+    }
+}
+
+table! {
+    companies (id) {
+        id -> Integer,
+    }
+}
+
+table! {
+    employments (id) {
+        id -> Integer,
+        user_id -> Integer,
+        company_id -> Integer,
+    }
+}
+
+table! {
+    tenant_users (tenant_id, id) {
+        tenant_id -> Integer,
+        id -> Integer,
+    }
+}
+
+table! {
+    tenant_cars (tenant_id, id) {
+        tenant_id -> Integer,
+        id -> Integer,
+        user_id -> Integer,
+    }
+}
+
+table! {
+    cars (id) {
+        id -> Integer,
+        user_id -> Integer,
+    }
+}
+
+#[derive(Queryable)]
+struct User {
+    id: i32,
+}
+
+#[derive(Queryable)]
+struct Company {
+    id: i32,
+}
+
+#[derive(Queryable)]
+struct Employment {
+    id: i32,
+    user_id: i32,
+    company_id: i32,
+}
+
+#[derive(Queryable)]
+struct TenantUser {
+    tenant_id: i32,
+    id: i32,
+}
+
+#[derive(Queryable)]
+struct TenantCar {
+    tenant_id: i32,
+    id: i32,
+    user_id: i32,
+}
+
+#[derive(Queryable)]
+struct Car {
+    id: i32,
+    user_id: i32,
+}
+
+struct CarCount;
+
+struct EmploymentCount;
+
+struct UserArgs {
+    min_id: i32,
+}
+
+fn users_above_min_id(args: &UserArgs) -> diesel::dsl::Gt<users::id, i32> {
+    users::id.gt(args.min_id)
+}
+
+struct PageArgs;
+
+impl PageArgs {
+    fn first(&self) -> Option<i32> {
+        None
+    }
+
+    fn after(&self) -> Option<String> {
+        None
+    }
+}
+
+struct Context {
+    db: RefCell<MysqlConnection>,
+}
+
+impl Context {
+    fn db(&self) -> RefMut<'_, MysqlConnection> {
+        self.db.borrow_mut()
+    }
+}
+
+impl_load_from_for_diesel_mysql! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+        diesel = 2,
+    ) => {
+        i32 -> (users, User),
+        i32 -> (companies, Company),
+        i32 -> (employments, Employment),
+        User.id -> (employments.user_id, Employment),
+        Company.id -> (employments.company_id, Employment),
+        Employment.company_id -> (companies.id, Company),
+        Employment.user_id -> (users.id, User),
+        (i32, i32) -> (tenant_users, TenantUser),
+        TenantUser.(tenant_id, id) -> (tenant_cars.(tenant_id, user_id), TenantCar),
+        TenantCar.(tenant_id, user_id) -> (tenant_users.(tenant_id, id), TenantUser),
+        i32 -> (tenant_cars.user_id, TenantCar),
+        (i32, i32) -> (tenant_cars.(tenant_id, user_id), TenantCar),
+        i32 -> (users, User, args = UserArgs, filter = users_above_min_id),
+        Company.id -> (
+            employments.company_id,
+            Employment,
+            args = UserArgs,
+            filter = |args| employments::user_id.gt(args.min_id),
+        ),
+        Company.id -> (employments.company_id, Employment, paginate = PageArgs, order_by = user_id),
+        Company.id: i32 -> count(employments.company_id, EmploymentCount),
+        TenantUser.(tenant_id, id): (i32, i32) -> count(tenant_cars.(tenant_id, user_id), CarCount),
+    }
+}
+
+struct PoolContext {
+    primary: Mutex<MysqlConnection>,
+    replica: RefCell<MysqlConnection>,
+}
+
+impl PoolContext {
+    fn primary(&self) -> Result<MutexGuard<'_, MysqlConnection>, diesel::result::Error> {
+        Ok(self.primary.lock().unwrap())
+    }
+}
+
+impl_load_from_for_diesel_mysql! {
+    (
+        error = diesel::result::Error,
+        context = PoolContext,
+        diesel = 2,
+        connection = |ctx| ctx.primary()?,
+    ) => {
+        i32 -> (cars, Car),
+        User.id -> (cars.user_id, Car, connection = |ctx| ctx.replica.borrow_mut()),
+        User.id: i32 -> count(cars.user_id, CarCount, connection = |ctx| ctx.replica.borrow_mut()),
+    }
+}
+
+assert_impl_all!(User: LoadFrom<i32>, LoadFrom<Employment>, LoadFrom<Employment>);
+assert_impl_all!(Company: LoadFrom<i32>);
+assert_impl_all!(Employment: LoadFrom<i32>, LoadFrom<User>, LoadFrom<Company>);
+assert_impl_all!(TenantUser: LoadFrom<(i32, i32)>, LoadFrom<TenantCar>);
+assert_impl_all!(TenantCar: LoadFrom<TenantUser>, LoadFrom<i32>, LoadFrom<(i32, i32)>);
+assert_impl_all!(User: LoadFrom<i32, UserArgs>);
+assert_impl_all!(Employment: LoadFrom<Company, UserArgs>, LoadFrom<Company, PageArgs>);
+assert_impl_all!(EmploymentCount: LoadAggregate<Company>);
+assert_impl_all!(CarCount: LoadAggregate<TenantUser>, LoadAggregate<User>);
+assert_impl_all!(Car: LoadFrom<i32>, LoadFrom<User>);
+
+fn main() {}
@@ -0,0 +1,305 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasCount, HasMany, HasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+      cars: [Car!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        tenantId: Int!
+        id: Int!
+        cars: [Car!]!
+        carCount: Int! @juniper(ownership: "owned")
+    }
+
+    type Car {
+        tenantId: Int!
+        id: Int!
+        user: User!
+    }
+}
+
+mod db_schema {
+    table! {
+        users (tenant_id, id) {
+            tenant_id -> Integer,
+            id -> Integer,
+        }
+    }
+
+    table! {
+        cars (tenant_id, id) {
+            tenant_id -> Integer,
+            id -> Integer,
+            user_id -> Integer,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub tenant_id: i32,
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Car {
+        pub tenant_id: i32,
+        pub id: i32,
+        pub user_id: i32,
+    }
+}
+
+use db_schema::{cars, users};
+
+pub struct CarCount;
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        (i32, i32) -> (users, models::User),
+        models::User.(tenant_id, id) -> (cars.(tenant_id, user_id), models::Car),
+        models::User.(tenant_id, id): (i32, i32) -> count(cars.(tenant_id, user_id), CarCount),
+    }
+}
+
+pub struct Context {
+    db: SqliteConnection,
+}
+
+impl Context {
+    fn db(&self) -> &SqliteConnection {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order((users::tenant_id, users::id))
+            .load::<models::User>(ctx.db())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+
+    fn field_cars<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, Car, Walked>,
+    ) -> FieldResult<Vec<Car>> {
+        let ctx = executor.context();
+        let car_models = cars::table
+            .order((cars::tenant_id, cars::id))
+            .load::<models::Car>(ctx.db())?;
+        let cars = Car::eager_load_each(&car_models, ctx, trail)?;
+        Ok(cars)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(
+    context = Context,
+    error = diesel::result::Error,
+    id = (i32, i32),
+    primary_key_field = (tenant_id, id),
+)]
+pub struct User {
+    user: models::User,
+    #[has_many(root_model_field = car, foreign_key_field = (tenant_id, user_id))]
+    cars: HasMany<Car>,
+    #[has_aggregate(aggregate = CarCount)]
+    car_count: HasCount,
+}
+
+impl UserFields for User {
+    fn field_tenant_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.tenant_id)
+    }
+
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+
+    fn field_car_count(&self, _: &Executor<'_, Context>) -> FieldResult<i32> {
+        Ok(*self.car_count.try_unwrap()? as i32)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(
+    context = Context,
+    error = diesel::result::Error,
+    id = (i32, i32),
+    primary_key_field = (tenant_id, id),
+)]
+pub struct Car {
+    car: models::Car,
+    #[has_one(
+        foreign_key_field = (tenant_id, user_id),
+        child_primary_key_field = (tenant_id, id),
+    )]
+    user: HasOne<User>,
+}
+
+impl CarFields for Car {
+    fn field_tenant_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.tenant_id)
+    }
+
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+
+    fn field_user(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<&User> {
+        Ok(self.user.try_unwrap()?)
+    }
+}
+
+// Both tenants have a user and a car with id 1, so matching on `id` alone would mix them up.
+fn setup_db() -> Context {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (tenant_id INTEGER NOT NULL, id INTEGER NOT NULL, PRIMARY KEY (tenant_id, id))",
+        "CREATE TABLE cars (tenant_id INTEGER NOT NULL, id INTEGER NOT NULL, user_id INTEGER NOT NULL, PRIMARY KEY (tenant_id, id))",
+        "INSERT INTO users (tenant_id, id) VALUES (1, 1), (1, 2), (2, 1)",
+        "INSERT INTO cars (tenant_id, id, user_id) VALUES (1, 1, 1), (1, 2, 2), (1, 3, 1), (2, 1, 1)",
+    ] {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+
+    Context { db }
+}
+
+#[test]
+fn loading_has_many_with_composite_keys() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                tenantId
+                id
+                cars { tenantId id }
+                carCount
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "tenantId": 1,
+                    "id": 1,
+                    "cars": [{ "tenantId": 1, "id": 1 }, { "tenantId": 1, "id": 3 }],
+                    "carCount": 2,
+                },
+                {
+                    "tenantId": 1,
+                    "id": 2,
+                    "cars": [{ "tenantId": 1, "id": 2 }],
+                    "carCount": 1,
+                },
+                {
+                    "tenantId": 2,
+                    "id": 1,
+                    "cars": [{ "tenantId": 2, "id": 1 }],
+                    "carCount": 1,
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_has_one_with_composite_keys() {
+    let json = run_query(
+        r#"
+        query Test {
+            cars {
+                tenantId
+                id
+                user { tenantId id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "cars": [
+                { "tenantId": 1, "id": 1, "user": { "tenantId": 1, "id": 1 } },
+                { "tenantId": 1, "id": 2, "user": { "tenantId": 1, "id": 2 } },
+                { "tenantId": 1, "id": 3, "user": { "tenantId": 1, "id": 1 } },
+                { "tenantId": 2, "id": 1, "user": { "tenantId": 2, "id": 1 } },
+            ]
+        }),
+        json,
+    );
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
@@ -0,0 +1,441 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasMany, HasOne};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        city: City!
+    }
+
+    type Country {
+        id: Int!
+        cities: [City!]!
+    }
+
+    type City {
+        id: Int!
+    }
+}
+
+type Error = Box<dyn std::error::Error + Send + Sync>;
+
+mod models {
+    use super::Error;
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+        pub city_id: i32,
+    }
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct Country {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
+    pub struct City {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    impl juniper_eager_loading::LoadFrom<i32> for Country {
+        type Error = Error;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let _guard = ctx.track_in_flight();
+
+            let models = ctx
+                .db
+                .countries
+                .all_values()
+                .into_iter()
+                .filter(|value| ids.contains(&value.id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<i32> for City {
+        type Error = Error;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let _guard = ctx.track_in_flight();
+
+            let models = ctx
+                .db
+                .cities
+                .all_values()
+                .into_iter()
+                .filter(|value| ids.contains(&value.id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<Country> for City {
+        type Error = Error;
+        type Context = super::Context;
+
+        fn load(
+            countries: &[Country],
+            _: &(),
+            ctx: &Self::Context,
+        ) -> Result<Vec<Self>, Self::Error> {
+            let country_ids = countries
+                .iter()
+                .map(|country| country.id)
+                .collect::<Vec<_>>();
+            let models = ctx
+                .db
+                .cities
+                .all_values()
+                .into_iter()
+                .filter(|city| country_ids.contains(&city.country_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            Ok(models)
+        }
+    }
+}
+
+pub struct Db {
+    users: Table<i32, models::User>,
+    countries: Table<i32, models::Country>,
+    cities: Table<i32, models::City>,
+}
+
+pub struct Context {
+    db: Db,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl Context {
+    /// Count the number of loads currently running, and wait a bit for other loads to start.
+    fn track_in_flight(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        let started_at = Instant::now();
+        while started_at.elapsed() < Duration::from_millis(500) {
+            let in_flight = self.in_flight.load(Ordering::SeqCst);
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            if in_flight > 1 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        InFlightGuard(&self.in_flight)
+    }
+}
+
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let mut user_models = ctx
+            .db
+            .users
+            .all_values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        user_models.sort_by_key(|user| user.id);
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Error, concurrent)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_one(default)]
+    city: HasOne<City>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_city(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, City, Walked>,
+    ) -> FieldResult<&City> {
+        Ok(self.city.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Error, concurrent)]
+pub struct Country {
+    country: models::Country,
+    #[has_many(root_model_field = city)]
+    cities: HasMany<City>,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_cities(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, City, Walked>,
+    ) -> FieldResult<&Vec<City>> {
+        Ok(self.cities.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Error, concurrent)]
+pub struct City {
+    city: models::City,
+}
+
+impl CityFields for City {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.city.id)
+    }
+}
+
+fn setup_db() -> Db {
+    let mut users = Table::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
+
+    countries.insert(1, models::Country { id: 1 });
+    countries.insert(2, models::Country { id: 2 });
+    cities.insert(
+        3,
+        models::City {
+            id: 3,
+            country_id: 1,
+        },
+    );
+    cities.insert(
+        4,
+        models::City {
+            id: 4,
+            country_id: 2,
+        },
+    );
+    users.insert(
+        5,
+        models::User {
+            id: 5,
+            country_id: 1,
+            city_id: 3,
+        },
+    );
+    users.insert(
+        6,
+        models::User {
+            id: 6,
+            country_id: 2,
+            city_id: 3,
+        },
+    );
+
+    Db {
+        users,
+        countries,
+        cities,
+    }
+}
+
+#[test]
+fn loading_associations_concurrently() {
+    let (json, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country {
+                    id
+                    cities { id }
+                }
+                city { id }
+            }
+        }
+    "#,
+        setup_db(),
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 5,
+                    "country": {
+                        "id": 1,
+                        "cities": [{ "id": 3 }],
+                    },
+                    "city": { "id": 3 },
+                },
+                {
+                    "id": 6,
+                    "country": {
+                        "id": 2,
+                        "cities": [{ "id": 4 }],
+                    },
+                    "city": { "id": 3 },
+                },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, counts.user_reads);
+    assert_eq!(1, counts.country_reads);
+    assert_eq!(2, counts.city_reads);
+}
+
+#[test]
+fn sibling_associations_are_loaded_concurrently() {
+    let (_, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                country { id }
+                city { id }
+            }
+        }
+    "#,
+        setup_db(),
+    );
+
+    assert_eq!(1, counts.country_reads);
+    assert_eq!(1, counts.city_reads);
+    assert_eq!(2, counts.max_in_flight);
+}
+
+#[test]
+fn associations_not_in_the_query_are_not_loaded() {
+    let (json, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                city { id }
+            }
+        }
+    "#,
+        setup_db(),
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "city": { "id": 3 } },
+                { "city": { "id": 3 } },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(0, counts.country_reads);
+    assert_eq!(1, counts.city_reads);
+    assert_eq!(1, counts.max_in_flight);
+}
+
+struct DbStats {
+    user_reads: usize,
+    country_reads: usize,
+    city_reads: usize,
+    max_in_flight: usize,
+}
+
+fn run_query(query: &str, db: Db) -> (Value, DbStats) {
+    // Make sure there are enough threads to load siblings at the same time, even on machines with
+    // a single core. This fails if the global pool has already been built by another test.
+    let _ = rayon::ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build_global();
+
+    let ctx = Context {
+        db,
+        in_flight: AtomicUsize::new(0),
+        max_in_flight: AtomicUsize::new(0),
+    };
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json: Value = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+
+    (
+        json,
+        DbStats {
+            user_reads: ctx.db.users.reads_count(),
+            country_reads: ctx.db.countries.reads_count(),
+            city_reads: ctx.db.cities.reads_count(),
+            max_in_flight: ctx.max_in_flight.load(Ordering::SeqCst),
+        },
+    )
+}
@@ -0,0 +1,572 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, Edge, HasConnection, LoadFrom,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        cars(first: Int = 100, after: String = ""): CarConnection! @juniper(ownership: "owned")
+        companies(first: Int = 100, after: String = ""): CompanyConnection! @juniper(ownership: "owned")
+    }
+
+    type CarConnection {
+        edges: [CarEdge!]! @juniper(ownership: "owned")
+        pageInfo: PageInfo! @juniper(ownership: "owned")
+    }
+
+    type CarEdge {
+        node: Car!
+        cursor: String!
+    }
+
+    type CompanyConnection {
+        edges: [CompanyEdge!]! @juniper(ownership: "owned")
+        pageInfo: PageInfo! @juniper(ownership: "owned")
+    }
+
+    type CompanyEdge {
+        node: Company!
+        cursor: String!
+    }
+
+    type PageInfo {
+        hasNextPage: Boolean!
+        hasPreviousPage: Boolean!
+        startCursor: String
+        endCursor: String
+    }
+
+    type Car {
+        id: Int!
+    }
+
+    type Company {
+        id: Int!
+    }
+}
+
+mod db_schema {
+    table! {
+        users (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        cars (id) {
+            id -> Integer,
+            user_id -> Integer,
+        }
+    }
+
+    table! {
+        companies (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        employments (id) {
+            id -> Integer,
+            user_id -> Integer,
+            company_id -> Integer,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable, PartialEq)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Company {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Employment {
+        pub id: i32,
+        pub user_id: i32,
+        pub company_id: i32,
+    }
+}
+
+use db_schema::{cars, companies, employments, users};
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        i32 -> (companies, models::Company),
+        models::User.id -> (cars.user_id, models::Car, paginate = UserCarsArgs<'_>),
+        models::User.id -> (employments.user_id, models::Employment, paginate = UserCompaniesArgs<'_>),
+        models::Employment.company_id -> (companies.id, models::Company),
+    }
+}
+
+impl LoadFrom<models::Employment, UserCompaniesArgs<'_>> for models::Company {
+    type Error = diesel::result::Error;
+    type Context = Context;
+
+    fn load(
+        employments: &[models::Employment],
+        _: &UserCompaniesArgs<'_>,
+        ctx: &Self::Context,
+    ) -> Result<Vec<Self>, Self::Error> {
+        LoadFrom::load(employments, &(), ctx)
+    }
+}
+
+pub struct Context {
+    db: SqliteConnection,
+}
+
+impl Context {
+    fn db(&self) -> &SqliteConnection {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order(users::id)
+            .load::<models::User>(ctx.db())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct User {
+    user: models::User,
+    #[has_connection(root_model_field = car, field_arguments = UserCarsArgs)]
+    cars: HasConnection<Car>,
+    #[has_connection(join_model = models::Employment, field_arguments = UserCompaniesArgs)]
+    companies: HasConnection<Company>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, CarConnection, Walked>,
+        _: i32,
+        _: String,
+    ) -> FieldResult<CarConnection> {
+        Ok(CarConnection(self.cars.try_unwrap()?.clone()))
+    }
+
+    fn field_companies(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, CompanyConnection, Walked>,
+        _: i32,
+        _: String,
+    ) -> FieldResult<CompanyConnection> {
+        Ok(CompanyConnection(self.companies.try_unwrap()?.clone()))
+    }
+}
+
+pub struct CarConnection(juniper_eager_loading::Connection<Car>);
+
+impl CarConnectionFields for CarConnection {
+    fn field_edges(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, CarEdge, Walked>,
+    ) -> FieldResult<Vec<CarEdge>> {
+        Ok(self.0.edges().iter().cloned().map(CarEdge).collect())
+    }
+
+    fn field_page_info(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, PageInfo, Walked>,
+    ) -> FieldResult<PageInfo> {
+        Ok(PageInfo(self.0.page_info().clone()))
+    }
+}
+
+pub struct CarEdge(Edge<Car>);
+
+impl CarEdgeFields for CarEdge {
+    fn field_node(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Car> {
+        Ok(self.0.node())
+    }
+
+    fn field_cursor(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(self.0.cursor())
+    }
+}
+
+pub struct CompanyConnection(juniper_eager_loading::Connection<Company>);
+
+impl CompanyConnectionFields for CompanyConnection {
+    fn field_edges(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, CompanyEdge, Walked>,
+    ) -> FieldResult<Vec<CompanyEdge>> {
+        Ok(self.0.edges().iter().cloned().map(CompanyEdge).collect())
+    }
+
+    fn field_page_info(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, PageInfo, Walked>,
+    ) -> FieldResult<PageInfo> {
+        Ok(PageInfo(self.0.page_info().clone()))
+    }
+}
+
+pub struct CompanyEdge(Edge<Company>);
+
+impl CompanyEdgeFields for CompanyEdge {
+    fn field_node(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Company, Walked>,
+    ) -> FieldResult<&Company> {
+        Ok(self.0.node())
+    }
+
+    fn field_cursor(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(self.0.cursor())
+    }
+}
+
+pub struct PageInfo(juniper_eager_loading::PageInfo);
+
+impl PageInfoFields for PageInfo {
+    fn field_has_next_page(&self, _: &Executor<'_, Context>) -> FieldResult<&bool> {
+        Ok(self.0.has_next_page())
+    }
+
+    fn field_has_previous_page(&self, _: &Executor<'_, Context>) -> FieldResult<&bool> {
+        Ok(self.0.has_previous_page())
+    }
+
+    fn field_start_cursor(&self, _: &Executor<'_, Context>) -> FieldResult<&Option<String>> {
+        Ok(self.0.start_cursor())
+    }
+
+    fn field_end_cursor(&self, _: &Executor<'_, Context>) -> FieldResult<&Option<String>> {
+        Ok(self.0.end_cursor())
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Company {
+    company: models::Company,
+}
+
+impl CompanyFields for Company {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.company.id)
+    }
+}
+
+/// Two users with four cars and three employments each.
+fn setup_db() -> Context {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (id INTEGER PRIMARY KEY)",
+        "CREATE TABLE cars (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL)",
+        "CREATE TABLE companies (id INTEGER PRIMARY KEY)",
+        "CREATE TABLE employments (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL, company_id INTEGER NOT NULL)",
+        "INSERT INTO users (id) VALUES (1), (2)",
+        "INSERT INTO cars (id, user_id) VALUES (1, 1), (2, 2), (3, 1), (4, 2), (5, 1), (6, 2), (7, 1), (8, 2)",
+        "INSERT INTO companies (id) VALUES (1), (2), (3)",
+        "INSERT INTO employments (id, user_id, company_id) VALUES (1, 1, 3), (2, 2, 1), (3, 1, 2), (4, 2, 2), (5, 1, 1), (6, 2, 3)",
+    ] {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+
+    Context { db }
+}
+
+#[test]
+fn loading_the_first_page_of_each_parent() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                cars(first: 2) {
+                    edges {
+                        cursor
+                        node { id }
+                    }
+                    pageInfo { hasNextPage hasPreviousPage startCursor endCursor }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 1,
+                    "cars": {
+                        "edges": [
+                            { "cursor": "0", "node": { "id": 1 } },
+                            { "cursor": "1", "node": { "id": 3 } },
+                        ],
+                        "pageInfo": {
+                            "hasNextPage": true,
+                            "hasPreviousPage": false,
+                            "startCursor": "0",
+                            "endCursor": "1",
+                        },
+                    },
+                },
+                {
+                    "id": 2,
+                    "cars": {
+                        "edges": [
+                            { "cursor": "0", "node": { "id": 2 } },
+                            { "cursor": "1", "node": { "id": 4 } },
+                        ],
+                        "pageInfo": {
+                            "hasNextPage": true,
+                            "hasPreviousPage": false,
+                            "startCursor": "0",
+                            "endCursor": "1",
+                        },
+                    },
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_the_last_page_of_each_parent() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                cars(first: 2, after: "1") {
+                    edges {
+                        cursor
+                        node { id }
+                    }
+                    pageInfo { hasNextPage hasPreviousPage startCursor endCursor }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "cars": {
+                        "edges": [
+                            { "cursor": "2", "node": { "id": 5 } },
+                            { "cursor": "3", "node": { "id": 7 } },
+                        ],
+                        "pageInfo": {
+                            "hasNextPage": false,
+                            "hasPreviousPage": true,
+                            "startCursor": "2",
+                            "endCursor": "3",
+                        },
+                    },
+                },
+                {
+                    "cars": {
+                        "edges": [
+                            { "cursor": "2", "node": { "id": 6 } },
+                            { "cursor": "3", "node": { "id": 8 } },
+                        ],
+                        "pageInfo": {
+                            "hasNextPage": false,
+                            "hasPreviousPage": true,
+                            "startCursor": "2",
+                            "endCursor": "3",
+                        },
+                    },
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_an_empty_page() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                cars(after: "3") {
+                    edges { cursor }
+                    pageInfo { hasNextPage hasPreviousPage startCursor endCursor }
+                }
+            }
+        }
+    "#,
+    );
+
+    let empty_page = json!({
+        "cars": {
+            "edges": [],
+            "pageInfo": {
+                "hasNextPage": false,
+                "hasPreviousPage": true,
+                "startCursor": null,
+                "endCursor": null,
+            },
+        },
+    });
+    assert_json_eq!(json!({ "users": [empty_page.clone(), empty_page] }), json);
+}
+
+#[test]
+fn loading_only_the_page_info() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                cars(first: 3) {
+                    pageInfo { hasNextPage endCursor }
+                }
+            }
+        }
+    "#,
+    );
+
+    let page = json!({
+        "cars": {
+            "pageInfo": { "hasNextPage": true, "endCursor": "2" },
+        },
+    });
+    assert_json_eq!(json!({ "users": [page.clone(), page] }), json);
+}
+
+#[test]
+fn loading_a_connection_through_a_join_model() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                companies(first: 1) {
+                    edges {
+                        cursor
+                        node { id }
+                    }
+                    pageInfo { hasNextPage }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "companies": {
+                        "edges": [{ "cursor": "0", "node": { "id": 3 } }],
+                        "pageInfo": { "hasNextPage": true },
+                    },
+                },
+                {
+                    "companies": {
+                        "edges": [{ "cursor": "0", "node": { "id": 1 } }],
+                        "pageInfo": { "hasNextPage": true },
+                    },
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
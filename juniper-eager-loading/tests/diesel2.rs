@@ -0,0 +1,274 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel2 as diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasCount, HasMany, HasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::cell::{RefCell, RefMut};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        cars(first: Int = 100, after: String = ""): [Car!]!
+        carCount: Int! @juniper(ownership: "owned")
+    }
+
+    type Country {
+        id: Int!
+    }
+
+    type Car {
+        id: Int!
+    }
+}
+
+mod db_schema {
+    table! {
+        users (id) {
+            id -> Integer,
+            country_id -> Integer,
+        }
+    }
+
+    table! {
+        countries (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        cars (id) {
+            id -> Integer,
+            user_id -> Integer,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Country {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+}
+
+use db_schema::{cars, countries, users};
+
+pub struct CarCount;
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+        diesel = 2,
+    ) => {
+        i32 -> (countries, models::Country),
+        models::User.id -> (cars.user_id, models::Car, paginate = UserCarsArgs<'_>),
+        models::User.id: i32 -> count(cars.user_id, CarCount),
+    }
+}
+
+// Diesel 2 needs `&mut` connections.
+pub struct Context {
+    db: RefCell<SqliteConnection>,
+}
+
+impl Context {
+    fn db(&self) -> RefMut<'_, SqliteConnection> {
+        self.db.borrow_mut()
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order(users::id)
+            .load::<models::User>(&mut *ctx.db())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_many(root_model_field = car, field_arguments = UserCarsArgs, paginate)]
+    cars: HasMany<Car>,
+    #[has_aggregate(aggregate = CarCount)]
+    car_count: HasCount,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+        _: i32,
+        _: String,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+
+    fn field_car_count(&self, _: &Executor<'_, Context>) -> FieldResult<i32> {
+        Ok(*self.car_count.try_unwrap()? as i32)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+}
+
+fn setup_db() -> Context {
+    let mut db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (id INTEGER NOT NULL PRIMARY KEY, country_id INTEGER NOT NULL)",
+        "CREATE TABLE countries (id INTEGER NOT NULL PRIMARY KEY)",
+        "CREATE TABLE cars (id INTEGER NOT NULL PRIMARY KEY, user_id INTEGER NOT NULL)",
+        "INSERT INTO users (id, country_id) VALUES (1, 10), (2, 20), (3, 10)",
+        "INSERT INTO countries (id) VALUES (10), (20)",
+        "INSERT INTO cars (id, user_id) VALUES (1, 1), (2, 2), (3, 1), (4, 1)",
+    ] {
+        diesel::sql_query(*sql).execute(&mut db).unwrap();
+    }
+
+    Context {
+        db: RefCell::new(db),
+    }
+}
+
+#[test]
+fn loading_with_diesel_2() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id }
+                cars(first: 2) { id }
+                carCount
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 1,
+                    "country": { "id": 10 },
+                    "cars": [{ "id": 1 }, { "id": 3 }],
+                    "carCount": 3,
+                },
+                {
+                    "id": 2,
+                    "country": { "id": 20 },
+                    "cars": [{ "id": 2 }],
+                    "carCount": 1,
+                },
+                {
+                    "id": 3,
+                    "country": { "id": 10 },
+                    "cars": [],
+                    "carCount": 0,
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
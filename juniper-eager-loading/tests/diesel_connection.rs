@@ -0,0 +1,282 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasMany, HasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        cars: [Car!]!
+    }
+
+    type Country {
+        id: Int!
+        name: String!
+    }
+
+    type Car {
+        id: Int!
+    }
+}
+
+mod db_schema {
+    table! {
+        users (id) {
+            id -> Integer,
+            country_id -> Integer,
+        }
+    }
+
+    table! {
+        countries (id) {
+            id -> Integer,
+            name -> Text,
+        }
+    }
+
+    table! {
+        cars (id) {
+            id -> Integer,
+            user_id -> Integer,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Country {
+        pub id: i32,
+        pub name: String,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+}
+
+use db_schema::{cars, countries, users};
+
+type Error = Box<dyn std::error::Error>;
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = Error,
+        context = Context,
+        connection = |ctx| ctx.primary.lock().map_err(|_| "primary poisoned")?,
+    ) => {
+        i32 -> (countries, models::Country),
+        models::User.id -> (
+            cars.user_id,
+            models::Car,
+            connection = |ctx| ctx.replica.lock().map_err(|_| "replica poisoned")?,
+        ),
+    }
+}
+
+// Mutex guards stand in for pooled connections, which also deref to the connection.
+pub struct Context {
+    primary: Mutex<SqliteConnection>,
+    replica: Mutex<SqliteConnection>,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order(users::id)
+            .load::<models::User>(&*ctx.primary.lock().unwrap())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Error)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_many(root_model_field = car)]
+    cars: HasMany<Car>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Error)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_name(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.country.name)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Error)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+}
+
+fn connection(sqls: &[&str]) -> SqliteConnection {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+    for sql in sqls {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+    db
+}
+
+fn setup_db() -> Context {
+    // Both databases have the same tables but different rows, so the results show which
+    // connection each query used.
+    let schema = [
+        "CREATE TABLE users (id INTEGER NOT NULL PRIMARY KEY, country_id INTEGER NOT NULL)",
+        "CREATE TABLE countries (id INTEGER NOT NULL PRIMARY KEY, name TEXT NOT NULL)",
+        "CREATE TABLE cars (id INTEGER NOT NULL PRIMARY KEY, user_id INTEGER NOT NULL)",
+    ];
+
+    let primary = connection(&[
+        schema[0],
+        schema[1],
+        schema[2],
+        "INSERT INTO users (id, country_id) VALUES (1, 10), (2, 20)",
+        "INSERT INTO countries (id, name) VALUES (10, 'Denmark'), (20, 'Sweden')",
+        "INSERT INTO cars (id, user_id) VALUES (1, 1)",
+    ]);
+
+    let replica = connection(&[
+        schema[0],
+        schema[1],
+        schema[2],
+        "INSERT INTO countries (id, name) VALUES (10, 'Replica Denmark'), (20, 'Replica Sweden')",
+        "INSERT INTO cars (id, user_id) VALUES (100, 1), (200, 2), (300, 2)",
+    ]);
+
+    Context {
+        primary: Mutex::new(primary),
+        replica: Mutex::new(replica),
+    }
+}
+
+#[test]
+fn loading_with_different_connections() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id name }
+                cars { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 1,
+                    "country": { "id": 10, "name": "Denmark" },
+                    "cars": [{ "id": 100 }],
+                },
+                {
+                    "id": 2,
+                    "country": { "id": 20, "name": "Sweden" },
+                    "cars": [{ "id": 200 }, { "id": 300 }],
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
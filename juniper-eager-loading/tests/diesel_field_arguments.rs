@@ -0,0 +1,323 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasMany, LoadFrom,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      countries: [Country!]! @juniper(ownership: "owned")
+    }
+
+    type Country {
+        id: Int!
+        users(admin: Boolean!): [User!]!
+    }
+
+    type User {
+        id: Int!
+        admin: Boolean!
+    }
+}
+
+mod db_schema {
+    table! {
+        countries (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        users (id) {
+            id -> Integer,
+            country_id -> Integer,
+            admin -> Bool,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Country {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+        pub admin: bool,
+    }
+}
+
+use db_schema::{countries, users};
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        models::Country.id -> (
+            users.country_id,
+            models::User,
+            args = CountryUsersArgs<'_>,
+            filter = |args| users::admin.eq(args.admin()),
+        ),
+        models::Country.id -> (
+            users.country_id,
+            models::User,
+            args = UserIdsArgs,
+            filter = |args| users::id.eq_any(&args.ids),
+            filter_params = 500,
+        ),
+    }
+}
+
+/// Arguments whose filter binds one parameter per id.
+pub struct UserIdsArgs {
+    ids: Vec<i32>,
+}
+
+pub struct Context {
+    db: SqliteConnection,
+}
+
+impl Context {
+    fn db(&self) -> &SqliteConnection {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_countries<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, Country, Walked>,
+    ) -> FieldResult<Vec<Country>> {
+        let ctx = executor.context();
+        let country_models = countries::table
+            .order(countries::id)
+            .load::<models::Country>(ctx.db())?;
+        let countries = Country::eager_load_each(&country_models, ctx, trail)?;
+        Ok(countries)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Country {
+    country: models::Country,
+    #[has_many(root_model_field = user, field_arguments = CountryUsersArgs)]
+    users: HasMany<User>,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_users(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+        _: bool,
+    ) -> FieldResult<&Vec<User>> {
+        Ok(self.users.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct User {
+    user: models::User,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_admin(&self, _: &Executor<'_, Context>) -> FieldResult<&bool> {
+        Ok(&self.user.admin)
+    }
+}
+
+fn setup_db() -> Context {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE countries (id INTEGER NOT NULL PRIMARY KEY)",
+        "CREATE TABLE users (id INTEGER NOT NULL PRIMARY KEY, country_id INTEGER NOT NULL, admin BOOLEAN NOT NULL)",
+        "INSERT INTO countries (id) VALUES (1), (2)",
+        "INSERT INTO users (id, country_id, admin) VALUES (1, 1, 1), (2, 1, 0), (3, 2, 1), (4, 2, 1)",
+    ] {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+
+    Context { db }
+}
+
+#[test]
+fn loading_with_field_arguments_from_the_macro() {
+    let json = run_query(
+        r#"
+        query Test {
+            countries {
+                id
+                users(admin: true) { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "countries": [
+                { "id": 1, "users": [{ "id": 1 }] },
+                { "id": 2, "users": [{ "id": 3 }, { "id": 4 }] },
+            ]
+        }),
+        json,
+    );
+
+    let json = run_query(
+        r#"
+        query Test {
+            countries {
+                id
+                users(admin: false) { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "countries": [
+                { "id": 1, "users": [{ "id": 2 }] },
+                { "id": 2, "users": [] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_more_ids_than_sqlite_can_bind_with_a_filter() {
+    let ctx = setup_db();
+    for id in 3..=1200 {
+        diesel::sql_query(format!("INSERT INTO countries (id) VALUES ({})", id))
+            .execute(ctx.db())
+            .unwrap();
+        diesel::sql_query(format!(
+            "INSERT INTO users (id, country_id, admin) VALUES ({}, {}, {})",
+            id + 2,
+            id,
+            id % 2,
+        ))
+        .execute(ctx.db())
+        .unwrap();
+    }
+
+    // The ids are loaded in chunks that leave room for the parameters bound by the filter.
+    assert_eq!(
+        Some(899),
+        <models::User as LoadFrom<models::Country, CountryUsersArgs<'_>>>::chunk_size()
+    );
+
+    let json = run_query_with_context(
+        r#"
+        query Test {
+            countries {
+                users(admin: true) { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    let countries = json["countries"].as_array().unwrap();
+    assert_eq!(1200, countries.len());
+    let admins = countries
+        .iter()
+        .map(|country| country["users"].as_array().unwrap().len())
+        .sum::<usize>();
+    // Two in the first two countries and one in every other country from 3 to 1200.
+    assert_eq!(3 + 599, admins);
+}
+
+#[test]
+fn loading_with_a_filter_that_binds_more_than_a_hundred_parameters() {
+    let ctx = setup_db();
+    for id in 3..=1200 {
+        diesel::sql_query(format!("INSERT INTO countries (id) VALUES ({})", id))
+            .execute(ctx.db())
+            .unwrap();
+        diesel::sql_query(format!(
+            "INSERT INTO users (id, country_id, admin) VALUES ({}, {}, 0)",
+            id + 2,
+            id,
+        ))
+        .execute(ctx.db())
+        .unwrap();
+    }
+
+    // Room is left for the 500 parameters the filter says it can bind.
+    assert_eq!(
+        Some(499),
+        <models::User as LoadFrom<models::Country, UserIdsArgs>>::chunk_size()
+    );
+
+    let countries = countries::table
+        .order(countries::id)
+        .load::<models::Country>(ctx.db())
+        .unwrap();
+    let args = UserIdsArgs {
+        ids: (1..=500).collect(),
+    };
+    let users: Vec<models::User> =
+        juniper_eager_loading::load_from_in_chunks(&countries, &args, &ctx, None).unwrap();
+
+    assert_eq!(500, users.len());
+}
+
+fn run_query(query: &str) -> Value {
+    run_query_with_context(query, &setup_db())
+}
+
+fn run_query_with_context(query: &str, ctx: &Context) -> Value {
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
@@ -0,0 +1,202 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasOne};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+    }
+
+    type Country {
+        id: Int!
+        isoCode: String!
+    }
+}
+
+mod db_schema {
+    table! {
+        users (id) {
+            id -> Integer,
+            country_code -> Text,
+        }
+    }
+
+    table! {
+        countries (id) {
+            id -> Integer,
+            iso_code -> Text,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+        pub country_code: String,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Country {
+        pub id: i32,
+        pub iso_code: String,
+    }
+}
+
+use db_schema::{countries, users};
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        String -> (countries.iso_code, models::Country),
+    }
+}
+
+pub struct Context {
+    db: SqliteConnection,
+}
+
+impl Context {
+    fn db(&self) -> &SqliteConnection {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order(users::id)
+            .load::<models::User>(ctx.db())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct User {
+    user: models::User,
+    #[has_one(foreign_key_field = country_code, child_primary_key_field = iso_code)]
+    country: HasOne<Country>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_iso_code(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.country.iso_code)
+    }
+}
+
+fn setup_db() -> Context {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (id INTEGER NOT NULL PRIMARY KEY, country_code TEXT NOT NULL)",
+        "CREATE TABLE countries (id INTEGER NOT NULL PRIMARY KEY, iso_code TEXT NOT NULL UNIQUE)",
+        "INSERT INTO users (id, country_code) VALUES (1, 'DK'), (2, 'SE'), (3, 'DK')",
+        "INSERT INTO countries (id, iso_code) VALUES (10, 'SE'), (20, 'DK'), (30, 'NO')",
+    ] {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+
+    Context { db }
+}
+
+#[test]
+fn loading_has_one_by_a_lookup_column() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id isoCode }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "country": { "id": 20, "isoCode": "DK" } },
+                { "id": 2, "country": { "id": 10, "isoCode": "SE" } },
+                { "id": 3, "country": { "id": 20, "isoCode": "DK" } },
+            ]
+        }),
+        json,
+    );
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
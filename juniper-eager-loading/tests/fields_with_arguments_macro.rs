@@ -4,11 +4,11 @@
 mod helpers;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
-use helpers::{SortedExtension, StatsHash};
+use helpers::SortedExtension;
 use juniper::{Executor, FieldError, FieldResult};
 use juniper_eager_loading::{
-    prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne, LoadChildrenOutput, LoadFrom,
-    OptionHasOne,
+    in_memory::Table, prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne,
+    LoadChildrenOutput, LoadFrom, OptionHasOne,
 };
 use juniper_from_schema::graphql_schema;
 use serde_json::{json, Value};
@@ -180,8 +180,8 @@ mod models {
 }
 
 pub struct Db {
-    users: StatsHash<i32, models::User>,
-    countries: StatsHash<i32, models::Country>,
+    users: Table<i32, models::User>,
+    countries: Table<i32, models::Country>,
 }
 
 pub struct Context {
@@ -287,8 +287,8 @@ impl CountryFields for Country {
 
 #[test]
 fn loading_user() {
-    let mut countries = StatsHash::new("countries");
-    let mut users = StatsHash::new("users");
+    let mut countries = Table::new("countries");
+    let mut users = Table::new("users");
 
     let mut country = models::Country { id: 10 };
     let country_id = country.id;
@@ -0,0 +1,220 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasManyFromIds};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      posts: [Post!]! @juniper(ownership: "owned")
+    }
+
+    type Post {
+        id: Int!
+        tags: [Tag!]!
+    }
+
+    type Tag {
+        id: Int!
+        name: String!
+    }
+}
+
+mod models {
+    use juniper_eager_loading::LoadFrom;
+    use std::collections::HashSet;
+
+    #[derive(Clone, Debug)]
+    pub struct Post {
+        pub id: i32,
+        pub tag_ids: Vec<i32>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Tag {
+        pub id: i32,
+        pub name: String,
+    }
+
+    impl LoadFrom<i32> for Tag {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let unique_ids = ids.iter().collect::<HashSet<_>>();
+            assert_eq!(ids.len(), unique_ids.len());
+
+            Ok(ctx
+                .db
+                .tags
+                .all_values()
+                .into_iter()
+                .filter(|tag| ids.contains(&tag.id))
+                .cloned()
+                .collect())
+        }
+    }
+}
+
+pub struct Db {
+    posts: Vec<models::Post>,
+    tags: Table<i32, models::Tag>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_posts<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, Post, Walked>,
+    ) -> FieldResult<Vec<Post>> {
+        let ctx = executor.context();
+        let posts = Post::eager_load_each(&ctx.db.posts, ctx, trail)?;
+        Ok(posts)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Post {
+    post: models::Post,
+    #[has_many_from_ids(root_model_field = tag)]
+    tags: HasManyFromIds<Tag>,
+}
+
+impl PostFields for Post {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.post.id)
+    }
+
+    fn field_tags(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Tag, Walked>,
+    ) -> FieldResult<&Vec<Tag>> {
+        Ok(self.tags.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Tag {
+    tag: models::Tag,
+}
+
+impl TagFields for Tag {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.tag.id)
+    }
+
+    fn field_name(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.tag.name)
+    }
+}
+
+fn setup_db() -> Db {
+    let mut tags = Table::new("tags");
+    for (id, name) in &[(1, "rust"), (2, "graphql"), (3, "sql")] {
+        tags.insert(
+            *id,
+            models::Tag {
+                id: *id,
+                name: name.to_string(),
+            },
+        );
+    }
+
+    let posts = vec![
+        models::Post {
+            id: 1,
+            tag_ids: vec![3, 1, 3],
+        },
+        models::Post {
+            id: 2,
+            tag_ids: vec![],
+        },
+        // Tag 4 doesn't exist.
+        models::Post {
+            id: 3,
+            tag_ids: vec![4, 2, 1],
+        },
+    ];
+
+    Db { posts, tags }
+}
+
+#[test]
+fn loading_children_from_a_list_of_ids() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            posts {
+                id
+                tags { id name }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "posts": [
+                {
+                    "id": 1,
+                    "tags": [
+                        { "id": 3, "name": "sql" },
+                        { "id": 1, "name": "rust" },
+                        { "id": 3, "name": "sql" },
+                    ]
+                },
+                { "id": 2, "tags": [] },
+                {
+                    "id": 3,
+                    "tags": [
+                        { "id": 2, "name": "graphql" },
+                        { "id": 1, "name": "rust" },
+                    ]
+                },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.tags.reads_count());
+}
+
+fn run_query(query: &str) -> (Value, Context) {
+    let ctx = Context { db: setup_db() };
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    (json, ctx)
+}
@@ -0,0 +1,413 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasOne, OptionHasOne};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        profile: Profile!
+        avatar: Avatar
+        team: Team!
+    }
+
+    type Profile {
+        id: Int!
+        bio: String!
+    }
+
+    type Avatar {
+        id: Int!
+    }
+
+    type Team {
+        id: Int!
+    }
+}
+
+mod models {
+    use juniper_eager_loading::LoadFrom;
+
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub team_code: String,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Profile {
+        pub id: i32,
+        pub user_id: i32,
+        pub bio: String,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Avatar {
+        pub id: i32,
+        pub owner_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Team {
+        pub id: i32,
+        pub code: String,
+    }
+
+    impl LoadFrom<User> for Profile {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(users: &[User], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let user_ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+
+            Ok(ctx
+                .db
+                .profiles
+                .all_values()
+                .into_iter()
+                .filter(|profile| user_ids.contains(&profile.user_id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl LoadFrom<User> for Avatar {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(users: &[User], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let user_ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+
+            Ok(ctx
+                .db
+                .avatars
+                .all_values()
+                .into_iter()
+                .filter(|avatar| user_ids.contains(&avatar.owner_id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl LoadFrom<String> for Team {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(codes: &[String], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .teams
+                .all_values()
+                .into_iter()
+                .filter(|team| codes.contains(&team.code))
+                .cloned()
+                .collect())
+        }
+    }
+}
+
+pub struct Db {
+    users: Vec<models::User>,
+    profiles: Table<i32, models::Profile>,
+    avatars: Table<i32, models::Avatar>,
+    teams: Table<i32, models::Team>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let users = User::eager_load_each(&ctx.db.users, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_one(inverse)]
+    profile: HasOne<Profile>,
+    #[option_has_one(inverse, foreign_key_field = owner_id)]
+    avatar: OptionHasOne<Avatar>,
+    #[has_one(foreign_key_field = team_code, child_primary_key_field = code)]
+    team: HasOne<Team>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_profile(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Profile, Walked>,
+    ) -> FieldResult<&Profile> {
+        Ok(self.profile.try_unwrap()?)
+    }
+
+    fn field_avatar(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Avatar, Walked>,
+    ) -> FieldResult<&Option<Avatar>> {
+        Ok(self.avatar.try_unwrap()?)
+    }
+
+    fn field_team(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Team, Walked>,
+    ) -> FieldResult<&Team> {
+        Ok(self.team.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Profile {
+    profile: models::Profile,
+}
+
+impl ProfileFields for Profile {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.profile.id)
+    }
+
+    fn field_bio(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.profile.bio)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Avatar {
+    avatar: models::Avatar,
+}
+
+impl AvatarFields for Avatar {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.avatar.id)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Team {
+    team: models::Team,
+}
+
+impl TeamFields for Team {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.team.id)
+    }
+}
+
+fn profile(id: i32, user_id: i32, bio: &str) -> models::Profile {
+    models::Profile {
+        id,
+        user_id,
+        bio: bio.to_string(),
+    }
+}
+
+fn setup_db() -> Db {
+    let mut profiles = Table::new("profiles");
+    profiles.insert(10, profile(10, 2, "Bob"));
+    profiles.insert(20, profile(20, 1, "Alice"));
+
+    let mut avatars = Table::new("avatars");
+    avatars.insert(1, models::Avatar { id: 1, owner_id: 2 });
+
+    let mut teams = Table::new("teams");
+    teams.insert(
+        1,
+        models::Team {
+            id: 1,
+            code: "red".to_string(),
+        },
+    );
+
+    Db {
+        users: vec![
+            models::User {
+                id: 1,
+                team_code: "red".to_string(),
+            },
+            models::User {
+                id: 2,
+                team_code: "red".to_string(),
+            },
+        ],
+        profiles,
+        avatars,
+        teams,
+    }
+}
+
+#[test]
+fn loading_has_one_with_foreign_key_on_child() {
+    let ctx = Context { db: setup_db() };
+    let (json, errors) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                profile { id bio }
+                avatar { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert!(errors.is_empty());
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "profile": { "id": 20, "bio": "Alice" }, "avatar": null },
+                { "id": 2, "profile": { "id": 10, "bio": "Bob" }, "avatar": { "id": 1 } },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.profiles.reads_count());
+    assert_eq!(1, ctx.db.avatars.reads_count());
+}
+
+#[test]
+fn has_one_with_more_than_one_child_errors() {
+    let mut db = setup_db();
+    db.profiles.insert(30, profile(30, 1, "Alice again"));
+    let ctx = Context { db };
+
+    let (json, errors) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                profile { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "Found more than one child for `HasOne`",
+        errors[0].error().message(),
+    );
+}
+
+#[test]
+fn has_one_by_a_column_that_isnt_unique_errors() {
+    let mut db = setup_db();
+    db.teams.insert(
+        2,
+        models::Team {
+            id: 2,
+            code: "red".to_string(),
+        },
+    );
+    let ctx = Context { db };
+
+    let (json, errors) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                team { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    // The users share a team code that is now used by two teams. `HasOne` used to keep the last
+    // of them, now it fails instead of picking one.
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "Found more than one child for `HasOne`",
+        errors[0].error().message(),
+    );
+}
+
+#[test]
+fn option_has_one_with_more_than_one_child_errors() {
+    let mut db = setup_db();
+    db.avatars.insert(2, models::Avatar { id: 2, owner_id: 2 });
+    let ctx = Context { db };
+
+    let (json, errors) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                avatar { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    // The avatar is nullable so only the field of the user with two avatars fails.
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "avatar": null },
+                { "id": 2, "avatar": null },
+            ]
+        }),
+        json,
+    );
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "Found more than one child for `OptionHasOne`",
+        errors[0].error().message(),
+    );
+}
+
+fn run_query(
+    query: &str,
+    ctx: &Context,
+) -> (
+    Value,
+    Vec<juniper::ExecutionError<juniper::DefaultScalarValue>>,
+) {
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        ctx,
+    )
+    .unwrap();
+
+    let json = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    (json, errors)
+}
@@ -0,0 +1,337 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_in_memory,
+    in_memory::{List, Table},
+    prelude::*,
+    EagerLoading, HasMany, HasOne, LoadFrom,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        cars: [Car!]!
+    }
+
+    type Country {
+        id: Int!
+        isoCode: String!
+    }
+
+    type Car {
+        id: Int!
+        owner: User!
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_code: String,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Country {
+        pub id: i32,
+        pub iso_code: String,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+}
+
+impl_load_from_for_in_memory! {
+    (
+        error = Box<dyn std::error::Error>,
+        context = Context,
+    ) => {
+        i32 -> (users, models::User),
+        String -> (countries.iso_code, models::Country),
+        models::User.id -> (cars.user_id, models::Car),
+    }
+}
+
+pub struct Db {
+    users: Table<i32, models::User>,
+    countries: Table<i32, models::Country>,
+    cars: List<models::Car>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl Context {
+    fn db(&self) -> &Db {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let mut user_models = ctx
+            .db()
+            .users
+            .all_values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        user_models.sort_by_key(|user| user.id);
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_one(foreign_key_field = country_code, child_primary_key_field = iso_code)]
+    country: HasOne<Country>,
+    #[has_many(root_model_field = car)]
+    cars: HasMany<Car>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_iso_code(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.country.iso_code)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Car {
+    car: models::Car,
+    #[has_one(foreign_key_field = user_id, root_model_field = user)]
+    owner: HasOne<Box<User>>,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+
+    fn field_owner(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<&User> {
+        Ok(self.owner.try_unwrap()?)
+    }
+}
+
+fn setup_db() -> Db {
+    let mut users = Table::new("users");
+    users.insert(
+        1,
+        models::User {
+            id: 1,
+            country_code: "DK".to_string(),
+        },
+    );
+    users.insert(
+        2,
+        models::User {
+            id: 2,
+            country_code: "SE".to_string(),
+        },
+    );
+    users.insert(
+        3,
+        models::User {
+            id: 3,
+            country_code: "DK".to_string(),
+        },
+    );
+
+    let mut countries = Table::new("countries");
+    countries.insert(
+        10,
+        models::Country {
+            id: 10,
+            iso_code: "SE".to_string(),
+        },
+    );
+    countries.insert(
+        20,
+        models::Country {
+            id: 20,
+            iso_code: "DK".to_string(),
+        },
+    );
+
+    let mut cars = List::new("cars");
+    cars.push(models::Car { id: 1, user_id: 1 });
+    cars.push(models::Car { id: 2, user_id: 3 });
+    cars.push(models::Car { id: 3, user_id: 1 });
+
+    Db {
+        users,
+        countries,
+        cars,
+    }
+}
+
+#[test]
+fn loading_from_in_memory_tables() {
+    let ctx = Context { db: setup_db() };
+
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id isoCode }
+                cars {
+                    id
+                    owner { id }
+                }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 1,
+                    "country": { "id": 20, "isoCode": "DK" },
+                    "cars": [
+                        { "id": 1, "owner": { "id": 1 } },
+                        { "id": 3, "owner": { "id": 1 } },
+                    ],
+                },
+                {
+                    "id": 2,
+                    "country": { "id": 10, "isoCode": "SE" },
+                    "cars": [],
+                },
+                {
+                    "id": 3,
+                    "country": { "id": 20, "isoCode": "DK" },
+                    "cars": [{ "id": 2, "owner": { "id": 3 } }],
+                },
+            ]
+        }),
+        json,
+    );
+
+    // One read for the root users and one for the owners of the cars.
+    assert_eq!(2, ctx.db().users.reads_count());
+    assert_eq!(1, ctx.db().countries.reads_count());
+    assert_eq!(1, ctx.db().cars.reads_count());
+}
+
+#[test]
+fn tables_count_every_read() {
+    let db = setup_db();
+
+    assert_eq!("users", db.users.name());
+    assert!(db.users.get(&1).is_some());
+    assert!(db.users.get(&4).is_none());
+    assert_eq!(3, db.users.all_values().len());
+    assert_eq!(2, db.users.get_many(&[1, 2, 4]).len());
+    assert_eq!(4, db.users.reads_count());
+
+    assert_eq!(3, db.cars.all_values().len());
+    assert_eq!(1, db.cars.reads_count());
+}
+
+#[test]
+fn loading_by_key_reads_the_table_once() {
+    let ctx = Context { db: setup_db() };
+
+    // Duplicate ids only return the user once and missing ids are skipped.
+    let mut users: Vec<models::User> = LoadFrom::load(&[3, 1, 3, 4], &(), &ctx).unwrap();
+    users.sort_by_key(|user| user.id);
+    assert_eq!(
+        vec![1, 3],
+        users.iter().map(|user| user.id).collect::<Vec<_>>()
+    );
+    assert_eq!(1, ctx.db().users.reads_count());
+
+    let cars: Vec<models::Car> =
+        LoadFrom::load(&[users[0].clone(), users[0].clone()], &(), &ctx).unwrap();
+    assert_eq!(2, cars.len());
+    assert_eq!(1, ctx.db().cars.reads_count());
+}
+
+fn run_query(query: &str, ctx: &Context) -> Value {
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
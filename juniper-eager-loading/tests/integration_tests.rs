@@ -3,10 +3,10 @@
 mod helpers;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
-use helpers::{SortedExtension, StatsHash};
+use helpers::SortedExtension;
 use juniper::{Executor, FieldError, FieldResult};
 use juniper_eager_loading::{
-    prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne, OptionHasOne,
+    in_memory::Table, prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne, OptionHasOne,
 };
 use juniper_from_schema::graphql_schema;
 use models::{CityId, CompanyId, CountryId, EmploymentId, IssueId, UserId};
@@ -92,7 +92,44 @@ mod models {
         }
     }
 
-    make_model_ids!(UserId, CountryId, CityId, CompanyId, EmploymentId, IssueId);
+    make_model_ids!(UserId, CityId, CompanyId, EmploymentId, IssueId);
+
+    thread_local! {
+        /// The number of times two `CountryId`s have been compared on the current thread.
+        pub static COUNTRY_ID_COMPARISONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// Like the other ids but counts comparisons, so tests can check that children are matched
+    /// with their parents without comparing every combination.
+    #[derive(Debug, Eq, Ord, PartialOrd, Clone, Copy)]
+    pub struct CountryId(i32);
+
+    impl PartialEq for CountryId {
+        fn eq(&self, other: &CountryId) -> bool {
+            COUNTRY_ID_COMPARISONS.with(|count| count.set(count.get() + 1));
+            self.0 == other.0
+        }
+    }
+
+    impl std::hash::Hash for CountryId {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.0.hash(state);
+        }
+    }
+
+    impl From<i32> for CountryId {
+        fn from(id: i32) -> CountryId {
+            CountryId(id)
+        }
+    }
+
+    impl std::ops::Deref for CountryId {
+        type Target = i32;
+
+        fn deref(&self) -> &i32 {
+            &self.0
+        }
+    }
 
     #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug)]
     pub struct User {
@@ -335,12 +372,12 @@ mod models {
 }
 
 pub struct Db {
-    users: StatsHash<UserId, models::User>,
-    countries: StatsHash<CountryId, models::Country>,
-    cities: StatsHash<CityId, models::City>,
-    companies: StatsHash<CompanyId, models::Company>,
-    employments: StatsHash<EmploymentId, models::Employment>,
-    issues: StatsHash<IssueId, models::Issue>,
+    users: Table<UserId, models::User>,
+    countries: Table<CountryId, models::Country>,
+    cities: Table<CityId, models::City>,
+    companies: Table<CompanyId, models::Company>,
+    employments: Table<EmploymentId, models::Employment>,
+    issues: Table<IssueId, models::Issue>,
 }
 
 pub struct Context {
@@ -673,9 +710,9 @@ impl IssueFields for Issue {
 
 #[test]
 fn loading_user() {
-    let mut countries = StatsHash::new("countries");
-    let cities = StatsHash::new("cities");
-    let mut users = StatsHash::new("users");
+    let mut countries = Table::new("countries");
+    let cities = Table::new("cities");
+    let mut users = Table::new("users");
 
     let mut country = models::Country {
         id: CountryId::from(10),
@@ -710,9 +747,9 @@ fn loading_user() {
         users,
         countries,
         cities,
-        employments: StatsHash::new("employments"),
-        companies: StatsHash::new("companies"),
-        issues: StatsHash::new("issues"),
+        employments: Table::new("employments"),
+        companies: Table::new("companies"),
+        issues: Table::new("issues"),
     };
     let (json, counts) = run_query("query Test { user(id: 1) { id } }", db);
 
@@ -730,9 +767,9 @@ fn loading_user() {
 
 #[test]
 fn loading_users() {
-    let mut countries = StatsHash::new("countries");
-    let cities = StatsHash::new("cities");
-    let mut users = StatsHash::new("users");
+    let mut countries = Table::new("countries");
+    let cities = Table::new("cities");
+    let mut users = Table::new("users");
 
     let mut country = models::Country {
         id: CountryId::from(10),
@@ -767,9 +804,9 @@ fn loading_users() {
         users,
         countries,
         cities,
-        employments: StatsHash::new("employments"),
-        companies: StatsHash::new("companies"),
-        issues: StatsHash::new("issues"),
+        employments: Table::new("employments"),
+        companies: Table::new("companies"),
+        issues: Table::new("issues"),
     };
     let (json, counts) = run_query("query Test { users { id } }", db);
 
@@ -790,9 +827,9 @@ fn loading_users() {
 
 #[test]
 fn loading_users_and_associations() {
-    let mut countries = StatsHash::new("countries");
-    let mut cities = StatsHash::new("cities");
-    let mut users = StatsHash::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
+    let mut users = Table::new("users");
 
     let country = models::Country {
         id: CountryId::from(10),
@@ -857,9 +894,9 @@ fn loading_users_and_associations() {
         users,
         countries,
         cities,
-        employments: StatsHash::new("employments"),
-        companies: StatsHash::new("companies"),
-        issues: StatsHash::new("issue"),
+        employments: Table::new("employments"),
+        companies: Table::new("companies"),
+        issues: Table::new("issue"),
     };
 
     let (json, counts) = run_query(
@@ -929,9 +966,9 @@ fn loading_users_and_associations() {
 
 #[test]
 fn test_caching() {
-    let mut users = StatsHash::new("users");
-    let mut countries = StatsHash::new("countries");
-    let mut cities = StatsHash::new("cities");
+    let mut users = Table::new("users");
+    let mut countries = Table::new("countries");
+    let mut cities = Table::new("cities");
 
     let mut country = models::Country {
         id: CountryId::from(1),
@@ -956,9 +993,9 @@ fn test_caching() {
         users,
         countries,
         cities,
-        employments: StatsHash::new("employments"),
-        companies: StatsHash::new("companies"),
-        issues: StatsHash::new("issues"),
+        employments: Table::new("employments"),
+        companies: Table::new("companies"),
+        issues: Table::new("issues"),
     };
 
     let (json, counts) = run_query(
@@ -1014,11 +1051,11 @@ fn test_caching() {
 
 #[test]
 fn test_loading_has_many_through() {
-    let mut cities = StatsHash::new("cities");
-    let mut companies = StatsHash::new("companies");
-    let mut countries = StatsHash::new("countries");
-    let mut employments = StatsHash::new("employments");
-    let mut users = StatsHash::new("users");
+    let mut cities = Table::new("cities");
+    let mut companies = Table::new("companies");
+    let mut countries = Table::new("countries");
+    let mut employments = Table::new("employments");
+    let mut users = Table::new("users");
 
     let mut country = models::Country {
         id: CountryId::from(1),
@@ -1066,7 +1103,7 @@ fn test_loading_has_many_through() {
         countries,
         employments,
         users,
-        issues: StatsHash::new("issues"),
+        issues: Table::new("issues"),
     };
 
     let (json, counts) = run_query(
@@ -1125,9 +1162,9 @@ fn test_loading_has_many_through() {
 
 #[test]
 fn test_loading_has_many_fk_optional() {
-    let mut countries = StatsHash::new("countries");
-    let mut users = StatsHash::new("users");
-    let mut issues = StatsHash::new("issues");
+    let mut countries = Table::new("countries");
+    let mut users = Table::new("users");
+    let mut issues = Table::new("issues");
 
     let country = models::Country {
         id: CountryId::from(1),
@@ -1156,10 +1193,10 @@ fn test_loading_has_many_fk_optional() {
     issues.insert(unassigned_issue.id, unassigned_issue);
 
     let db = Db {
-        cities: StatsHash::new("cities"),
-        companies: StatsHash::new("companies"),
+        cities: Table::new("cities"),
+        companies: Table::new("companies"),
         countries,
-        employments: StatsHash::new("employments"),
+        employments: Table::new("employments"),
         users,
         issues,
     };
@@ -1197,6 +1234,71 @@ fn test_loading_has_many_fk_optional() {
     );
 }
 
+#[test]
+fn test_loading_many_users_with_shared_countries() {
+    let mut countries = Table::new("countries");
+    let mut users = Table::new("users");
+
+    for id in 1..=10 {
+        let country = models::Country {
+            id: CountryId::from(id),
+        };
+        countries.insert(country.id, country);
+    }
+
+    for id in 1..=1000 {
+        let user = models::User {
+            id: UserId::from(id),
+            country_id: CountryId::from(id % 10 + 1),
+            city_id: None,
+        };
+        users.insert(user.id, user);
+    }
+
+    let db = Db {
+        users,
+        countries,
+        cities: Table::new("cities"),
+        employments: Table::new("employments"),
+        companies: Table::new("companies"),
+        issues: Table::new("issues"),
+    };
+
+    models::COUNTRY_ID_COMPARISONS.with(|count| count.set(0));
+
+    let (json, counts) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id }
+            }
+        }
+    "#,
+        db,
+    );
+
+    let json_users = json["users"].as_array().unwrap();
+    assert_eq!(1000, json_users.len());
+    for json_user in json_users {
+        let id = json_user["id"].as_i64().unwrap();
+        let country_id = json_user["country"]["id"].as_i64().unwrap();
+        assert_eq!(id % 10 + 1, country_id);
+    }
+
+    assert_eq!(1, counts.user_reads);
+    assert_eq!(1, counts.country_reads);
+
+    // Comparing every user with every country would take 10,000 comparisons. Matching through
+    // the join keys only confirms the few candidates found for each user.
+    let comparisons = models::COUNTRY_ID_COMPARISONS.with(|count| count.get());
+    assert!(
+        comparisons <= 3 * 1000,
+        "expected at most 3000 comparisons but got {}",
+        comparisons
+    );
+}
+
 struct DbStats {
     user_reads: usize,
     country_reads: usize,
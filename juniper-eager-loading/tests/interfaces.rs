@@ -3,10 +3,10 @@
 mod helpers;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
-use helpers::{SortedExtension, StatsHash};
+use helpers::SortedExtension;
 use juniper::{Executor, FieldError, FieldResult};
 use juniper_eager_loading::{
-    prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne, OptionHasOne,
+    in_memory::Table, prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne, OptionHasOne,
 };
 use juniper_from_schema::graphql_schema;
 use serde_json::{json, Value};
@@ -81,9 +81,9 @@ mod models {
 }
 
 pub struct Db {
-    users: StatsHash<i32, models::User>,
-    cities: StatsHash<i32, models::City>,
-    countries: StatsHash<i32, models::Country>,
+    users: Table<i32, models::User>,
+    cities: Table<i32, models::City>,
+    countries: Table<i32, models::Country>,
 }
 
 pub struct Context {
@@ -186,18 +186,18 @@ impl CountryFields for Country {
 
 #[test]
 fn loading_users_and_associations() {
-    let mut countries = StatsHash::new("countries");
+    let mut countries = Table::new("countries");
     let country = models::Country { id: 10 };
     countries.insert(country.id, country.clone());
 
-    let mut users = StatsHash::new("users");
+    let mut users = Table::new("users");
     let user = models::User {
         id: 10,
         country_id: country.id,
     };
     users.insert(user.id, user);
 
-    let mut cities = StatsHash::new("cities");
+    let mut cities = Table::new("cities");
     let city = models::City {
         id: 10,
         country_id: country.id,
@@ -0,0 +1,606 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_in_memory,
+    in_memory::{List, Table},
+    prelude::*,
+    testing::{load_and_record, ContextWithLoadRecorder, LoadCall, LoadRecorder},
+    Cache, ContextWithCache, EagerLoading, HasCount, HasMany, HasOne, LoadAggregate, LoadFrom,
+    LoadTree, OptionHasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        cars: [Car!]!
+        recentCars(first: Int!): [Car!]!
+        carCount: Int! @juniper(ownership: "owned")
+        referrer: User @juniper(ownership: "as_ref")
+    }
+
+    type Country {
+        id: Int!
+    }
+
+    type Car {
+        id: Int!
+        owner: User!
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+        pub referrer_id: Option<i32>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Country {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+}
+
+impl_load_from_for_in_memory! {
+    (
+        error = Box<dyn std::error::Error>,
+        context = Context,
+    ) => {
+        i32 -> (users, models::User),
+        i32 -> (countries, models::Country),
+        models::User.id -> (cars.user_id, models::Car),
+    }
+}
+
+/// The most recent cars of all the users.
+impl LoadFrom<models::User, UserRecentCarsArgs<'_>> for models::Car {
+    type Error = Box<dyn std::error::Error>;
+    type Context = Context;
+
+    fn load(
+        users: &[models::User],
+        args: &UserRecentCarsArgs<'_>,
+        ctx: &Self::Context,
+    ) -> Result<Vec<Self>, Self::Error> {
+        let mut cars: Vec<Self> = LoadFrom::load(users, &(), ctx)?;
+        cars.sort_by_key(|car| std::cmp::Reverse(car.id));
+        cars.truncate(args.first() as usize);
+        Ok(cars)
+    }
+}
+
+/// Field arguments that implement `Debug`, for calling `LoadFrom` outside of a query.
+#[derive(Debug)]
+pub struct RecentCars {
+    first: usize,
+}
+
+impl LoadFrom<models::User, RecentCars> for models::Car {
+    type Error = Box<dyn std::error::Error>;
+    type Context = Context;
+
+    fn load(
+        users: &[models::User],
+        args: &RecentCars,
+        ctx: &Self::Context,
+    ) -> Result<Vec<Self>, Self::Error> {
+        let mut cars: Vec<Self> = LoadFrom::load(users, &(), ctx)?;
+        cars.sort_by_key(|car| std::cmp::Reverse(car.id));
+        cars.truncate(args.first);
+        Ok(cars)
+    }
+}
+
+pub struct CarCount;
+
+impl LoadAggregate<models::User> for CarCount {
+    type Key = i32;
+    type Value = i64;
+    type Error = Box<dyn std::error::Error>;
+    type Context = Context;
+
+    fn load(
+        users: &[models::User],
+        _: &(),
+        ctx: &Self::Context,
+    ) -> Result<Vec<(i32, i64)>, Self::Error> {
+        let cars = ctx.db.cars.all_values();
+        Ok(users
+            .iter()
+            .map(|user| {
+                let count = cars.iter().filter(|car| car.user_id == user.id).count();
+                (user.id, count as i64)
+            })
+            .collect())
+    }
+}
+
+/// Loads the chain of referrers with a single read.
+pub struct ReferrerChain;
+
+impl LoadTree<models::User> for ReferrerChain {
+    type Error = Box<dyn std::error::Error>;
+    type Context = Context;
+
+    fn load(
+        users: &[models::User],
+        depth: usize,
+        ctx: &Self::Context,
+    ) -> Result<Vec<Vec<models::User>>, Self::Error> {
+        let all_users = ctx.db.users.all_values();
+
+        let mut levels: Vec<Vec<models::User>> = Vec::new();
+        while levels.len() < depth {
+            let referrer_ids = levels
+                .last()
+                .map(|level| &level[..])
+                .unwrap_or(users)
+                .iter()
+                .filter_map(|user| user.referrer_id)
+                .collect::<Vec<_>>();
+
+            if referrer_ids.is_empty() {
+                break;
+            }
+
+            levels.push(
+                all_users
+                    .iter()
+                    .filter(|user| referrer_ids.contains(&user.id))
+                    .map(|user| (*user).clone())
+                    .collect(),
+            );
+        }
+
+        Ok(levels)
+    }
+}
+
+pub struct Db {
+    users: Table<i32, models::User>,
+    countries: Table<i32, models::Country>,
+    cars: List<models::Car>,
+}
+
+pub struct Context {
+    db: Db,
+    load_recorder: LoadRecorder,
+    cache: Cache,
+}
+
+impl Context {
+    fn db(&self) -> &Db {
+        &self.db
+    }
+}
+
+impl ContextWithLoadRecorder for Context {
+    fn load_recorder(&self) -> &LoadRecorder {
+        &self.load_recorder
+    }
+}
+
+impl ContextWithCache for Context {
+    fn cache(&self) -> &Cache {
+        &self.cache
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let mut user_models = ctx
+            .db()
+            .users
+            .all_values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        user_models.sort_by_key(|user| user.id);
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(
+    context = Context,
+    error = Box<dyn std::error::Error>,
+    record_loads,
+    cache
+)]
+pub struct User {
+    user: models::User,
+    #[has_one(default)]
+    country: HasOne<Country>,
+    #[has_many(root_model_field = car)]
+    cars: HasMany<Car>,
+    #[has_many(
+        root_model_field = car,
+        graphql_field = recentCars,
+        field_arguments = UserRecentCarsArgs
+    )]
+    recent_cars: HasMany<Car>,
+    #[has_aggregate(aggregate = CarCount)]
+    car_count: HasCount,
+    #[option_has_one(root_model_field = user, tree_loader = ReferrerChain)]
+    referrer: OptionHasOne<Box<User>>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+
+    fn field_recent_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+        _: i32,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.recent_cars.try_unwrap()?)
+    }
+
+    fn field_car_count(&self, _: &Executor<'_, Context>) -> FieldResult<i32> {
+        Ok(*self.car_count.try_unwrap()? as i32)
+    }
+
+    fn field_referrer(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<Option<&User>> {
+        Ok(self.referrer.try_unwrap()?.as_deref())
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>, record_loads)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>, record_loads)]
+pub struct Car {
+    car: models::Car,
+    #[has_one(foreign_key_field = user_id, root_model_field = user)]
+    owner: HasOne<Box<User>>,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+
+    fn field_owner(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<&User> {
+        Ok(self.owner.try_unwrap()?)
+    }
+}
+
+fn setup_context() -> Context {
+    let mut users = Table::new("users");
+    for id in 1..=3 {
+        let referrer_id = if id == 1 { None } else { Some(id - 1) };
+        users.insert(
+            id,
+            models::User {
+                id,
+                country_id: 10,
+                referrer_id,
+            },
+        );
+    }
+
+    let mut countries = Table::new("countries");
+    countries.insert(10, models::Country { id: 10 });
+
+    let mut cars = List::new("cars");
+    cars.push(models::Car { id: 1, user_id: 1 });
+    cars.push(models::Car { id: 2, user_id: 3 });
+
+    Context {
+        db: Db {
+            users,
+            countries,
+            cars,
+        },
+        load_recorder: LoadRecorder::new(),
+        cache: Cache::new(),
+    }
+}
+
+#[test]
+fn recording_the_loads_of_a_query() {
+    let ctx = setup_context();
+
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id }
+                cars { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "country": { "id": 10 }, "cars": [{ "id": 1 }] },
+                { "id": 2, "country": { "id": 10 }, "cars": [] },
+                { "id": 3, "country": { "id": 10 }, "cars": [{ "id": 2 }] },
+            ]
+        }),
+        json,
+    );
+
+    let recorder = ctx.load_recorder();
+    assert_eq!(
+        vec![
+            LoadCall {
+                model: std::any::type_name::<models::Country>(),
+                from: "i32",
+                // The users share a country and the ids are deduplicated.
+                keys: 1,
+                field_args: "()".to_string(),
+            },
+            LoadCall {
+                model: std::any::type_name::<models::Car>(),
+                from: std::any::type_name::<models::User>(),
+                keys: 3,
+                field_args: "()".to_string(),
+            },
+        ],
+        recorder.calls(),
+    );
+    assert_eq!(1, recorder.loads_of::<models::Country>());
+    assert_eq!(0, recorder.loads_of::<models::User>());
+
+    recorder.assert_at_most_one_load_per_type();
+    recorder.assert_total_loads_at_most(2);
+    recorder.assert_loads_of_at_most::<models::Car>(1);
+
+    recorder.clear();
+    assert_eq!(0, recorder.loads_count());
+}
+
+#[test]
+fn failing_assertions_print_the_call_log() {
+    let ctx = setup_context();
+
+    run_query(
+        r#"
+        query Test {
+            users {
+                cars {
+                    owner {
+                        cars { id }
+                    }
+                }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    let recorder = ctx.load_recorder();
+    assert_eq!(3, recorder.loads_count());
+    assert_eq!(2, recorder.loads_of::<models::Car>());
+
+    let message = panic_message(|| recorder.assert_at_most_one_load_per_type());
+    assert!(message.contains(&format!(
+        "{} was loaded 2 times",
+        std::any::type_name::<models::Car>()
+    )));
+    assert!(message.contains(&format!(
+        "  1. {} from {} (3 keys, field args: ())",
+        std::any::type_name::<models::Car>(),
+        std::any::type_name::<models::User>(),
+    )));
+    assert!(message.contains(&format!(
+        "  2. {} from i32 (2 keys, field args: ())",
+        std::any::type_name::<models::User>(),
+    )));
+    assert!(message.contains(&format!(
+        "  3. {} from {} (2 keys, field args: ())",
+        std::any::type_name::<models::Car>(),
+        std::any::type_name::<models::User>(),
+    )));
+
+    let message = panic_message(|| recorder.assert_total_loads_at_most(2));
+    assert!(message.starts_with("Expected at most 2 loads but 3 were made"));
+    assert!(message.contains("Recorded loads:"));
+
+    let message = panic_message(|| recorder.assert_loads_of_at_most::<models::Car>(1));
+    assert!(message.contains("to be loaded at most 1 times but it was loaded 2 times"));
+}
+
+#[test]
+fn recording_aggregates_trees_and_field_arguments() {
+    let ctx = setup_context();
+
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                recentCars(first: 1) { id }
+                carCount
+                referrer {
+                    id
+                    referrer { id }
+                }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "recentCars": [], "carCount": 1, "referrer": null },
+                {
+                    "id": 2,
+                    "recentCars": [],
+                    "carCount": 0,
+                    "referrer": { "id": 1, "referrer": null },
+                },
+                {
+                    "id": 3,
+                    "recentCars": [{ "id": 2 }],
+                    "carCount": 1,
+                    "referrer": { "id": 2, "referrer": { "id": 1 } },
+                },
+            ]
+        }),
+        json,
+    );
+
+    let calls = ctx.load_recorder().calls();
+    let user = std::any::type_name::<models::User>();
+
+    // The generated field argument types don't implement `Debug`, so their type name is logged.
+    assert!(calls.contains(&LoadCall {
+        model: std::any::type_name::<models::Car>(),
+        from: user,
+        keys: 3,
+        field_args: std::any::type_name::<UserRecentCarsArgs<'_>>().to_string(),
+    }));
+    assert!(calls.contains(&LoadCall {
+        model: std::any::type_name::<CarCount>(),
+        from: user,
+        keys: 3,
+        field_args: "()".to_string(),
+    }));
+    assert!(calls.contains(&LoadCall {
+        model: user,
+        from: user,
+        keys: 3,
+        field_args: "depth: 2".to_string(),
+    }));
+}
+
+#[test]
+fn cached_models_are_only_recorded_when_loaded() {
+    let ctx = setup_context();
+    let query = "query Test { users { country { id } } }";
+
+    run_query(query, &ctx);
+    run_query(query, &ctx);
+
+    // The second query finds the country in the cache.
+    assert_eq!(1, ctx.load_recorder().loads_of::<models::Country>());
+}
+
+#[test]
+fn wrapping_a_load_made_by_hand() {
+    let ctx = setup_context();
+    let users = ctx
+        .db()
+        .users
+        .get_many(&[1, 3])
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let cars: Vec<models::Car> = load_and_record(&users, &RecentCars { first: 1 }, &ctx).unwrap();
+    assert_eq!(1, cars.len());
+
+    assert_eq!(
+        vec![LoadCall {
+            model: std::any::type_name::<models::Car>(),
+            from: std::any::type_name::<models::User>(),
+            keys: 2,
+            field_args: "RecentCars { first: 1 }".to_string(),
+        }],
+        ctx.load_recorder().calls(),
+    );
+}
+
+fn panic_message<F: FnOnce()>(f: F) -> String {
+    let err = catch_unwind(AssertUnwindSafe(f)).expect_err("expected the assertion to fail");
+    match err.downcast::<String>() {
+        Ok(message) => *message,
+        Err(err) => err.downcast_ref::<&str>().unwrap().to_string(),
+    }
+}
+
+fn run_query(query: &str, ctx: &Context) -> Value {
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
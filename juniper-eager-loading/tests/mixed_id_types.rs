@@ -1,11 +1,8 @@
 #![allow(unused_variables, unused_imports, dead_code, unused_mut)]
 
-mod helpers;
-
 use assert_json_diff::assert_json_include;
-use helpers::StatsHash;
 use juniper::{EmptyMutation, Executor, FieldResult, ID};
-use juniper_eager_loading::{prelude::*, EagerLoading, HasManyThrough, HasOne};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasManyThrough, HasOne};
 use juniper_from_schema::graphql_schema;
 use serde_json::{json, Value};
 
@@ -121,8 +118,8 @@ mod models {
 }
 
 pub struct Db {
-    users: StatsHash<i32, models::User>,
-    countries: StatsHash<i64, models::Country>,
+    users: Table<i32, models::User>,
+    countries: Table<i64, models::Country>,
     visits: Vec<models::Visit>,
 }
 
@@ -213,8 +210,8 @@ impl CountryFields for Country {
 
 #[test]
 fn loading_users_and_associations() {
-    let mut countries = StatsHash::new("countries");
-    let mut users = StatsHash::new("users");
+    let mut countries = Table::new("countries");
+    let mut users = Table::new("users");
 
     let country = models::Country { id: 10 };
 
@@ -268,8 +265,8 @@ fn loading_users_and_associations() {
 
 #[test]
 fn has_many_through_fkey() {
-    let mut countries = StatsHash::new("countries");
-    let mut users = StatsHash::new("users");
+    let mut countries = Table::new("countries");
+    let mut users = Table::new("users");
     let mut visits = vec![];
 
     let country = models::Country { id: 10 };
@@ -0,0 +1,282 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_in_memory,
+    in_memory::{List, Table},
+    prelude::*,
+    CloneChild, EagerLoading, HasMany, HasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users(repeat: Int = 1): [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        cars: [Car!]!
+        country: Country!
+    }
+
+    type Car {
+        id: Int!
+    }
+
+    type Country {
+        id: Int!
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+        pub country_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Country {
+        pub id: i32,
+    }
+}
+
+impl_load_from_for_in_memory! {
+    (
+        error = Box<dyn std::error::Error>,
+        context = Context,
+    ) => {
+        i32 -> (countries, models::Country),
+        models::User.id -> (cars.user_id, models::Car),
+    }
+}
+
+pub struct Db {
+    users: Table<i32, models::User>,
+    cars: List<models::Car>,
+    countries: Table<i32, models::Country>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl Context {
+    fn db(&self) -> &Db {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+        repeat: i32,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let mut user_models = ctx
+            .db()
+            .users
+            .all_values()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        user_models.sort_by_key(|user| user.id);
+        let user_models = (0..repeat)
+            .flat_map(|_| user_models.clone())
+            .collect::<Vec<_>>();
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+// Only `Country` implements `Clone`, since a country can belong to several users.
+#[derive(EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_many(root_model_field = car)]
+    cars: HasMany<Car>,
+    #[has_one(default)]
+    country: HasOne<Country>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+}
+
+#[derive(EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CloneChild for Car {
+    fn clone_child(&self) -> Option<Self> {
+        None
+    }
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+}
+
+fn setup_context() -> Context {
+    let mut users = Table::new("users");
+    users.insert(
+        1,
+        models::User {
+            id: 1,
+            country_id: 10,
+        },
+    );
+    users.insert(
+        2,
+        models::User {
+            id: 2,
+            country_id: 20,
+        },
+    );
+
+    let mut cars = List::new("cars");
+    cars.push(models::Car { id: 1, user_id: 1 });
+    cars.push(models::Car { id: 2, user_id: 2 });
+    cars.push(models::Car { id: 3, user_id: 1 });
+
+    let mut countries = Table::new("countries");
+    countries.insert(10, models::Country { id: 10 });
+    countries.insert(20, models::Country { id: 20 });
+
+    Context {
+        db: Db {
+            users,
+            cars,
+            countries,
+        },
+    }
+}
+
+#[test]
+fn children_that_belong_to_a_single_parent_dont_have_to_be_clone() {
+    let ctx = setup_context();
+
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                cars { id }
+                country { id }
+            }
+        }
+    "#,
+        &ctx,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "cars": [{ "id": 1 }, { "id": 3 }], "country": { "id": 10 } },
+                { "id": 2, "cars": [{ "id": 2 }], "country": { "id": 20 } },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn sharing_a_child_that_cannot_be_cloned_errors() {
+    let ctx = setup_context();
+
+    let (result, errors) = juniper::execute(
+        r#"
+        query Test {
+            users(repeat: 2) {
+                country { id }
+                cars { id }
+            }
+        }
+    "#,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    assert_eq!(1, errors.len());
+    assert_eq!(
+        "A child of `HasMany` belongs to more than one parent but cannot be cloned",
+        errors[0].error().message(),
+    );
+}
+
+fn run_query(query: &str, ctx: &Context) -> Value {
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
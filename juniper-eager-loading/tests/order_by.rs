@@ -0,0 +1,353 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{prelude::*, EagerLoading, HasMany, HasManyThrough};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        carsByName: [Car!]!
+        carsByNewest: [Car!]!
+        companies: [Company!]!
+    }
+
+    type Car {
+        id: Int!
+        name: String!
+    }
+
+    type Company {
+        id: Int!
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+        pub name: String,
+    }
+
+    impl Car {
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Company {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Employment {
+        pub id: i32,
+        pub user_id: i32,
+        pub company_id: i32,
+        pub started_at: i32,
+    }
+
+    impl juniper_eager_loading::LoadFrom<User> for Car {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(users: &[User], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .cars
+                .iter()
+                .filter(|car| users.iter().any(|user| user.id == car.user_id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<User> for Employment {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(users: &[User], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .employments
+                .iter()
+                .filter(|employment| users.iter().any(|user| user.id == employment.user_id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl juniper_eager_loading::LoadFrom<Employment> for Company {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(
+            employments: &[Employment],
+            _: &(),
+            ctx: &Self::Context,
+        ) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .companies
+                .iter()
+                .filter(|company| {
+                    employments
+                        .iter()
+                        .any(|employment| employment.company_id == company.id)
+                })
+                .cloned()
+                .collect())
+        }
+    }
+}
+
+pub struct Context {
+    users: Vec<models::User>,
+    cars: Vec<models::Car>,
+    companies: Vec<models::Company>,
+    employments: Vec<models::Employment>,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let users = User::eager_load_each(&ctx.users, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_many(root_model_field = car, order_by = name)]
+    cars_by_name: HasMany<Car>,
+    #[has_many(root_model_field = car, order_by = |a, b| b.id.cmp(&a.id))]
+    cars_by_newest: HasMany<Car>,
+    #[has_many_through(join_model = models::Employment, order_by = employment.started_at)]
+    companies: HasManyThrough<Company>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_cars_by_name(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars_by_name.try_unwrap()?)
+    }
+
+    fn field_cars_by_newest(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars_by_newest.try_unwrap()?)
+    }
+
+    fn field_companies(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Company, Walked>,
+    ) -> FieldResult<&Vec<Company>> {
+        Ok(self.companies.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+
+    fn field_name(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.car.name)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Company {
+    company: models::Company,
+}
+
+impl CompanyFields for Company {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.company.id)
+    }
+}
+
+fn car(id: i32, user_id: i32, name: &str) -> models::Car {
+    models::Car {
+        id,
+        user_id,
+        name: name.to_string(),
+    }
+}
+
+fn employment(id: i32, user_id: i32, company_id: i32, started_at: i32) -> models::Employment {
+    models::Employment {
+        id,
+        user_id,
+        company_id,
+        started_at,
+    }
+}
+
+fn setup_context() -> Context {
+    Context {
+        users: vec![models::User { id: 1 }, models::User { id: 2 }],
+        cars: vec![
+            car(1, 1, "Volvo"),
+            car(2, 2, "Tesla"),
+            car(3, 1, "Audi"),
+            car(4, 2, "BMW"),
+            car(5, 1, "Fiat"),
+        ],
+        companies: vec![
+            models::Company { id: 1 },
+            models::Company { id: 2 },
+            models::Company { id: 3 },
+        ],
+        employments: vec![
+            employment(1, 1, 1, 2015),
+            employment(2, 1, 2, 2010),
+            employment(3, 1, 3, 2020),
+            employment(4, 2, 1, 2019),
+            employment(5, 2, 3, 2001),
+        ],
+    }
+}
+
+#[test]
+fn ordering_children_by_a_key_method() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                carsByName { name }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "carsByName": [
+                        { "name": "Audi" },
+                        { "name": "Fiat" },
+                        { "name": "Volvo" },
+                    ],
+                },
+                {
+                    "carsByName": [
+                        { "name": "BMW" },
+                        { "name": "Tesla" },
+                    ],
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn ordering_children_with_a_comparator() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                carsByNewest { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "carsByNewest": [{ "id": 5 }, { "id": 3 }, { "id": 1 }] },
+                { "carsByNewest": [{ "id": 4 }, { "id": 2 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn ordering_children_by_a_join_model_field() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                companies { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "companies": [{ "id": 2 }, { "id": 1 }, { "id": 3 }] },
+                { "companies": [{ "id": 3 }, { "id": 1 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_context();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
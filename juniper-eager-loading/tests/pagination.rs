@@ -0,0 +1,481 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+#[macro_use]
+extern crate diesel;
+
+use assert_json_diff::assert_json_eq;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_diesel_sqlite, prelude::*, EagerLoading, HasMany, HasManyThrough, LoadFrom,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        cars(first: Int = 100, after: String = ""): [Car!]!
+        carsByName(first: Int = 100, after: String = ""): [Car!]!
+        companies(first: Int = 100, after: String = ""): [Company!]!
+    }
+
+    type Car {
+        id: Int!
+    }
+
+    type Company {
+        id: Int!
+    }
+}
+
+mod db_schema {
+    table! {
+        users (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        cars (id) {
+            id -> Integer,
+            user_id -> Integer,
+            name -> Text,
+        }
+    }
+
+    table! {
+        companies (id) {
+            id -> Integer,
+        }
+    }
+
+    table! {
+        employments (id) {
+            id -> Integer,
+            user_id -> Integer,
+            company_id -> Integer,
+        }
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, Queryable)]
+    pub struct User {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable, PartialEq)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+        pub name: String,
+    }
+
+    impl Car {
+        pub fn name(&self) -> &str {
+            &self.name
+        }
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Company {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug, Queryable)]
+    pub struct Employment {
+        pub id: i32,
+        pub user_id: i32,
+        pub company_id: i32,
+    }
+}
+
+use db_schema::{cars, companies, employments, users};
+
+/// Arguments used to call `LoadFrom` directly, outside of a GraphQL query.
+pub struct PageArgs {
+    first: Option<i32>,
+    after: Option<String>,
+}
+
+impl PageArgs {
+    fn first(&self) -> Option<i32> {
+        self.first
+    }
+
+    fn after(&self) -> Option<String> {
+        self.after.clone()
+    }
+}
+
+impl_load_from_for_diesel_sqlite! {
+    (
+        error = diesel::result::Error,
+        context = Context,
+    ) => {
+        i32 -> (companies, models::Company),
+        models::User.id -> (cars.user_id, models::Car, paginate = UserCarsArgs<'_>),
+        models::User.id -> (cars.user_id, models::Car, paginate = PageArgs),
+        models::User.id -> (
+            cars.user_id,
+            models::Car,
+            paginate = UserCarsByNameArgs<'_>,
+            order_by = name,
+        ),
+        models::User.id -> (employments.user_id, models::Employment, paginate = UserCompaniesArgs<'_>),
+        models::Employment.company_id -> (companies.id, models::Company),
+    }
+}
+
+impl LoadFrom<models::Employment, UserCompaniesArgs<'_>> for models::Company {
+    type Error = diesel::result::Error;
+    type Context = Context;
+
+    fn load(
+        employments: &[models::Employment],
+        _: &UserCompaniesArgs<'_>,
+        ctx: &Self::Context,
+    ) -> Result<Vec<Self>, Self::Error> {
+        LoadFrom::load(employments, &(), ctx)
+    }
+}
+
+pub struct Context {
+    db: SqliteConnection,
+}
+
+impl Context {
+    fn db(&self) -> &SqliteConnection {
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = users::table
+            .order(users::id)
+            .load::<models::User>(ctx.db())?;
+        let users = User::eager_load_each(&user_models, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct User {
+    user: models::User,
+    #[has_many(root_model_field = car, field_arguments = UserCarsArgs, paginate)]
+    cars: HasMany<Car>,
+    #[has_many(
+        root_model_field = car,
+        field_arguments = UserCarsByNameArgs,
+        paginate,
+        order_by = name
+    )]
+    cars_by_name: HasMany<Car>,
+    #[has_many_through(join_model = models::Employment, field_arguments = UserCompaniesArgs, paginate)]
+    companies: HasManyThrough<Company>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+        _: i32,
+        _: String,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+
+    fn field_cars_by_name(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+        _: i32,
+        _: String,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars_by_name.try_unwrap()?)
+    }
+
+    fn field_companies(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Company, Walked>,
+        _: i32,
+        _: String,
+    ) -> FieldResult<&Vec<Company>> {
+        Ok(self.companies.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Car {
+    car: models::Car,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = diesel::result::Error)]
+pub struct Company {
+    company: models::Company,
+}
+
+impl CompanyFields for Company {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.company.id)
+    }
+}
+
+/// Two users with four cars and three employments each. The names of the cars are in a different
+/// order than their ids.
+fn setup_db() -> Context {
+    let db = SqliteConnection::establish(":memory:").unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (id INTEGER PRIMARY KEY)",
+        "CREATE TABLE cars (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL, name TEXT NOT NULL)",
+        "CREATE TABLE companies (id INTEGER PRIMARY KEY)",
+        "CREATE TABLE employments (id INTEGER PRIMARY KEY, user_id INTEGER NOT NULL, company_id INTEGER NOT NULL)",
+        "INSERT INTO users (id) VALUES (1), (2)",
+        "INSERT INTO cars (id, user_id, name) VALUES (1, 1, 'delta'), (2, 2, 'alpha'), (3, 1, 'bravo'), (4, 2, 'delta'), (5, 1, 'charlie'), (6, 2, 'charlie'), (7, 1, 'alpha'), (8, 2, 'bravo')",
+        "INSERT INTO companies (id) VALUES (1), (2), (3)",
+        "INSERT INTO employments (id, user_id, company_id) VALUES (1, 1, 3), (2, 2, 1), (3, 1, 2), (4, 2, 2), (5, 1, 1), (6, 2, 3)",
+    ] {
+        diesel::sql_query(*sql).execute(&db).unwrap();
+    }
+
+    Context { db }
+}
+
+#[test]
+fn loading_the_first_children_of_each_parent() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                cars(first: 2) { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "cars": [{ "id": 1 }, { "id": 3 }] },
+                { "id": 2, "cars": [{ "id": 2 }, { "id": 4 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_the_children_after_a_cursor() {
+    let cursor = juniper_eager_loading::encode_cursor(1);
+
+    let json = run_query(&format!(
+        r#"
+        query Test {{
+            users {{
+                id
+                cars(first: 1, after: "{}") {{ id }}
+                companies(after: "{}") {{ id }}
+            }}
+        }}
+    "#,
+        cursor, cursor
+    ));
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "cars": [{ "id": 5 }], "companies": [{ "id": 1 }] },
+                { "id": 2, "cars": [{ "id": 6 }], "companies": [{ "id": 3 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn paginating_ordered_children() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                carsByName(first: 2) { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "carsByName": [{ "id": 7 }, { "id": 3 }] },
+                { "id": 2, "carsByName": [{ "id": 2 }, { "id": 8 }] },
+            ]
+        }),
+        json,
+    );
+
+    let cursor = juniper_eager_loading::encode_cursor(1);
+    let json = run_query(&format!(
+        r#"
+        query Test {{
+            users {{
+                id
+                carsByName(first: 1, after: "{}") {{ id }}
+            }}
+        }}
+    "#,
+        cursor
+    ));
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "carsByName": [{ "id": 5 }] },
+                { "id": 2, "carsByName": [{ "id": 6 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_children_through_a_join_model() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                companies(first: 2) { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "id": 1, "companies": [{ "id": 3 }, { "id": 2 }] },
+                { "id": 2, "companies": [{ "id": 1 }, { "id": 2 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_all_children_without_arguments() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                cars { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                { "cars": [{ "id": 1 }, { "id": 3 }, { "id": 5 }, { "id": 7 }] },
+                { "cars": [{ "id": 2 }, { "id": 4 }, { "id": 6 }, { "id": 8 }] },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn the_database_only_returns_the_rows_that_are_needed() {
+    let ctx = setup_db();
+    let users = users::table
+        .order(users::id)
+        .load::<models::User>(ctx.db())
+        .unwrap();
+
+    let args = PageArgs {
+        first: Some(1),
+        after: Some(juniper_eager_loading::encode_cursor(0)),
+    };
+    let cars: Vec<models::Car> = LoadFrom::load(&users, &args, &ctx).unwrap();
+
+    // The first three cars of each user. The first one is skipped after matching the cars with
+    // their users and the last one is only used to tell if there is a next page.
+    assert_eq!(
+        vec![1, 2, 3, 4, 5, 6],
+        cars.iter().map(|car| car.id).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn loading_a_page_for_no_parents() {
+    let ctx = setup_db();
+
+    let args = PageArgs {
+        first: Some(1),
+        after: None,
+    };
+    let cars: Vec<models::Car> = LoadFrom::load(&[] as &[models::User], &args, &ctx).unwrap();
+
+    assert!(cars.is_empty());
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
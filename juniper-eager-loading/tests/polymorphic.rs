@@ -0,0 +1,377 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    in_memory::Table, prelude::*, EagerLoading, HasOne, PolymorphicHasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      comments: [Comment!]! @juniper(ownership: "owned")
+    }
+
+    type Comment {
+        id: Int!
+        commentable: Commentable!
+    }
+
+    union Commentable = Post | Issue
+
+    type Post {
+        id: Int!
+        author: User!
+    }
+
+    type Issue {
+        id: Int!
+        number: Int!
+    }
+
+    type User {
+        id: Int!
+    }
+}
+
+mod models {
+    use juniper_eager_loading::LoadFrom;
+
+    #[derive(Clone, Debug)]
+    pub struct Comment {
+        pub id: i32,
+        pub commentable_type: String,
+        pub commentable_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Post {
+        pub id: i32,
+        pub author_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Issue {
+        pub id: i32,
+        pub number: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+    }
+
+    impl LoadFrom<i32> for Post {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .posts
+                .all_values()
+                .into_iter()
+                .filter(|post| ids.contains(&post.id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl LoadFrom<i32> for Issue {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .issues
+                .all_values()
+                .into_iter()
+                .filter(|issue| ids.contains(&issue.id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl LoadFrom<i32> for User {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .users
+                .all_values()
+                .into_iter()
+                .filter(|user| ids.contains(&user.id))
+                .cloned()
+                .collect())
+        }
+    }
+}
+
+pub struct Db {
+    comments: Vec<models::Comment>,
+    posts: Table<i32, models::Post>,
+    issues: Table<i32, models::Issue>,
+    users: Table<i32, models::User>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_comments<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, Comment, Walked>,
+    ) -> FieldResult<Vec<Comment>> {
+        let ctx = executor.context();
+        let comments = Comment::eager_load_each(&ctx.db.comments, ctx, trail)?;
+        Ok(comments)
+    }
+}
+
+#[derive(EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Comment {
+    comment: models::Comment,
+    #[polymorphic_has_one(variants = [Post, Issue = "issue"])]
+    commentable: PolymorphicHasOne<Commentable>,
+}
+
+impl CommentFields for Comment {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.comment.id)
+    }
+
+    fn field_commentable(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Commentable, Walked>,
+    ) -> FieldResult<&Commentable> {
+        Ok(self.commentable.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Post {
+    post: models::Post,
+    #[has_one(root_model_field = user)]
+    author: HasOne<User>,
+}
+
+impl PostFields for Post {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.post.id)
+    }
+
+    fn field_author(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<&User> {
+        Ok(self.author.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Issue {
+    issue: models::Issue,
+}
+
+impl IssueFields for Issue {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.issue.id)
+    }
+
+    fn field_number(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.issue.number)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+}
+
+fn comment(id: i32, commentable_type: &str, commentable_id: i32) -> models::Comment {
+    models::Comment {
+        id,
+        commentable_type: commentable_type.to_string(),
+        commentable_id,
+    }
+}
+
+fn setup_db(comments: Vec<models::Comment>) -> Db {
+    let mut posts = Table::new("posts");
+    posts.insert(
+        1,
+        models::Post {
+            id: 1,
+            author_id: 1,
+        },
+    );
+    posts.insert(
+        2,
+        models::Post {
+            id: 2,
+            author_id: 2,
+        },
+    );
+
+    let mut issues = Table::new("issues");
+    issues.insert(1, models::Issue { id: 1, number: 100 });
+    issues.insert(2, models::Issue { id: 2, number: 200 });
+
+    let mut users = Table::new("users");
+    users.insert(1, models::User { id: 1 });
+    users.insert(2, models::User { id: 2 });
+
+    Db {
+        comments,
+        posts,
+        issues,
+        users,
+    }
+}
+
+#[test]
+fn loading_each_type_of_child() {
+    let db = setup_db(vec![
+        comment(1, "Post", 1),
+        comment(2, "issue", 1),
+        comment(3, "Post", 2),
+        comment(4, "issue", 2),
+        comment(5, "Post", 1),
+    ]);
+
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            comments {
+                id
+                commentable {
+                    ... on Post {
+                        id
+                        author { id }
+                    }
+                    ... on Issue {
+                        id
+                        number
+                    }
+                }
+            }
+        }
+    "#,
+        db,
+    );
+
+    assert_json_eq!(
+        json!({
+            "comments": [
+                { "id": 1, "commentable": { "id": 1, "author": { "id": 1 } } },
+                { "id": 2, "commentable": { "id": 1, "number": 100 } },
+                { "id": 3, "commentable": { "id": 2, "author": { "id": 2 } } },
+                { "id": 4, "commentable": { "id": 2, "number": 200 } },
+                { "id": 5, "commentable": { "id": 1, "author": { "id": 1 } } },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.posts.reads_count());
+    assert_eq!(1, ctx.db.issues.reads_count());
+    assert_eq!(1, ctx.db.users.reads_count());
+}
+
+#[test]
+fn types_without_parents_are_not_loaded() {
+    let db = setup_db(vec![comment(1, "Post", 1), comment(2, "Post", 2)]);
+
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            comments {
+                commentable {
+                    ... on Post { id }
+                    ... on Issue { id }
+                }
+            }
+        }
+    "#,
+        db,
+    );
+
+    assert_json_eq!(
+        json!({
+            "comments": [
+                { "commentable": { "id": 1 } },
+                { "commentable": { "id": 2 } },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.posts.reads_count());
+    assert_eq!(0, ctx.db.issues.reads_count());
+}
+
+#[test]
+fn unknown_types_fail_to_load() {
+    let db = setup_db(vec![comment(1, "Post", 1), comment(2, "Video", 1)]);
+    let ctx = Context { db };
+
+    let (result, errors) = juniper::execute(
+        "query Test { comments { commentable { ... on Post { id } } } }",
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    assert_eq!(1, errors.len());
+}
+
+fn run_query(query: &str, db: Db) -> (Value, Context) {
+    let ctx = Context { db };
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    (json, ctx)
+}
@@ -1,12 +1,10 @@
 #![allow(unused_variables, unused_imports, dead_code, unused_mut)]
 
-mod helpers;
-
 use assert_json_diff::assert_json_include;
-use helpers::StatsHash;
 use juniper::{EmptyMutation, Executor, FieldResult, ID};
 use juniper_eager_loading::{
-    prelude::*, EagerLoading, HasManyThrough, HasOne, LoadChildrenOutput, LoadFrom, OptionHasOne,
+    in_memory::Table, prelude::*, EagerLoading, HasManyThrough, HasOne, LoadChildrenOutput,
+    LoadFrom, OptionHasOne,
 };
 use juniper_from_schema::graphql_schema;
 use serde_json::{json, Value};
@@ -56,7 +54,7 @@ mod models {
 }
 
 pub struct Db {
-    users: StatsHash<i32, models::User>,
+    users: Table<i32, models::User>,
 }
 
 pub struct Context {
@@ -132,7 +130,7 @@ impl UserFields for User {
 
 #[test]
 fn loading_recursive_type() {
-    let mut users = StatsHash::new("users");
+    let mut users = Table::new("users");
 
     users.insert(
         1,
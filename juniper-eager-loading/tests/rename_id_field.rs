@@ -3,7 +3,7 @@
 mod helpers;
 
 use assert_json_diff::{assert_json_eq, assert_json_include};
-use helpers::{SortedExtension, StatsHash};
+use helpers::SortedExtension;
 use juniper::{Executor, FieldError, FieldResult};
 use juniper_eager_loading::{
     prelude::*, EagerLoading, HasMany, HasManyThrough, HasOne, OptionHasOne,
@@ -0,0 +1,321 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use futures::executor::block_on;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    impl_load_from_for_sqlx, prelude::*, AsyncEagerLoading, AsyncLoadFrom, HasMany, HasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    type User {
+        id: Int!
+        country: Country!
+        cars: [Car!]!
+    }
+
+    type Country {
+        id: Int!
+        isoCode: String!
+    }
+
+    type Car {
+        id: Int!
+        owner: User!
+    }
+}
+
+mod models {
+    #[derive(Clone, Debug, sqlx::FromRow)]
+    pub struct User {
+        pub id: i32,
+        pub country_code: String,
+    }
+
+    #[derive(Clone, Debug, sqlx::FromRow)]
+    pub struct Country {
+        pub id: i32,
+        pub iso_code: String,
+    }
+
+    #[derive(Clone, Debug, sqlx::FromRow)]
+    pub struct Car {
+        pub id: i32,
+        pub user_id: i32,
+    }
+}
+
+impl_load_from_for_sqlx! {
+    (
+        error = sqlx::Error,
+        context = Context,
+        database = Sqlite,
+        connection = |ctx| ctx.connection(),
+    ) => {
+        i32 -> (users, models::User),
+        String -> (countries.iso_code, models::Country),
+        models::User.id -> (cars.user_id, models::Car),
+    }
+}
+
+// Only compiled, as there is no Postgres database to run against.
+mod postgres {
+    use super::models;
+    use juniper_eager_loading::{impl_load_from_for_sqlx, AsyncLoadFrom};
+    use static_assertions::assert_impl_all;
+
+    pub struct Context {
+        db: sqlx::PgPool,
+        replica: sqlx::PgPool,
+    }
+
+    impl Context {
+        fn db(&self) -> &sqlx::PgPool {
+            &self.db
+        }
+    }
+
+    #[derive(Clone, Debug, sqlx::FromRow)]
+    pub struct Employment {
+        pub id: i32,
+        pub user_id: i32,
+    }
+
+    impl_load_from_for_sqlx! {
+        (
+            error = sqlx::Error,
+            context = Context,
+            database = Postgres,
+        ) => {
+            i32 -> (employments, Employment),
+            String -> (employments.name, Employment),
+            models::User.id -> (employments.user_id, Employment, connection = |ctx| &ctx.replica),
+        }
+    }
+
+    assert_impl_all!(
+        Employment: AsyncLoadFrom<i32>,
+        AsyncLoadFrom<String>,
+        AsyncLoadFrom<models::User>
+    );
+}
+
+pub struct Context {
+    db: SqlitePool,
+    connections: AtomicUsize,
+}
+
+impl Context {
+    fn db(&self) -> &SqlitePool {
+        &self.db
+    }
+
+    /// The connection used by `LoadFrom`, which counts how many times it is used.
+    fn connection(&self) -> &SqlitePool {
+        self.connections.fetch_add(1, Ordering::SeqCst);
+        &self.db
+    }
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let user_models = block_on(
+            sqlx::query_as::<_, models::User>("SELECT * FROM users ORDER BY id")
+                .fetch_all(ctx.db()),
+        )?;
+        let users = block_on(User::eager_load_each(&user_models, ctx, trail))?;
+        Ok(users)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = sqlx::Error, asynchronous)]
+pub struct User {
+    user: models::User,
+    #[has_one(foreign_key_field = country_code, child_primary_key_field = iso_code)]
+    country: HasOne<Country>,
+    #[has_many(root_model_field = car)]
+    cars: HasMany<Car>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_country(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Country, Walked>,
+    ) -> FieldResult<&Country> {
+        Ok(self.country.try_unwrap()?)
+    }
+
+    fn field_cars(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Car, Walked>,
+    ) -> FieldResult<&Vec<Car>> {
+        Ok(self.cars.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = sqlx::Error, asynchronous)]
+pub struct Country {
+    country: models::Country,
+}
+
+impl CountryFields for Country {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.country.id)
+    }
+
+    fn field_iso_code(&self, _: &Executor<'_, Context>) -> FieldResult<&String> {
+        Ok(&self.country.iso_code)
+    }
+}
+
+#[derive(Clone, Debug, EagerLoading)]
+#[eager_loading(context = Context, error = sqlx::Error, asynchronous)]
+pub struct Car {
+    car: models::Car,
+    #[has_one(foreign_key_field = user_id, root_model_field = user)]
+    owner: HasOne<Box<User>>,
+}
+
+impl CarFields for Car {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.car.id)
+    }
+
+    fn field_owner(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<&User> {
+        Ok(self.owner.try_unwrap()?)
+    }
+}
+
+fn setup_db() -> Context {
+    // Every connection to `sqlite::memory:` opens a new database, so the pool only has one.
+    let db = block_on(
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:"),
+    )
+    .unwrap();
+
+    for sql in &[
+        "CREATE TABLE users (id INTEGER NOT NULL PRIMARY KEY, country_code TEXT NOT NULL)",
+        "CREATE TABLE countries (id INTEGER NOT NULL PRIMARY KEY, iso_code TEXT NOT NULL UNIQUE)",
+        "CREATE TABLE cars (id INTEGER NOT NULL PRIMARY KEY, user_id INTEGER NOT NULL)",
+        "INSERT INTO users (id, country_code) VALUES (1, 'DK'), (2, 'SE')",
+        "INSERT INTO countries (id, iso_code) VALUES (10, 'SE'), (20, 'DK'), (30, 'NO')",
+        "INSERT INTO cars (id, user_id) VALUES (1, 2), (2, 1), (3, 2)",
+    ] {
+        block_on(sqlx::query(sql).execute(&db)).unwrap();
+    }
+
+    Context {
+        db,
+        connections: AtomicUsize::new(0),
+    }
+}
+
+#[test]
+fn loading_with_sqlx() {
+    let json = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                country { id isoCode }
+                cars {
+                    id
+                    owner { id }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 1,
+                    "country": { "id": 20, "isoCode": "DK" },
+                    "cars": [{ "id": 2, "owner": { "id": 1 } }],
+                },
+                {
+                    "id": 2,
+                    "country": { "id": 10, "isoCode": "SE" },
+                    "cars": [
+                        { "id": 1, "owner": { "id": 2 } },
+                        { "id": 3, "owner": { "id": 2 } },
+                    ],
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_without_ids_doesnt_query_the_database() {
+    let ctx = setup_db();
+
+    let cars: Vec<models::Car> =
+        block_on(AsyncLoadFrom::load(&[] as &[models::User], &(), &ctx)).unwrap();
+    assert!(cars.is_empty());
+    assert_eq!(0, ctx.connections.load(Ordering::SeqCst));
+
+    let users: Vec<models::User> = block_on(AsyncLoadFrom::load(&[1], &(), &ctx)).unwrap();
+    assert_eq!(1, users.len());
+    assert_eq!(1, ctx.connections.load(Ordering::SeqCst));
+}
+
+fn run_query(query: &str) -> Value {
+    let ctx = setup_db();
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap()
+}
@@ -0,0 +1,533 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{
+    in_memory::Table, prelude::*, EagerLoading, HasMany, HasOne, LoadTree, OptionHasOne,
+};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      categories: [Category!]! @juniper(ownership: "owned")
+      employees: [Employee!]! @juniper(ownership: "owned")
+    }
+
+    type Category {
+        id: Int!
+        owner: User!
+        parent: Category @juniper(ownership: "as_ref")
+        children: [Category!]!
+    }
+
+    type User {
+        id: Int!
+    }
+
+    type Employee {
+        id: Int!
+        manager: Employee @juniper(ownership: "as_ref")
+    }
+}
+
+mod models {
+    use juniper_eager_loading::LoadFrom;
+
+    #[derive(Clone, Debug)]
+    pub struct Category {
+        pub id: i32,
+        pub parent_id: Option<i32>,
+        pub owner_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Employee {
+        pub id: i32,
+        pub manager_id: Option<i32>,
+    }
+
+    impl LoadFrom<i32> for Category {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .categories
+                .all_values()
+                .into_iter()
+                .filter(|category| ids.contains(&category.id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl LoadFrom<Category> for Category {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(
+            parents: &[Category],
+            _: &(),
+            ctx: &Self::Context,
+        ) -> Result<Vec<Self>, Self::Error> {
+            let parent_ids = parents.iter().map(|parent| parent.id).collect::<Vec<_>>();
+
+            let mut children = ctx
+                .db
+                .categories
+                .all_values()
+                .into_iter()
+                .filter(|category| {
+                    category
+                        .parent_id
+                        .map(|parent_id| parent_ids.contains(&parent_id))
+                        .unwrap_or(false)
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            children.sort_by_key(|category| category.id);
+
+            Ok(children)
+        }
+    }
+
+    impl LoadFrom<i32> for User {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .users
+                .all_values()
+                .into_iter()
+                .filter(|user| ids.contains(&user.id))
+                .cloned()
+                .collect())
+        }
+    }
+
+    impl LoadFrom<i32> for Employee {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .employees
+                .all_values()
+                .into_iter()
+                .filter(|employee| ids.contains(&employee.id))
+                .cloned()
+                .collect())
+        }
+    }
+}
+
+/// Loads a chain of managers with a single read, like a recursive query would.
+pub struct ManagerChain;
+
+impl LoadTree<models::Employee> for ManagerChain {
+    type Error = Box<dyn std::error::Error>;
+    type Context = Context;
+
+    fn load(
+        employees: &[models::Employee],
+        depth: usize,
+        ctx: &Self::Context,
+    ) -> Result<Vec<Vec<models::Employee>>, Self::Error> {
+        let all_employees = ctx.db.employees.all_values();
+
+        let mut levels: Vec<Vec<models::Employee>> = Vec::new();
+        while levels.len() < depth {
+            let manager_ids = levels
+                .last()
+                .map(|level| &level[..])
+                .unwrap_or(employees)
+                .iter()
+                .filter_map(|employee| employee.manager_id)
+                .collect::<Vec<_>>();
+
+            if manager_ids.is_empty() {
+                break;
+            }
+
+            levels.push(
+                all_employees
+                    .iter()
+                    .filter(|employee| manager_ids.contains(&employee.id))
+                    .map(|employee| (*employee).clone())
+                    .collect(),
+            );
+        }
+
+        Ok(levels)
+    }
+}
+
+pub struct Db {
+    categories: Table<i32, models::Category>,
+    users: Table<i32, models::User>,
+    employees: Table<i32, models::Employee>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_categories<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, Category, Walked>,
+    ) -> FieldResult<Vec<Category>> {
+        let ctx = executor.context();
+        let roots = vec![category(1, None, 1), category(2, None, 2)];
+        let categories = Category::eager_load_each(&roots, ctx, trail)?;
+        Ok(categories)
+    }
+
+    fn field_employees<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, Employee, Walked>,
+    ) -> FieldResult<Vec<Employee>> {
+        let ctx = executor.context();
+        let employees = vec![
+            employee(4, Some(3)),
+            employee(5, Some(3)),
+            employee(2, Some(1)),
+        ];
+        let employees = Employee::eager_load_each(&employees, ctx, trail)?;
+        Ok(employees)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Category {
+    category: models::Category,
+    #[has_one(root_model_field = user)]
+    owner: HasOne<User>,
+    #[option_has_one(root_model_field = category, tree)]
+    parent: OptionHasOne<Box<Category>>,
+    #[has_many(
+        root_model_field = category,
+        foreign_key_field = parent_id,
+        foreign_key_optional,
+        tree
+    )]
+    children: HasMany<Category>,
+}
+
+impl CategoryFields for Category {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.category.id)
+    }
+
+    fn field_owner(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, User, Walked>,
+    ) -> FieldResult<&User> {
+        Ok(self.owner.try_unwrap()?)
+    }
+
+    fn field_parent(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Category, Walked>,
+    ) -> FieldResult<Option<&Category>> {
+        Ok(self.parent.try_unwrap()?.as_deref())
+    }
+
+    fn field_children(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Category, Walked>,
+    ) -> FieldResult<&Vec<Category>> {
+        Ok(self.children.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Employee {
+    employee: models::Employee,
+    #[option_has_one(root_model_field = employee, tree_loader = ManagerChain)]
+    manager: OptionHasOne<Box<Employee>>,
+}
+
+impl EmployeeFields for Employee {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.employee.id)
+    }
+
+    fn field_manager(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Employee, Walked>,
+    ) -> FieldResult<Option<&Employee>> {
+        Ok(self.manager.try_unwrap()?.as_deref())
+    }
+}
+
+fn category(id: i32, parent_id: Option<i32>, owner_id: i32) -> models::Category {
+    models::Category {
+        id,
+        parent_id,
+        owner_id,
+    }
+}
+
+fn employee(id: i32, manager_id: Option<i32>) -> models::Employee {
+    models::Employee { id, manager_id }
+}
+
+// 1          2
+// ├── 3      └── 6
+// │   └── 5
+// │       └── 7
+// └── 4
+fn setup_db() -> Db {
+    let mut categories = Table::new("categories");
+    for category in [
+        category(1, None, 1),
+        category(2, None, 2),
+        category(3, Some(1), 1),
+        category(4, Some(1), 2),
+        category(5, Some(3), 1),
+        category(6, Some(2), 2),
+        category(7, Some(5), 1),
+    ] {
+        categories.insert(category.id, category);
+    }
+
+    let mut users = Table::new("users");
+    users.insert(1, models::User { id: 1 });
+    users.insert(2, models::User { id: 2 });
+
+    // 1 manages 2 and 3, 3 manages 4 and 5
+    let mut employees = Table::new("employees");
+    for employee in [
+        employee(1, None),
+        employee(2, Some(1)),
+        employee(3, Some(1)),
+        employee(4, Some(3)),
+        employee(5, Some(3)),
+    ] {
+        employees.insert(employee.id, employee);
+    }
+
+    Db {
+        categories,
+        users,
+        employees,
+    }
+}
+
+#[test]
+fn loading_children_one_level_at_a_time() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            categories {
+                id
+                owner { id }
+                children {
+                    id
+                    owner { id }
+                    children {
+                        id
+                        children { id }
+                    }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "categories": [
+                {
+                    "id": 1,
+                    "owner": { "id": 1 },
+                    "children": [
+                        {
+                            "id": 3,
+                            "owner": { "id": 1 },
+                            "children": [
+                                { "id": 5, "children": [{ "id": 7 }] },
+                            ],
+                        },
+                        { "id": 4, "owner": { "id": 2 }, "children": [] },
+                    ],
+                },
+                {
+                    "id": 2,
+                    "owner": { "id": 2 },
+                    "children": [
+                        { "id": 6, "owner": { "id": 2 }, "children": [] },
+                    ],
+                },
+            ]
+        }),
+        json,
+    );
+
+    // One read for each level below the roots
+    assert_eq!(3, ctx.db.categories.reads_count());
+    // The owners of the roots and of the first level of children
+    assert_eq!(2, ctx.db.users.reads_count());
+}
+
+#[test]
+fn levels_below_the_trail_are_not_loaded() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            categories {
+                id
+                children { id }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "categories": [
+                { "id": 1, "children": [{ "id": 3 }, { "id": 4 }] },
+                { "id": 2, "children": [{ "id": 6 }] },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.categories.reads_count());
+}
+
+#[test]
+fn loading_parents_inside_a_tree() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            categories {
+                id
+                children {
+                    id
+                    parent {
+                        id
+                        parent { id }
+                    }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "categories": [
+                {
+                    "id": 1,
+                    "children": [
+                        { "id": 3, "parent": { "id": 1, "parent": null } },
+                        { "id": 4, "parent": { "id": 1, "parent": null } },
+                    ],
+                },
+                {
+                    "id": 2,
+                    "children": [
+                        { "id": 6, "parent": { "id": 2, "parent": null } },
+                    ],
+                },
+            ]
+        }),
+        json,
+    );
+}
+
+#[test]
+fn loading_a_tree_with_a_tree_loader() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            employees {
+                id
+                manager {
+                    id
+                    manager {
+                        id
+                        manager { id }
+                    }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "employees": [
+                { "id": 4, "manager": { "id": 3, "manager": { "id": 1, "manager": null } } },
+                { "id": 5, "manager": { "id": 3, "manager": { "id": 1, "manager": null } } },
+                { "id": 2, "manager": { "id": 1, "manager": null } },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.employees.reads_count());
+}
+
+fn run_query(query: &str) -> (Value, Context) {
+    let ctx = Context { db: setup_db() };
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    (json, ctx)
+}
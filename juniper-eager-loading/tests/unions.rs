@@ -0,0 +1,408 @@
+#![allow(unused_variables, unused_imports, dead_code, unused_mut)]
+
+use assert_json_diff::assert_json_eq;
+use juniper::{EmptyMutation, Executor, FieldResult};
+use juniper_eager_loading::{in_memory::Table, prelude::*, EagerLoading, HasOne, SharedHasMany};
+use juniper_from_schema::graphql_schema;
+use serde_json::{json, Value};
+
+graphql_schema! {
+    schema {
+      query: Query
+    }
+
+    type Query {
+      search: [SearchResult!]! @juniper(ownership: "owned")
+      users: [User!]! @juniper(ownership: "owned")
+    }
+
+    union SearchResult = Post | Issue
+
+    type User {
+        id: Int!
+        bookmarks: [SearchResult!]! @juniper(ownership: "as_ref")
+    }
+
+    type Post {
+        id: Int!
+        author: Author!
+    }
+
+    type Issue {
+        id: Int!
+        number: Int!
+    }
+
+    type Author {
+        id: Int!
+    }
+}
+
+mod models {
+    use juniper_eager_loading::LoadFrom;
+
+    #[derive(Clone, Debug)]
+    pub enum SearchResult {
+        Post(Post),
+        Issue(Issue),
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct User {
+        pub id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Post {
+        pub id: i32,
+        pub user_id: i32,
+        pub author_id: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Issue {
+        pub id: i32,
+        pub user_id: i32,
+        pub number: i32,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Author {
+        pub id: i32,
+    }
+
+    impl LoadFrom<User> for SearchResult {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(users: &[User], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            let user_ids = users.iter().map(|user| user.id).collect::<Vec<_>>();
+
+            let mut posts = ctx
+                .db
+                .posts
+                .all_values()
+                .into_iter()
+                .filter(|post| user_ids.contains(&post.user_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            posts.sort_by_key(|post| post.id);
+
+            let mut issues = ctx
+                .db
+                .issues
+                .all_values()
+                .into_iter()
+                .filter(|issue| user_ids.contains(&issue.user_id))
+                .cloned()
+                .collect::<Vec<_>>();
+            issues.sort_by_key(|issue| issue.id);
+
+            Ok(posts
+                .into_iter()
+                .map(SearchResult::Post)
+                .chain(issues.into_iter().map(SearchResult::Issue))
+                .collect())
+        }
+    }
+
+    impl LoadFrom<i32> for Author {
+        type Error = Box<dyn std::error::Error>;
+        type Context = super::Context;
+
+        fn load(ids: &[i32], _: &(), ctx: &Self::Context) -> Result<Vec<Self>, Self::Error> {
+            Ok(ctx
+                .db
+                .authors
+                .all_values()
+                .into_iter()
+                .filter(|author| ids.contains(&author.id))
+                .cloned()
+                .collect())
+        }
+    }
+}
+
+pub struct Db {
+    search: Vec<models::SearchResult>,
+    users: Vec<models::User>,
+    posts: Table<i32, models::Post>,
+    issues: Table<i32, models::Issue>,
+    authors: Table<i32, models::Author>,
+}
+
+pub struct Context {
+    db: Db,
+}
+
+impl juniper::Context for Context {}
+
+pub struct Query;
+
+impl QueryFields for Query {
+    fn field_search<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, SearchResult, Walked>,
+    ) -> FieldResult<Vec<SearchResult>> {
+        let ctx = executor.context();
+        let results = SearchResult::eager_load_each(&ctx.db.search, ctx, trail)?;
+        Ok(results)
+    }
+
+    fn field_users<'a>(
+        &self,
+        executor: &Executor<'a, Context>,
+        trail: &QueryTrail<'a, User, Walked>,
+    ) -> FieldResult<Vec<User>> {
+        let ctx = executor.context();
+        let users = User::eager_load_each(&ctx.db.users, ctx, trail)?;
+        Ok(users)
+    }
+}
+
+// `SearchResult` is generated by `graphql_schema!` so the derive goes on a mirror of it.
+#[derive(EagerLoading)]
+#[eager_loading(
+    context = Context,
+    error = Box<dyn std::error::Error>,
+    remote = SearchResult,
+)]
+enum SearchResultDef {
+    Post(Post),
+    Issue(Issue),
+}
+
+impl SearchResult {
+    fn user_id(&self) -> i32 {
+        match self {
+            SearchResult::Post(post) => post.post.user_id,
+            SearchResult::Issue(issue) => issue.issue.user_id,
+        }
+    }
+}
+
+#[derive(EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct User {
+    user: models::User,
+    #[has_many(foreign_key_method = user_id)]
+    bookmarks: SharedHasMany<SearchResult>,
+}
+
+impl UserFields for User {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.user.id)
+    }
+
+    fn field_bookmarks(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, SearchResult, Walked>,
+    ) -> FieldResult<Vec<&SearchResult>> {
+        Ok(self.bookmarks.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Post {
+    post: models::Post,
+    #[has_one(default)]
+    author: HasOne<Author>,
+}
+
+impl PostFields for Post {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.post.id)
+    }
+
+    fn field_author(
+        &self,
+        _: &Executor<'_, Context>,
+        _: &QueryTrail<'_, Author, Walked>,
+    ) -> FieldResult<&Author> {
+        Ok(self.author.try_unwrap()?)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Issue {
+    issue: models::Issue,
+}
+
+impl IssueFields for Issue {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.issue.id)
+    }
+
+    fn field_number(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.issue.number)
+    }
+}
+
+#[derive(Clone, EagerLoading)]
+#[eager_loading(context = Context, error = Box<dyn std::error::Error>)]
+pub struct Author {
+    author: models::Author,
+}
+
+impl AuthorFields for Author {
+    fn field_id(&self, _: &Executor<'_, Context>) -> FieldResult<&i32> {
+        Ok(&self.author.id)
+    }
+}
+
+fn post(id: i32, user_id: i32, author_id: i32) -> models::Post {
+    models::Post {
+        id,
+        user_id,
+        author_id,
+    }
+}
+
+fn issue(id: i32, user_id: i32, number: i32) -> models::Issue {
+    models::Issue {
+        id,
+        user_id,
+        number,
+    }
+}
+
+fn setup_db() -> Db {
+    let mut posts = Table::new("posts");
+    posts.insert(1, post(1, 1, 1));
+    posts.insert(2, post(2, 2, 2));
+    posts.insert(3, post(3, 1, 2));
+
+    let mut issues = Table::new("issues");
+    issues.insert(1, issue(1, 2, 100));
+    issues.insert(2, issue(2, 1, 200));
+
+    let mut authors = Table::new("authors");
+    authors.insert(1, models::Author { id: 1 });
+    authors.insert(2, models::Author { id: 2 });
+
+    let search = vec![
+        models::SearchResult::Issue(issue(1, 2, 100)),
+        models::SearchResult::Post(post(2, 2, 2)),
+        models::SearchResult::Post(post(1, 1, 1)),
+        models::SearchResult::Issue(issue(2, 1, 200)),
+    ];
+
+    Db {
+        search,
+        users: vec![models::User { id: 1 }, models::User { id: 2 }],
+        posts,
+        issues,
+        authors,
+    }
+}
+
+#[test]
+fn loading_union_results_keeps_their_order() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            search {
+                ... on Post {
+                    id
+                    author { id }
+                }
+                ... on Issue {
+                    id
+                    number
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "search": [
+                { "id": 1, "number": 100 },
+                { "id": 2, "author": { "id": 2 } },
+                { "id": 1, "author": { "id": 1 } },
+                { "id": 2, "number": 200 },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.authors.reads_count());
+}
+
+#[test]
+fn loading_union_children() {
+    let (json, ctx) = run_query(
+        r#"
+        query Test {
+            users {
+                id
+                bookmarks {
+                    ... on Post {
+                        id
+                        author { id }
+                    }
+                    ... on Issue {
+                        id
+                        number
+                    }
+                }
+            }
+        }
+    "#,
+    );
+
+    assert_json_eq!(
+        json!({
+            "users": [
+                {
+                    "id": 1,
+                    "bookmarks": [
+                        { "id": 1, "author": { "id": 1 } },
+                        { "id": 3, "author": { "id": 2 } },
+                        { "id": 2, "number": 200 },
+                    ]
+                },
+                {
+                    "id": 2,
+                    "bookmarks": [
+                        { "id": 2, "author": { "id": 2 } },
+                        { "id": 1, "number": 100 },
+                    ]
+                },
+            ]
+        }),
+        json,
+    );
+
+    assert_eq!(1, ctx.db.posts.reads_count());
+    assert_eq!(1, ctx.db.issues.reads_count());
+    assert_eq!(1, ctx.db.authors.reads_count());
+}
+
+fn run_query(query: &str) -> (Value, Context) {
+    let ctx = Context { db: setup_db() };
+
+    let (result, errors) = juniper::execute(
+        query,
+        None,
+        &Schema::new(Query, EmptyMutation::new()),
+        &juniper::Variables::new(),
+        &ctx,
+    )
+    .unwrap();
+
+    if !errors.is_empty() {
+        panic!(
+            "GraphQL errors\n{}",
+            serde_json::to_string_pretty(&errors).unwrap()
+        );
+    }
+
+    let json = serde_json::from_str(&serde_json::to_string(&result).unwrap()).unwrap();
+    (json, ctx)
+}